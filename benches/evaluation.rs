@@ -0,0 +1,54 @@
+//! Compares evaluating an expression by binding a `meval::Expr` for every sample, as the plot
+//! used to do, with evaluating the compiled form cached on `Function`.
+//!
+//! Run with `cargo bench --bench evaluation`.
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use meval::Expr;
+use plotting_rs::evaluation::Function;
+
+const EXPRESSION: &str = "sin(x*a)*b + c*x^2 - d";
+const SAMPLES: usize = 500;
+const FRAMES: usize = 200;
+
+fn xs() -> impl Iterator<Item = f64> {
+    (0..SAMPLES).map(|i| -10.0 + 20.0 * i as f64 / (SAMPLES - 1) as f64)
+}
+
+fn time(label: &str, mut frame: impl FnMut() -> f64) -> Duration {
+    // warm up
+    black_box(frame());
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        black_box(frame());
+    }
+    let elapsed = start.elapsed() / FRAMES as u32;
+    println!("{:<28} {:>10.1?} per frame of {} points", label, elapsed, SAMPLES);
+    elapsed
+}
+
+fn main() {
+    let parsed: Expr = EXPRESSION.parse().unwrap();
+    let values = [1.5, 2.0, 0.1, 0.5];
+    let rebinding = time("meval clone + bind5", || {
+        xs().map(|x| {
+            let f = parsed.clone().bind5("x", "a", "b", "c", "d").unwrap();
+            f(x, values[0], values[1], values[2], values[3])
+        })
+        .sum()
+    });
+
+    let mut function = Function::new(EXPRESSION.to_owned(), "f".to_owned()).unwrap();
+    for (name, value) in ["a", "b", "c", "d"].iter().zip(values) {
+        function.assign_value_to_var(name.to_string(), value).unwrap();
+    }
+    let compiled = time("compiled Function::eval", || {
+        xs().map(|x| function.eval(black_box(x))).sum()
+    });
+
+    println!(
+        "speedup: {:.0}x",
+        rebinding.as_secs_f64() / compiled.as_secs_f64()
+    );
+}
@@ -10,18 +10,16 @@ fn main() -> Result<(), eframe::Error> {
         viewport: egui::ViewportBuilder::default(),
         ..Default::default()
     };
-    eframe::run_native("Plotting", options, Box::new(|cc| Box::<MyApp>::default()))
+    eframe::run_native("Plotting", options, Box::new(|_cc| Box::<MyApp>::default()))
 }
 
 struct MyApp {
-    name: String,
     a: u32,
 }
 
 impl Default for MyApp {
     fn default() -> Self {
         Self {
-            name: "Arthur".to_owned(),
             a: 42,
         }
     }
@@ -54,7 +52,7 @@ impl eframe::App for MyApp {
                     1000,
                 )));
                 plot_ui.line(
-                    Line::new(PlotPoints::from_ys_f32(&vec![1.0, 2.0, 3.0, 2.0, 1.5]))
+                    Line::new(PlotPoints::from_ys_f32(&[1.0, 2.0, 3.0, 2.0, 1.5]))
                         .color(Color32::RED)
                         .stroke(Stroke::new(2.0, Color32::RED))
                         .name("Explicit"),
@@ -2,7 +2,6 @@
 #![allow(rustdoc::missing_crate_level_docs)] // it's an example
 
 use eframe::egui;
-use egui::{Color32, Stroke};
 use egui_plot::{Legend, Line, Plot, PlotPoints};
 
 fn main() -> Result<(), eframe::Error> {
@@ -10,13 +9,14 @@ fn main() -> Result<(), eframe::Error> {
         viewport: egui::ViewportBuilder::default().with_inner_size([1000.0, 1000.0]),
         ..Default::default()
     };
-    eframe::run_native("Plotting", options, Box::new(|cc| Box::<MyApp>::default()))
+    eframe::run_native("Plotting", options, Box::new(|_cc| Box::<MyApp>::default()))
 }
 struct Element {
     name: String,
     //type_: Elements,
     a: f64,
 }
+#[allow(dead_code)]
 enum Elements {
     Function,
     Parameter,
@@ -16,7 +16,7 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "eframe template",
         native_options,
-        Box::new(|cc| Box::<PlottingApp>::default()),
+        Box::new(|_cc| Box::<PlottingApp>::default()),
     )
 }
 
@@ -35,7 +35,7 @@ fn main() {
             .start(
                 "the_canvas_id", // hardcode it
                 web_options,
-                Box::new(|cc| Box::<PlottingApp>::default()),
+                Box::new(|_cc| Box::<PlottingApp>::default()),
             )
             .await
             .expect("failed to start eframe");
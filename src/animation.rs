@@ -0,0 +1,112 @@
+//! Animation of the parameters, sweeping their value between their limits.
+use serde::{Deserialize, Serialize};
+
+/// What happens when an animated value reaches the upper limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum AnimationMode {
+    /// Starts again from the lower limit.
+    #[default]
+    Loop,
+    /// Goes back and forth between the limits.
+    Bounce,
+    /// Stops at the upper limit.
+    Once,
+}
+impl AnimationMode {
+    pub const ALL: [Self; 3] = [Self::Loop, Self::Bounce, Self::Once];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Loop => "loop",
+            Self::Bounce => "bounce",
+            Self::Once => "once",
+        }
+    }
+}
+
+/// The animation of a parameter, stopped until [`Animation::play`] is called.
+///
+/// Only the mode and the speed are saved, a restored animation is stopped.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Animation {
+    #[serde(skip)]
+    pub playing: bool,
+    pub mode: AnimationMode,
+    /// Change of the value per second, in absolute value.
+    pub speed: f64,
+    // going down to the lower limit, only in bounce mode
+    #[serde(skip)]
+    backwards: bool,
+}
+impl Default for Animation {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            mode: AnimationMode::default(),
+            speed: Self::DEFAULT_SPEED,
+            backwards: false,
+        }
+    }
+}
+impl Animation {
+    pub const DEFAULT_SPEED: f64 = 1.0;
+
+    pub fn new(mode: AnimationMode, speed: f64) -> Self {
+        Self {
+            mode,
+            speed,
+            ..Self::default()
+        }
+    }
+    pub fn play(&mut self) {
+        self.playing = true;
+    }
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+    pub fn toggle(&mut self) {
+        self.playing = !self.playing;
+    }
+    /// The value `dt` seconds after `value` while playing, staying within `(min, max)`.
+    ///
+    /// In [`AnimationMode::Once`] the animation stops at `max`, and starts again from `min` when
+    /// played from there.
+    pub fn advance(&mut self, value: f64, (min, max): (f64, f64), dt: f64) -> f64 {
+        if !self.playing {
+            return value;
+        }
+        let width = max - min;
+        if width.is_nan() || width <= 0.0 {
+            return value;
+        }
+        let step = self.speed.abs() * dt;
+        let value = value.clamp(min, max);
+        match self.mode {
+            AnimationMode::Loop => min + (value - min + step).rem_euclid(width),
+            AnimationMode::Bounce => {
+                // position along a back and forth sweep starting from `min`
+                let unfolded = if self.backwards {
+                    2.0 * width - (value - min)
+                } else {
+                    value - min
+                };
+                let unfolded = (unfolded + step).rem_euclid(2.0 * width);
+                self.backwards = unfolded > width;
+                if self.backwards {
+                    min + 2.0 * width - unfolded
+                } else {
+                    min + unfolded
+                }
+            }
+            AnimationMode::Once => {
+                let start = if value >= max { min } else { value };
+                let value = (start + step).min(max);
+                if value >= max {
+                    self.playing = false;
+                }
+                value
+            }
+        }
+    }
+}
@@ -0,0 +1,557 @@
+//! The command line of the app, plotting and evaluating expressions without a window.
+//!
+//! The expressions are read by the same engine as the functions of the app, and the images
+//! are rendered like [`PlottingApp::export_svg`] and [`PlottingApp::export_png`].
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::document::View;
+use crate::error::PlotError;
+use crate::evaluation::Function;
+use crate::parameters::Parameter;
+use crate::plotting::PlottingApp;
+
+pub const USAGE: &str = "\
+usage: plotting-rs                        open the app
+       plotting-rs plot <expression>... [--param <name>=<value>]... [--xlim <min>,<max>]
+                   [--ylim <min>,<max>] [-o <image>] [--size <width>x<height>]
+       plotting-rs eval <expression> --at <x>... [--param <name>=<value>]... [--format csv|json]
+       plotting-rs table <expression> --from <x> --to <x> --step <dx>
+                   [--param <name>=<value>]... [--format csv|json]
+       plotting-rs render <workspace> [-o <image>] [--size <width>x<height>]
+
+Images are written as SVG or PNG following the extension of <image>, as SVG on the standard
+output without -o. Plotted expressions are named f1, f2... and can call each other.
+--limit <name>=<min>,<max> gives the range of a parameter of --param, its value has to be in
+it. Without it the range is the one of the app, widened to the value.";
+
+/// Size of the images when `--size` is not given, in pixels.
+pub const DEFAULT_SIZE: (u32, u32) = (800, 600);
+
+/// Number of points sampled to fit the plot in y when `--ylim` is not given.
+const FIT_SAMPLES: usize = 200;
+
+/// Number of rows a table can have at most, a smaller step being refused.
+pub const MAX_ROWS: u64 = 1_000_000;
+
+/// The options taking a value, any other argument being positional.
+const OPTIONS: [&str; 12] = [
+    "--output", "--size", "--param", "--limit", "--xlim", "--ylim", "--at", "--from", "--to",
+    "--step", "--format", "--png",
+];
+
+/// How values are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+/// The range given to a parameter with `--limit`, `(name, (min, max))`.
+pub type ParameterLimits = (String, (f64, f64));
+
+/// Where an image goes and its size.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageOutput {
+    /// A `.svg` or `.png` file, `None` for SVG on the standard output.
+    pub path: Option<PathBuf>,
+    pub size: (u32, u32),
+}
+
+/// What the command line asks for, see [`USAGE`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Help,
+    /// Plots functions of `x`, the bounds in y fitting them unless given.
+    Plot {
+        expressions: Vec<String>,
+        parameters: Vec<(String, f64)>,
+        /// The ranges given to some of the parameters.
+        limits: Vec<ParameterLimits>,
+        xlim: (f64, f64),
+        ylim: Option<(f64, f64)>,
+        image: ImageOutput,
+    },
+    /// Prints the values of a function at some points.
+    Eval {
+        expression: String,
+        parameters: Vec<(String, f64)>,
+        limits: Vec<ParameterLimits>,
+        at: Vec<f64>,
+        format: TableFormat,
+    },
+    /// Prints the values of a function from `from` to `to` included.
+    Table {
+        expression: String,
+        parameters: Vec<(String, f64)>,
+        limits: Vec<ParameterLimits>,
+        from: f64,
+        to: f64,
+        step: f64,
+        format: TableFormat,
+    },
+    /// Renders a saved workspace as it is shown in the app.
+    Render {
+        workspace: PathBuf,
+        image: ImageOutput,
+    },
+}
+
+/// The arguments after the subcommand, split into positional ones and options.
+struct Arguments<'a> {
+    positional: Vec<&'a str>,
+    options: Vec<(&'a str, &'a str)>,
+}
+impl<'a> Arguments<'a> {
+    fn new(args: &'a [String]) -> Result<Self, PlotError> {
+        let (mut positional, mut options) = (vec![], vec![]);
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let name = match arg.as_str() {
+                "-o" => "--output",
+                name => name,
+            };
+            if OPTIONS.contains(&name) {
+                let value = args
+                    .next()
+                    .ok_or_else(|| usage_error(format!("{} needs a value", name)))?;
+                options.push((name, value.as_str()));
+            } else {
+                positional.push(arg.as_str());
+            }
+        }
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+    /// Fails on the options not in `allowed`.
+    fn allow(&self, allowed: &[&str]) -> Result<(), PlotError> {
+        match self
+            .options
+            .iter()
+            .find(|(name, _)| !allowed.contains(name))
+        {
+            Some((name, _)) => Err(usage_error(format!("{} is not an option here", name))),
+            None => Ok(()),
+        }
+    }
+    fn all<'s>(&'s self, name: &'s str) -> impl Iterator<Item = &'a str> + 's {
+        self.options
+            .iter()
+            .filter(move |(option, _)| *option == name)
+            .map(|(_, value)| *value)
+    }
+    /// The value of an option given at most once.
+    fn get(&self, name: &str) -> Result<Option<&'a str>, PlotError> {
+        let mut values = self.all(name);
+        let value = values.next();
+        if values.next().is_some() {
+            return Err(usage_error(format!("{} is given twice", name)));
+        }
+        Ok(value)
+    }
+    fn required(&self, name: &str) -> Result<&'a str, PlotError> {
+        self.get(name)?
+            .ok_or_else(|| usage_error(format!("{} is missing", name)))
+    }
+    /// The only positional argument, described by `what` in errors.
+    fn single(&self, what: &str) -> Result<&'a str, PlotError> {
+        match self.positional[..] {
+            [value] => Ok(value),
+            [] => Err(usage_error(format!("the {} is missing", what))),
+            _ => Err(usage_error(format!("only one {} is expected", what))),
+        }
+    }
+    fn parameters(&self) -> Result<Vec<(String, f64)>, PlotError> {
+        self.all("--param")
+            .map(|assignment| {
+                let (name, value) = assignment.split_once('=').ok_or_else(|| {
+                    usage_error(format!("{} is not written like a=2", assignment))
+                })?;
+                let value = number(value)?;
+                if !value.is_finite() {
+                    return Err(usage_error(format!("the value of {} is not finite", name)));
+                }
+                Ok((name.trim().to_owned(), value))
+            })
+            .collect()
+    }
+    /// The ranges of `--limit`, each one of a parameter of `parameters` whose value is in it.
+    fn limits(&self, parameters: &[(String, f64)]) -> Result<Vec<ParameterLimits>, PlotError> {
+        self.all("--limit")
+            .map(|assignment| {
+                let (name, limits) = assignment.split_once('=').ok_or_else(|| {
+                    usage_error(format!("{} is not written like a=-5,5", assignment))
+                })?;
+                let (name, (min, max)) = (name.trim(), bounds(limits)?);
+                let Some((_, value)) = parameters.iter().find(|(given, _)| given == name) else {
+                    return Err(usage_error(format!("--limit {} has no --param", name)));
+                };
+                if !(min..=max).contains(value) {
+                    return Err(usage_error(format!(
+                        "the value of {} is not within {}",
+                        name, limits
+                    )));
+                }
+                Ok((name.to_owned(), (min, max)))
+            })
+            .collect()
+    }
+    fn format(&self) -> Result<TableFormat, PlotError> {
+        match self.get("--format")? {
+            None | Some("csv") => Ok(TableFormat::Csv),
+            Some("json") => Ok(TableFormat::Json),
+            Some(format) => Err(usage_error(format!("unknown format {}", format))),
+        }
+    }
+    fn image(&self, output: &str) -> Result<ImageOutput, PlotError> {
+        let path = self.get(output)?.map(PathBuf::from);
+        if let Some(path) = &path {
+            image_format(path)?;
+        }
+        let size = match self.get("--size")? {
+            None => DEFAULT_SIZE,
+            Some(size) => size
+                .split_once('x')
+                .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)))
+                .ok_or_else(|| usage_error(format!("{} is not written like 800x600", size)))?,
+        };
+        Ok(ImageOutput { path, size })
+    }
+}
+
+impl Command {
+    /// Reads the arguments of the command line, without the name of the program.
+    pub fn parse(args: &[String]) -> Result<Self, PlotError> {
+        let Some((command, rest)) = args.split_first() else {
+            return Ok(Self::Help);
+        };
+        let arguments = Arguments::new(rest)?;
+        let command = match command.as_str() {
+            "help" | "-h" | "--help" => Self::Help,
+            "plot" => {
+                arguments.allow(&[
+                    "--param", "--limit", "--xlim", "--ylim", "--output", "--size",
+                ])?;
+                if arguments.positional.is_empty() {
+                    return Err(usage_error("the expression to plot is missing".to_owned()));
+                }
+                let parameters = arguments.parameters()?;
+                let limits = arguments.limits(&parameters)?;
+                Self::Plot {
+                    expressions: arguments.positional.iter().map(|e| e.to_string()).collect(),
+                    parameters,
+                    limits,
+                    xlim: arguments
+                        .get("--xlim")?
+                        .map_or(Ok(View::default().xlim), bounds)?,
+                    ylim: arguments.get("--ylim")?.map(bounds).transpose()?,
+                    image: arguments.image("--output")?,
+                }
+            }
+            "eval" => {
+                arguments.allow(&["--param", "--limit", "--at", "--format"])?;
+                let at = arguments
+                    .all("--at")
+                    .map(number)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if at.is_empty() {
+                    return Err(usage_error("--at is missing".to_owned()));
+                }
+                let parameters = arguments.parameters()?;
+                let limits = arguments.limits(&parameters)?;
+                Self::Eval {
+                    expression: arguments.single("expression")?.to_owned(),
+                    parameters,
+                    limits,
+                    at,
+                    format: arguments.format()?,
+                }
+            }
+            "table" => {
+                arguments.allow(&["--param", "--limit", "--from", "--to", "--step", "--format"])?;
+                let (from, to) = (
+                    number(arguments.required("--from")?)?,
+                    number(arguments.required("--to")?)?,
+                );
+                let step = number(arguments.required("--step")?)?;
+                if !from.is_finite() || !to.is_finite() {
+                    return Err(usage_error("--from and --to have to be finite".to_owned()));
+                }
+                if step.is_nan() || step <= 0.0 || from > to {
+                    return Err(usage_error(
+                        "the step has to be positive and --from below --to".to_owned(),
+                    ));
+                }
+                if row_count(from, to, step) > MAX_ROWS as f64 {
+                    return Err(usage_error(format!(
+                        "the table would have more than {} rows, the step is too small",
+                        MAX_ROWS
+                    )));
+                }
+                let parameters = arguments.parameters()?;
+                let limits = arguments.limits(&parameters)?;
+                Self::Table {
+                    expression: arguments.single("expression")?.to_owned(),
+                    parameters,
+                    limits,
+                    from,
+                    to,
+                    step,
+                    format: arguments.format()?,
+                }
+            }
+            "render" => {
+                arguments.allow(&["--output", "--size"])?;
+                Self::Render {
+                    workspace: arguments.single("workspace")?.into(),
+                    image: arguments.image("--output")?,
+                }
+            }
+            // `plotting-rs --png <output> <workspace>`, from before the subcommands
+            _ => {
+                let arguments = Arguments::new(args)?;
+                arguments.allow(&["--png", "--size"])?;
+                if arguments.get("--png")?.is_none() {
+                    return Err(usage_error(format!("unknown command {}", command)));
+                }
+                Self::Render {
+                    workspace: arguments.single("workspace")?.into(),
+                    image: arguments.image("--png")?,
+                }
+            }
+        };
+        Ok(command)
+    }
+    /// Runs the command, printing to `out` what goes to the standard output.
+    pub fn run(&self, out: &mut impl Write) -> Result<(), PlotError> {
+        match self {
+            Self::Help => writeln!(out, "{}", USAGE).map_err(output_error),
+            Self::Plot {
+                expressions,
+                parameters,
+                limits,
+                xlim,
+                ylim,
+                image,
+            } => {
+                let mut app = PlottingApp::empty();
+                for (i, expression) in expressions.iter().enumerate() {
+                    let name = format!("f{}", i + 1);
+                    let definitions = app.definitions();
+                    let function =
+                        Function::with_definitions(expression.to_owned(), name, &definitions)?;
+                    app.add_function(function);
+                }
+                set_parameters(&mut app, parameters, limits)?;
+                let ylim = ylim.unwrap_or_else(|| fit(app.functions(), *xlim));
+                app.set_view_bounds(*xlim, ylim);
+                write_image(&app, image, out)
+            }
+            Self::Eval {
+                expression,
+                parameters,
+                limits,
+                at,
+                format,
+            } => {
+                let function = function(expression, parameters, limits)?;
+                let values: Vec<f64> = at.iter().map(|x| function.eval(*x)).collect();
+                // an array even for a single --at, the output does not change shape
+                let text = match format {
+                    TableFormat::Csv => values.iter().map(|y| format!("{}\n", y)).collect(),
+                    TableFormat::Json => json(&values)? + "\n",
+                };
+                out.write_all(text.as_bytes()).map_err(output_error)
+            }
+            Self::Table {
+                expression,
+                parameters,
+                limits,
+                from,
+                to,
+                step,
+                format,
+            } => {
+                let function = function(expression, parameters, limits)?;
+                let rows: Vec<Row> = table_points(*from, *to, *step)
+                    .map(|x| Row {
+                        x,
+                        y: function.eval(x),
+                    })
+                    .collect();
+                let text = match format {
+                    TableFormat::Csv => std::iter::once("x,y\n".to_owned())
+                        .chain(rows.iter().map(|row| format!("{},{}\n", row.x, row.y)))
+                        .collect(),
+                    TableFormat::Json => json(&rows)? + "\n",
+                };
+                out.write_all(text.as_bytes()).map_err(output_error)
+            }
+            Self::Render { workspace, image } => {
+                let app = PlottingApp::open_file(workspace)?;
+                write_image(&app, image, out)
+            }
+        }
+    }
+}
+
+/// A row of [`Command::Table`].
+#[derive(Serialize)]
+struct Row {
+    x: f64,
+    y: f64,
+}
+
+/// The points of a table, multiples of the step from `from`, the last one being `to` itself
+/// when it is within rounding errors of it.
+fn table_points(from: f64, to: f64, step: f64) -> impl Iterator<Item = f64> {
+    let count = row_count(from, to, step) as u64;
+    (0..count).map(move |k| (from + k as f64 * step).min(to))
+}
+
+/// The number of points of a table, see [`table_points`].
+fn row_count(from: f64, to: f64, step: f64) -> f64 {
+    ((to - from) / step + 1e-9).floor() + 1.0
+}
+
+fn function(
+    expression: &str,
+    parameters: &[(String, f64)],
+    limits: &[ParameterLimits],
+) -> Result<Function, PlotError> {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new(expression.to_owned(), "f".to_owned())?);
+    set_parameters(&mut app, parameters, limits)?;
+    Ok(app.functions()[0].clone())
+}
+
+/// Gives its value to every parameter of the functions of `app`, each one having to be in
+/// `parameters`, with its range in `limits` if it is there.
+fn set_parameters(
+    app: &mut PlottingApp,
+    parameters: &[(String, f64)],
+    limits: &[ParameterLimits],
+) -> Result<(), PlotError> {
+    let used = app.functions().iter().flat_map(|f| f.vars_names().to_vec());
+    for name in used.collect::<Vec<_>>() {
+        if !parameters.iter().any(|(given, _)| *given == name) {
+            return Err(PlotError::UnboundVariable(name));
+        }
+    }
+    for (name, value) in parameters {
+        let limits = match limits.iter().find(|(limited, _)| limited == name) {
+            Some((_, limits)) => *limits,
+            // the value is not clamped to the range of the sliders
+            None => {
+                let (min, max) = Parameter::DEFAULT_RANGE;
+                (min.min(*value), max.max(*value))
+            }
+        };
+        // the range goes first, the value is clamped to it
+        app.set_parameter_limits(name, limits);
+        app.set_parameter(name, *value);
+    }
+    Ok(())
+}
+
+/// Bounds in y showing the values of `functions` over `xlim`, with a margin.
+fn fit(functions: &[Function], (min, max): (f64, f64)) -> (f64, f64) {
+    let values = functions.iter().flat_map(|function| {
+        (0..=FIT_SAMPLES)
+            .map(move |k| function.eval(min + (max - min) * k as f64 / FIT_SAMPLES as f64))
+    });
+    let (low, high) = values
+        .filter(|y| y.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(low, high), y| {
+            (low.min(y), high.max(y))
+        });
+    if low > high {
+        // nothing to show
+        return View::default().ylim;
+    }
+    let margin = if high > low { (high - low) * 0.05 } else { 1.0 };
+    (low - margin, high + margin)
+}
+
+fn write_image(
+    app: &PlottingApp,
+    image: &ImageOutput,
+    out: &mut impl Write,
+) -> Result<(), PlotError> {
+    // an element that does not parse would be missing from the image
+    if let Some((name, error)) = app.first_error() {
+        return Err(PlotError::Document(format!("{}: {}", name, error)));
+    }
+    let (width, height) = image.size;
+    let Some(path) = &image.path else {
+        return out
+            .write_all(app.export_svg(width, height).as_bytes())
+            .map_err(output_error);
+    };
+    let bytes = match image_format(path)? {
+        ImageFormat::Svg => app.export_svg(width, height).into_bytes(),
+        ImageFormat::Png => app.export_png(width, height)?,
+    };
+    std::fs::write(path, bytes)
+        .map_err(|error| PlotError::Export(format!("{}: {}", path.display(), error)))
+}
+
+enum ImageFormat {
+    Svg,
+    Png,
+}
+
+fn image_format(path: &Path) -> Result<ImageFormat, PlotError> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) if extension.eq_ignore_ascii_case("svg") => Ok(ImageFormat::Svg),
+        Some(extension) if extension.eq_ignore_ascii_case("png") => Ok(ImageFormat::Png),
+        _ => Err(usage_error(format!(
+            "{} is not a .svg or .png file",
+            path.display()
+        ))),
+    }
+}
+
+fn number(text: &str) -> Result<f64, PlotError> {
+    text.trim()
+        .parse()
+        .map_err(|_| usage_error(format!("{} is not a number", text)))
+}
+
+/// Bounds written like `-5,5`.
+fn bounds(text: &str) -> Result<(f64, f64), PlotError> {
+    let (min, max) = text
+        .split_once(',')
+        .ok_or_else(|| usage_error(format!("{} is not written like -5,5", text)))?;
+    let (min, max) = (number(min)?, number(max)?);
+    if !min.is_finite() || !max.is_finite() {
+        return Err(usage_error(format!(
+            "the bounds of {} are not finite",
+            text
+        )));
+    }
+    if min >= max {
+        return Err(usage_error(format!(
+            "{} is empty, the first bound has to be below the second",
+            text
+        )));
+    }
+    Ok((min, max))
+}
+
+fn json(value: &impl Serialize) -> Result<String, PlotError> {
+    serde_json::to_string(value).map_err(|error| PlotError::Export(error.to_string()))
+}
+
+fn usage_error(message: String) -> PlotError {
+    PlotError::Usage(message)
+}
+
+fn output_error(error: std::io::Error) -> PlotError {
+    PlotError::Export(error.to_string())
+}
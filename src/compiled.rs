@@ -0,0 +1,386 @@
+use std::f64::consts;
+
+use crate::error::PlotError;
+use crate::parser::{Expression, Operator, UnaryOperator};
+
+/// Names that are constants rather than parameters.
+pub const CONSTANTS: [(&str, f64); 2] = [("pi", consts::PI), ("e", consts::E)];
+
+fn apply(op: Operator, left: f64, right: f64) -> f64 {
+    match op {
+        Operator::Add => left + right,
+        Operator::Subtract => left - right,
+        Operator::Multiply => left * right,
+        Operator::Divide => left / right,
+        Operator::Remainder => left % right,
+        Operator::Pow => left.powf(right),
+        _ if left.is_nan() || right.is_nan() => f64::NAN,
+        Operator::Less => f64::from(left < right),
+        Operator::LessOrEqual => f64::from(left <= right),
+        Operator::Greater => f64::from(left > right),
+        Operator::GreaterOrEqual => f64::from(left >= right),
+        Operator::Equal => f64::from(left == right),
+        Operator::NotEqual => f64::from(left != right),
+        Operator::And => f64::from(left != 0.0 && right != 0.0),
+        Operator::Or => f64::from(left != 0.0 || right != 0.0),
+    }
+}
+
+/// Whether a condition holds, `None` when it is undefined.
+fn truth(value: f64) -> Option<bool> {
+    if value.is_nan() {
+        None
+    } else {
+        Some(value != 0.0)
+    }
+}
+
+fn not(value: f64) -> f64 {
+    truth(value).map_or(f64::NAN, |holds| f64::from(!holds))
+}
+
+/// Mixes the choice made by a conditional into the hash of [`CompiledExpression::branch`].
+fn mix(hash: &mut u64, choice: u64) {
+    *hash = hash.wrapping_mul(31).wrapping_add(choice + 1);
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Constant(f64),
+    // the free variable, `x` for a function
+    X,
+    // the second free variable, `y` for an implicit curve
+    Y,
+    // index into the parameter values
+    Parameter(usize),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Call1(fn(f64) -> f64, Box<Node>),
+    Call2(fn(f64, f64) -> f64, Box<Node>, Box<Node>),
+    // variadic functions such as `max`, evaluated by folding the arguments
+    Fold(fn(f64, f64) -> f64, f64, Vec<Node>),
+    // `if(condition, then, else)`, only the branch taken is evaluated
+    If(Box<Node>, Box<Node>, Box<Node>),
+    Piecewise(Vec<(Node, Node)>, Option<Box<Node>>),
+}
+impl Node {
+    fn eval(&self, point: [f64; 2], parameters: &[f64]) -> f64 {
+        match self {
+            Self::Constant(value) => *value,
+            Self::X => point[0],
+            Self::Y => point[1],
+            Self::Parameter(i) => parameters[*i],
+            Self::Negate(node) => -node.eval(point, parameters),
+            Self::Binary(op, left, right) => apply(
+                *op,
+                left.eval(point, parameters),
+                right.eval(point, parameters),
+            ),
+            Self::Call1(f, arg) => f(arg.eval(point, parameters)),
+            Self::Call2(f, arg1, arg2) => {
+                f(arg1.eval(point, parameters), arg2.eval(point, parameters))
+            }
+            Self::Fold(f, init, args) => args
+                .iter()
+                .fold(*init, |acc, arg| f(acc, arg.eval(point, parameters))),
+            Self::If(condition, then, otherwise) => {
+                match truth(condition.eval(point, parameters)) {
+                    Some(true) => then.eval(point, parameters),
+                    Some(false) => otherwise.eval(point, parameters),
+                    None => f64::NAN,
+                }
+            }
+            Self::Piecewise(branches, otherwise) => {
+                for (condition, value) in branches {
+                    match truth(condition.eval(point, parameters)) {
+                        Some(true) => return value.eval(point, parameters),
+                        Some(false) => {}
+                        None => return f64::NAN,
+                    }
+                }
+                otherwise
+                    .as_ref()
+                    .map_or(f64::NAN, |otherwise| otherwise.eval(point, parameters))
+            }
+        }
+    }
+    /// Mixes into `hash` the branches taken by the conditionals when evaluating at `point`.
+    fn branch(&self, point: [f64; 2], parameters: &[f64], hash: &mut u64) {
+        match self {
+            Self::Constant(_) | Self::X | Self::Y | Self::Parameter(_) => {}
+            Self::Negate(node) | Self::Call1(_, node) => node.branch(point, parameters, hash),
+            Self::Binary(_, left, right) | Self::Call2(_, left, right) => {
+                left.branch(point, parameters, hash);
+                right.branch(point, parameters, hash);
+            }
+            Self::Fold(_, _, args) => args
+                .iter()
+                .for_each(|arg| arg.branch(point, parameters, hash)),
+            Self::If(condition, then, otherwise) => {
+                condition.branch(point, parameters, hash);
+                match truth(condition.eval(point, parameters)) {
+                    Some(true) => {
+                        mix(hash, 0);
+                        then.branch(point, parameters, hash);
+                    }
+                    Some(false) => {
+                        mix(hash, 1);
+                        otherwise.branch(point, parameters, hash);
+                    }
+                    None => mix(hash, 2),
+                }
+            }
+            Self::Piecewise(branches, otherwise) => {
+                for (i, (condition, value)) in branches.iter().enumerate() {
+                    condition.branch(point, parameters, hash);
+                    match truth(condition.eval(point, parameters)) {
+                        Some(true) => {
+                            mix(hash, i as u64);
+                            return value.branch(point, parameters, hash);
+                        }
+                        Some(false) => {}
+                        None => return mix(hash, u64::MAX - 1),
+                    }
+                }
+                mix(hash, branches.len() as u64);
+                if let Some(otherwise) = otherwise {
+                    otherwise.branch(point, parameters, hash);
+                }
+            }
+        }
+    }
+    fn has_conditionals(&self) -> bool {
+        match self {
+            Self::Constant(_) | Self::X | Self::Y | Self::Parameter(_) => false,
+            Self::Negate(node) | Self::Call1(_, node) => node.has_conditionals(),
+            Self::Binary(_, left, right) | Self::Call2(_, left, right) => {
+                left.has_conditionals() || right.has_conditionals()
+            }
+            Self::Fold(_, _, args) => args.iter().any(Self::has_conditionals),
+            Self::If(..) | Self::Piecewise(..) => true,
+        }
+    }
+    fn as_constant(&self) -> Option<f64> {
+        match self {
+            Self::Constant(value) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Evaluates the node right away when it does not depend on the variables nor on the parameters.
+    fn folded(self) -> Self {
+        let is_constant = match &self {
+            Self::Negate(node) | Self::Call1(_, node) => node.as_constant().is_some(),
+            Self::Binary(_, left, right) | Self::Call2(_, left, right) => {
+                left.as_constant().is_some() && right.as_constant().is_some()
+            }
+            Self::Fold(_, _, args) => args.iter().all(|arg| arg.as_constant().is_some()),
+            Self::If(condition, then, otherwise) => [condition, then, otherwise]
+                .iter()
+                .all(|node| node.as_constant().is_some()),
+            Self::Piecewise(branches, otherwise) => {
+                branches.iter().all(|(condition, value)| {
+                    condition.as_constant().is_some() && value.as_constant().is_some()
+                }) && otherwise
+                    .as_ref()
+                    .is_none_or(|otherwise| otherwise.as_constant().is_some())
+            }
+            Self::Constant(_) | Self::X | Self::Y | Self::Parameter(_) => false,
+        };
+        if is_constant {
+            Self::Constant(self.eval([0.0; 2], &[]))
+        } else {
+            self
+        }
+    }
+}
+
+/// Whether `name` is one of the functions every expression can call, like `sin` or `max`.
+pub fn is_builtin_function(name: &str) -> bool {
+    matches!(name, "atan2" | "max" | "min" | "if") || function1(name).is_some()
+}
+
+fn function1(name: &str) -> Option<fn(f64) -> f64> {
+    let f: fn(f64) -> f64 = match name {
+        "sqrt" => f64::sqrt,
+        "exp" => f64::exp,
+        "ln" => f64::ln,
+        "abs" => f64::abs,
+        "sin" => f64::sin,
+        "cos" => f64::cos,
+        "tan" => f64::tan,
+        "asin" => f64::asin,
+        "acos" => f64::acos,
+        "atan" => f64::atan,
+        "sinh" => f64::sinh,
+        "cosh" => f64::cosh,
+        "tanh" => f64::tanh,
+        "asinh" => f64::asinh,
+        "acosh" => f64::acosh,
+        "atanh" => f64::atanh,
+        "floor" => f64::floor,
+        "ceil" => f64::ceil,
+        "round" => f64::round,
+        "signum" => f64::signum,
+        _ => return None,
+    };
+    Some(f)
+}
+
+/// An expression turned into a tree that evaluates without allocating.
+///
+/// Variables are resolved to `x`, to an index into the parameter values or to a constant when
+/// compiling, so it only has to be rebuilt when the expression or its parameters change.
+#[derive(Debug, Clone)]
+pub struct CompiledExpression {
+    root: Node,
+    has_conditionals: bool,
+}
+impl CompiledExpression {
+    /// Compiles a parsed expression of `x`.
+    ///
+    /// `parameters` gives the index of each parameter in the values passed to
+    /// [`CompiledExpression::eval`].
+    pub fn new(expression: &Expression, parameters: &[String]) -> Result<Self, PlotError> {
+        Self::with_variable(expression, "x", parameters)
+    }
+    /// Compiles a parsed expression whose free variable is `variable`, like `t` for a curve.
+    pub fn with_variable(
+        expression: &Expression,
+        variable: &str,
+        parameters: &[String],
+    ) -> Result<Self, PlotError> {
+        Self::with_variables(expression, &[variable], parameters)
+    }
+    /// Compiles a parsed expression of one or two free variables, like `x` and `y` for an
+    /// implicit curve, evaluated with [`CompiledExpression::eval_xy`].
+    pub fn with_variables(
+        expression: &Expression,
+        variables: &[&str],
+        parameters: &[String],
+    ) -> Result<Self, PlotError> {
+        let root = Self::node(expression, variables, parameters)?;
+        Ok(Self {
+            has_conditionals: root.has_conditionals(),
+            root,
+        })
+    }
+    fn node(
+        expression: &Expression,
+        variables: &[&str],
+        parameters: &[String],
+    ) -> Result<Node, PlotError> {
+        let node = match expression {
+            Expression::Number(value) => Node::Constant(*value),
+            Expression::Variable(name) if variables.first().is_some_and(|&x| name == x) => Node::X,
+            Expression::Variable(name) if variables.get(1).is_some_and(|&y| name == y) => Node::Y,
+            Expression::Variable(name) => {
+                if let Some(i) = parameters.iter().position(|p| p == name) {
+                    Node::Parameter(i)
+                } else if let Some((_, value)) = CONSTANTS.iter().find(|(c, _)| c == name) {
+                    Node::Constant(*value)
+                } else {
+                    return Err(PlotError::UnboundVariable(name.to_owned()));
+                }
+            }
+            Expression::Paren(inner) | Expression::Unary(UnaryOperator::Plus, inner) => {
+                Self::node(inner, variables, parameters)?
+            }
+            Expression::Unary(UnaryOperator::Minus, operand) => {
+                Node::Negate(Box::new(Self::node(operand, variables, parameters)?))
+            }
+            Expression::Unary(UnaryOperator::Not, operand) => {
+                Node::Call1(not, Box::new(Self::node(operand, variables, parameters)?))
+            }
+            Expression::Binary(op, left, right) => Node::Binary(
+                *op,
+                Box::new(Self::node(left, variables, parameters)?),
+                Box::new(Self::node(right, variables, parameters)?),
+            ),
+            Expression::Call(name, args) => {
+                let mut args = args
+                    .iter()
+                    .map(|arg| Self::node(arg, variables, parameters))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Self::call(name, &mut args)?
+            }
+            Expression::Piecewise(branches, otherwise) => {
+                let node = |expression| Self::node(expression, variables, parameters);
+                Node::Piecewise(
+                    branches
+                        .iter()
+                        .map(|(condition, value)| Ok((node(condition)?, node(value)?)))
+                        .collect::<Result<_, PlotError>>()?,
+                    match otherwise {
+                        Some(otherwise) => Some(Box::new(node(otherwise)?)),
+                        None => None,
+                    },
+                )
+            }
+        };
+        Ok(node.folded())
+    }
+    fn call(name: &str, args: &mut Vec<Node>) -> Result<Node, PlotError> {
+        let wrong_arguments = |expected: &str| PlotError::Parse {
+            message: format!(
+                "`{}` expects {} argument(s), found {}",
+                name,
+                expected,
+                args.len()
+            ),
+            position: None,
+        };
+        match name {
+            "atan2" if args.len() == 2 => {
+                let arg2 = args.pop().expect("two arguments");
+                let arg1 = args.pop().expect("two arguments");
+                Ok(Node::Call2(f64::atan2, Box::new(arg1), Box::new(arg2)))
+            }
+            "atan2" => Err(wrong_arguments("2")),
+            "if" if args.len() == 3 => {
+                let otherwise = args.pop().expect("three arguments");
+                let then = args.pop().expect("three arguments");
+                let condition = args.pop().expect("three arguments");
+                Ok(Node::If(
+                    Box::new(condition),
+                    Box::new(then),
+                    Box::new(otherwise),
+                ))
+            }
+            "if" => Err(wrong_arguments("3")),
+            "max" | "min" if args.is_empty() => Err(wrong_arguments("at least 1")),
+            "max" => Ok(Node::Fold(
+                f64::max,
+                f64::NEG_INFINITY,
+                std::mem::take(args),
+            )),
+            "min" => Ok(Node::Fold(f64::min, f64::INFINITY, std::mem::take(args))),
+            _ => match function1(name) {
+                Some(f) if args.len() == 1 => {
+                    Ok(Node::Call1(f, Box::new(args.pop().expect("one argument"))))
+                }
+                Some(_) => Err(wrong_arguments("1")),
+                None => Err(PlotError::UnknownFunction(name.to_owned())),
+            },
+        }
+    }
+    /// Evaluates the expression at `x`, the value of its free variable, `parameters` are in the
+    /// order given when compiling.
+    pub fn eval(&self, x: f64, parameters: &[f64]) -> f64 {
+        self.root.eval([x, f64::NAN], parameters)
+    }
+    /// Evaluates an expression of two free variables at `(x, y)`.
+    pub fn eval_xy(&self, x: f64, y: f64, parameters: &[f64]) -> f64 {
+        self.root.eval([x, y], parameters)
+    }
+    /// Identifies the branches of the conditionals taken at `x`, the expression is continuous
+    /// between two points with the same value as far as its conditionals are concerned.
+    ///
+    /// Always 0 for an expression without `if` nor piecewise definition.
+    pub fn branch(&self, x: f64, parameters: &[f64]) -> u64 {
+        let mut hash = 0;
+        if self.has_conditionals {
+            self.root.branch([x, f64::NAN], parameters, &mut hash);
+        }
+        hash
+    }
+}
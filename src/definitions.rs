@@ -0,0 +1,116 @@
+//! User-defined functions, so a function named `f1` can be called as `f1(x - 1)` by the
+//! expressions of other elements.
+use std::collections::HashMap;
+
+use crate::compiled::is_builtin_function;
+use crate::error::PlotError;
+use crate::evaluation::Function;
+use crate::parser::Expression;
+
+/// The functions of `x` callable by name, resolved when an expression is compiled.
+///
+/// A call like `f1(x - 1)` is replaced by the expression of `f1` with `x - 1` in place of `x`,
+/// so the parameters of `f1` become parameters of the caller and derivatives go through it.
+#[derive(Debug, Clone, Default)]
+pub struct Definitions {
+    functions: HashMap<String, Expression>,
+}
+impl Definitions {
+    /// The functions whose name is an identifier, like `f1` but not `sin(x)`, and whose
+    /// expression is valid. Built-in functions like `sin` cannot be redefined.
+    pub fn from_functions(functions: &[Function]) -> Self {
+        let mut definitions = Self::default();
+        for function in functions {
+            if let (Ok(Expression::Variable(name)), Ok(body)) = (
+                Expression::parse(&function.name),
+                Expression::parse(&function.expression),
+            ) {
+                definitions.define(name, body);
+            }
+        }
+        definitions
+    }
+    /// Makes `name` callable, `body` being an expression of `x`. Ignored for built-in functions.
+    pub fn define(&mut self, name: String, body: Expression) {
+        if !is_builtin_function(&name) {
+            self.functions.insert(name, body);
+        }
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+    /// Replaces every call to a user-defined function in `expression` by its definition.
+    ///
+    /// Fails with [`PlotError::CyclicDefinition`] when a function ends up calling itself.
+    pub fn expand(&self, expression: &Expression) -> Result<Expression, PlotError> {
+        self.expand_within(expression, &mut vec![])
+    }
+    /// `callers` are the functions being expanded, outermost first.
+    fn expand_within(
+        &self,
+        expression: &Expression,
+        callers: &mut Vec<String>,
+    ) -> Result<Expression, PlotError> {
+        let mut expand = |expression: &Expression| self.expand_within(expression, callers);
+        let expanded = match expression {
+            Expression::Number(_) | Expression::Variable(_) => expression.clone(),
+            Expression::Paren(inner) => Expression::Paren(Box::new(expand(inner)?)),
+            Expression::Unary(op, operand) => Expression::Unary(*op, Box::new(expand(operand)?)),
+            Expression::Binary(op, left, right) => {
+                Expression::Binary(*op, Box::new(expand(left)?), Box::new(expand(right)?))
+            }
+            Expression::Piecewise(branches, otherwise) => Expression::Piecewise(
+                branches
+                    .iter()
+                    .map(|(condition, value)| Ok((expand(condition)?, expand(value)?)))
+                    .collect::<Result<_, PlotError>>()?,
+                match otherwise {
+                    Some(otherwise) => Some(Box::new(expand(otherwise)?)),
+                    None => None,
+                },
+            ),
+            Expression::Call(name, args) => {
+                let args = args.iter().map(expand).collect::<Result<Vec<_>, _>>()?;
+                let Some(body) = self.functions.get(name) else {
+                    return Ok(Expression::Call(name.to_owned(), args));
+                };
+                if let Some(start) = callers.iter().position(|caller| caller == name) {
+                    let mut cycle = callers[start..].to_vec();
+                    cycle.push(name.to_owned());
+                    return Err(PlotError::CyclicDefinition(cycle));
+                }
+                let [arg] = args.as_slice() else {
+                    return Err(PlotError::Parse {
+                        message: format!("`{}` expects 1 argument(s), found {}", name, args.len()),
+                        position: None,
+                    });
+                };
+                callers.push(name.to_owned());
+                let body = self.expand_within(body, callers);
+                callers.pop();
+                body?.substitute("x", arg)
+            }
+        };
+        Ok(expanded)
+    }
+}
+
+/// Splits a definition like `f1(x) = sin(a*x)` into the name and the expression.
+///
+/// Returns `None` when `text` is not a definition, the name not being an identifier applied
+/// to `x`, or being a built-in function.
+pub fn parse_definition(text: &str) -> Option<(String, &str)> {
+    let (head, body) = text.split_once('=')?;
+    // `f(x) == 1` or `f(x) <= 1` is a comparison
+    if body.starts_with('=') {
+        return None;
+    }
+    match Expression::parse(head) {
+        Ok(Expression::Call(name, args))
+            if args == [Expression::Variable("x".to_owned())] && !is_builtin_function(&name) =>
+        {
+            Some((name, body.trim()))
+        }
+        _ => None,
+    }
+}
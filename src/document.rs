@@ -0,0 +1,243 @@
+//! The saved form of a workspace, a versioned document written as JSON or RON.
+use std::path::Path;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::animation::Animation;
+use crate::error::PlotError;
+use crate::evaluation::CurveStyle;
+use crate::parameters::Parameter;
+use crate::sweep::ParameterSweep;
+
+/// Version of the documents written by this build, see [`Document::migrate`].
+pub const CURRENT_VERSION: u32 = 1;
+
+/// Start of the URL fragments holding a document, see [`Document::to_fragment`].
+const FRAGMENT_PREFIX: &str = "w=";
+
+/// Largest decompressed document accepted from a URL fragment, in bytes.
+const MAX_FRAGMENT_DOCUMENT: usize = 1 << 20;
+
+/// Upgrades a document from the version of its index to the next one.
+///
+/// The fields added since a version are filled with their default when reading, the
+/// migrations only have to fix what changed meaning.
+const MIGRATIONS: [fn(&mut Document); CURRENT_VERSION as usize] = [
+    // 0: written by hand, without a version
+    |_| {},
+];
+
+/// A saved function, its expression kept as typed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FunctionEntry {
+    pub name: String,
+    pub expression: String,
+    #[serde(default)]
+    pub style: CurveStyle,
+    #[serde(default)]
+    pub show_derivative: bool,
+    #[serde(default)]
+    pub show_second_derivative: bool,
+    #[serde(default)]
+    pub sweep: Option<ParameterSweep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParametricCurveEntry {
+    pub name: String,
+    pub x_expression: String,
+    pub y_expression: String,
+    pub t_range: (f64, f64),
+    pub samples: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolarCurveEntry {
+    pub name: String,
+    pub expression: String,
+    pub theta_range: (f64, f64),
+    pub samples: usize,
+}
+
+/// A saved implicit curve or region, both drawn over a grid following the view.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GridElementEntry {
+    pub name: String,
+    pub expression: String,
+    pub resolution: usize,
+}
+
+/// A saved parameter, `expression` being set for a derived parameter.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterEntry {
+    pub name: String,
+    // JSON has no NaN, it is written as `null`
+    #[serde(deserialize_with = "nan_if_null")]
+    pub value: f64,
+    #[serde(default = "default_range")]
+    pub range: (f64, f64),
+    #[serde(default = "default_step")]
+    pub step: f64,
+    #[serde(default)]
+    pub expression: Option<String>,
+    #[serde(default)]
+    pub animation: Animation,
+}
+
+fn default_range() -> (f64, f64) {
+    Parameter::DEFAULT_RANGE
+}
+
+fn default_step() -> f64 {
+    Parameter::DEFAULT_STEP
+}
+
+fn nan_if_null<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Value {
+        Number(f64),
+        Null(()),
+    }
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(value) => value,
+        Value::Null(()) => f64::NAN,
+    })
+}
+
+/// The bounds of the plot.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct View {
+    pub xlim: (f64, f64),
+    pub ylim: (f64, f64),
+}
+impl Default for View {
+    fn default() -> Self {
+        Self {
+            xlim: (-10.0, 10.0),
+            ylim: (-10.0, 10.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub polar_grid: bool,
+}
+
+/// Everything needed to restore a workspace, see
+/// [`PlottingApp::to_document`](crate::plotting::PlottingApp::to_document).
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Document {
+    /// The version the document was written with, [`CURRENT_VERSION`] once read.
+    pub version: u32,
+    pub functions: Vec<FunctionEntry>,
+    pub parametric_curves: Vec<ParametricCurveEntry>,
+    pub polar_curves: Vec<PolarCurveEntry>,
+    pub implicit_curves: Vec<GridElementEntry>,
+    pub regions: Vec<GridElementEntry>,
+    pub parameters: Vec<ParameterEntry>,
+    pub view: View,
+    pub settings: Settings,
+}
+
+/// The text formats a document can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Ron,
+}
+impl Format {
+    /// The format of a file from its extension, JSON unless it is `.ron`.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("ron") => Self::Ron,
+            _ => Self::Json,
+        }
+    }
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Ron => "ron",
+        }
+    }
+}
+
+impl Document {
+    pub fn to_string(&self, format: Format) -> Result<String, PlotError> {
+        let text = match format {
+            Format::Json => serde_json::to_string_pretty(self).map_err(document_error)?,
+            Format::Ron => ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(document_error)?,
+        };
+        Ok(text)
+    }
+    /// Reads a document of any version up to [`CURRENT_VERSION`] and upgrades it.
+    pub fn from_str(text: &str, format: Format) -> Result<Self, PlotError> {
+        let document: Self = match format {
+            Format::Json => serde_json::from_str(text).map_err(document_error)?,
+            Format::Ron => ron::from_str(text).map_err(document_error)?,
+        };
+        document.migrate()
+    }
+    /// Brings a document read from an older version up to [`CURRENT_VERSION`].
+    ///
+    /// Fails for a document written by a newer version.
+    pub fn migrate(mut self) -> Result<Self, PlotError> {
+        if self.version > CURRENT_VERSION {
+            return Err(PlotError::Document(format!(
+                "version {} is newer than the supported version {}",
+                self.version, CURRENT_VERSION
+            )));
+        }
+        for migration in &MIGRATIONS[self.version as usize..] {
+            migration(&mut self);
+        }
+        self.version = CURRENT_VERSION;
+        Ok(self)
+    }
+    /// Encodes the document in a URL fragment, like `w=rVLBbs...`, without the leading `#`.
+    ///
+    /// The document is written as compact JSON, compressed and encoded as URL safe base64.
+    pub fn to_fragment(&self) -> Result<String, PlotError> {
+        let json = serde_json::to_vec(self).map_err(document_error)?;
+        let compressed = miniz_oxide::deflate::compress_to_vec(&json, 9);
+        Ok(format!(
+            "{}{}",
+            FRAGMENT_PREFIX,
+            URL_SAFE_NO_PAD.encode(compressed)
+        ))
+    }
+    /// Decodes a URL fragment written by [`Document::to_fragment`], with or without the
+    /// leading `#`, and upgrades the document.
+    pub fn from_fragment(fragment: &str) -> Result<Self, PlotError> {
+        let fragment = fragment.strip_prefix('#').unwrap_or(fragment);
+        let encoded = fragment
+            .strip_prefix(FRAGMENT_PREFIX)
+            .ok_or_else(|| PlotError::Document("the link holds no workspace".to_owned()))?;
+        let compressed = URL_SAFE_NO_PAD.decode(encoded).map_err(document_error)?;
+        let json =
+            miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_FRAGMENT_DOCUMENT)
+                .map_err(|_| PlotError::Document("the link is damaged".to_owned()))?;
+        let document: Self = serde_json::from_slice(&json).map_err(document_error)?;
+        document.migrate()
+    }
+    /// Writes the document to a file, in the format of its extension.
+    pub fn save(&self, path: &Path) -> Result<(), PlotError> {
+        let text = self.to_string(Format::from_path(path))?;
+        std::fs::write(path, text).map_err(document_error)
+    }
+    /// Reads a document from a file, in the format of its extension.
+    pub fn load(path: &Path) -> Result<Self, PlotError> {
+        let text = std::fs::read_to_string(path).map_err(document_error)?;
+        Self::from_str(&text, Format::from_path(path))
+    }
+}
+
+fn document_error(error: impl std::fmt::Display) -> PlotError {
+    PlotError::Document(error.to_string())
+}
@@ -0,0 +1,695 @@
+//! Plot elements other than `y = f(x)` functions.
+use std::ops::RangeInclusive;
+
+use crate::compiled::CompiledExpression;
+use crate::definitions::Definitions;
+use crate::error::PlotError;
+use crate::evaluation::{compile_all, parameters_of, Parametrized, DEFAULT_VALUE_PARAMETER};
+use crate::parser::{self, Expression, Operator};
+use crate::sampling;
+
+/// The compiled expressions of an element with the values of their parameters.
+#[derive(Debug, Clone)]
+struct Parametrization<C> {
+    // `None` until the expressions parse, an element loaded with an invalid text draws nothing
+    compiled: Option<C>,
+    vars_names: Vec<String>,
+    vars_values: Vec<f64>,
+    // error of the last edit of the expressions, the previous valid ones are kept meanwhile
+    error: Option<PlotError>,
+}
+impl<C> Parametrization<C> {
+    /// `compiled` with its parameters at their default value.
+    fn new((compiled, vars_names): (C, Vec<String>)) -> Self {
+        Self {
+            compiled: Some(compiled),
+            vars_values: vars_names.iter().map(|_| DEFAULT_VALUE_PARAMETER).collect(),
+            vars_names,
+            error: None,
+        }
+    }
+    /// Nothing compiled yet, the expressions are parsed with [`Parametrization::update`].
+    fn unparsed() -> Self {
+        Self {
+            compiled: None,
+            vars_names: vec![],
+            vars_values: vec![],
+            error: None,
+        }
+    }
+    fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        match self.vars_names.iter().position(|x| x == &var_name) {
+            Some(i) => {
+                self.vars_values[i] = value;
+                Ok(())
+            }
+            None => Err(PlotError::UnboundVariable(var_name)),
+        }
+    }
+    /// Replaces the compiled expressions by the result of parsing them again, or keeps them
+    /// and stores the error.
+    fn update(&mut self, parsed: Result<(C, Vec<String>), PlotError>) -> Result<(), PlotError> {
+        match parsed {
+            Ok(parsed) => {
+                *self = Self::new(parsed);
+                Ok(())
+            }
+            Err(error) => {
+                self.error = Some(error.clone());
+                Err(error)
+            }
+        }
+    }
+}
+
+/// A curve `(x(t), y(t))` drawn over its own range of `t`.
+///
+/// Like a [`Function`](crate::evaluation::Function), every variable other than `t` is a
+/// parameter, shared by both expressions.
+#[derive(Debug, Clone)]
+pub struct ParametricCurve {
+    pub name: String,
+    pub x_expression: String,
+    pub y_expression: String,
+    pub t_range: (f64, f64),
+    /// Number of evenly spaced values of `t` the curve is drawn with.
+    pub samples: usize,
+    parametrization: Parametrization<[CompiledExpression; 2]>,
+}
+impl ParametricCurve {
+    pub const DEFAULT_SAMPLES: usize = 500;
+    /// Bounds of the number of samples, a workspace loaded with more is drawn with the maximum.
+    pub const SAMPLES_RANGE: RangeInclusive<usize> = 2..=100_000;
+    pub const DEFAULT_T_RANGE: (f64, f64) = (0.0, std::f64::consts::TAU);
+
+    pub fn new(
+        x_expression: String,
+        y_expression: String,
+        name: String,
+    ) -> Result<Self, PlotError> {
+        let parsed = Self::parse(&x_expression, &y_expression, &Definitions::default())?;
+        Ok(Self {
+            name,
+            x_expression,
+            y_expression,
+            t_range: Self::DEFAULT_T_RANGE,
+            samples: Self::DEFAULT_SAMPLES,
+            parametrization: Parametrization::new(parsed),
+        })
+    }
+    /// A curve of the texts `x_expression` and `y_expression`, drawing nothing until they are
+    /// parsed by [`ParametricCurve::reparse_with`].
+    pub fn unparsed(x_expression: String, y_expression: String, name: String) -> Self {
+        Self {
+            name,
+            x_expression,
+            y_expression,
+            t_range: Self::DEFAULT_T_RANGE,
+            samples: Self::DEFAULT_SAMPLES,
+            parametrization: Parametrization::unparsed(),
+        }
+    }
+    fn parse(
+        x_expression: &str,
+        y_expression: &str,
+        definitions: &Definitions,
+    ) -> Result<([CompiledExpression; 2], Vec<String>), PlotError> {
+        let parsed = [
+            definitions.expand(&Expression::parse(x_expression)?)?,
+            definitions.expand(&Expression::parse(y_expression)?)?,
+        ];
+        let (compiled, vars_names) = compile_all(&parsed, "t")?;
+        let compiled = compiled.try_into().expect("two expressions were compiled");
+        Ok((compiled, vars_names))
+    }
+    /// Names of the parameters of both expressions, sorted and without `t`.
+    pub fn vars_names(&self) -> &[String] {
+        &self.parametrization.vars_names
+    }
+    pub fn vars_values(&self) -> &[f64] {
+        &self.parametrization.vars_values
+    }
+    /// The error of the last call to [`ParametricCurve::reparse`], if it failed.
+    pub fn error(&self) -> Option<&PlotError> {
+        self.parametrization.error.as_ref()
+    }
+    pub fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        self.parametrization.assign_value_to_var(var_name, value)
+    }
+    /// Parses both expressions again after one of them was edited.
+    ///
+    /// On failure the previous valid curve keeps being drawn and the error is stored, see
+    /// [`ParametricCurve::error`].
+    pub fn reparse(&mut self) -> Result<(), PlotError> {
+        self.reparse_with(&Definitions::default())
+    }
+    /// Like [`ParametricCurve::reparse`], resolving calls with `definitions`.
+    pub fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        let parsed = Self::parse(&self.x_expression, &self.y_expression, definitions);
+        self.parametrization.update(parsed)
+    }
+    /// The point of the curve at `t`.
+    pub fn eval(&self, t: f64) -> [f64; 2] {
+        let Parametrization {
+            compiled: Some([x, y]),
+            vars_values,
+            ..
+        } = &self.parametrization
+        else {
+            return [f64::NAN; 2];
+        };
+        [x.eval(t, vars_values), y.eval(t, vars_values)]
+    }
+    /// The curve as line segments, split where it is not defined.
+    pub fn sample(&self) -> Vec<Vec<[f64; 2]>> {
+        sampling::sample_parametric(|t| self.eval(t), self.t_range, self.samples)
+    }
+}
+impl Parametrized for ParametricCurve {
+    fn vars_names(&self) -> &[String] {
+        ParametricCurve::vars_names(self)
+    }
+    fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        ParametricCurve::assign_value_to_var(self, var_name, value)
+    }
+    fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        ParametricCurve::reparse_with(self, definitions)
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn error(&self) -> Option<&PlotError> {
+        ParametricCurve::error(self)
+    }
+    fn is_compiled(&self) -> bool {
+        self.parametrization.compiled.is_some()
+    }
+    fn rename_var(&mut self, old: &str, new: &str) {
+        self.x_expression = parser::rename_variable(&self.x_expression, old, new);
+        self.y_expression = parser::rename_variable(&self.y_expression, old, new);
+    }
+}
+
+/// A curve `r = f(θ)` in polar coordinates, drawn over its own range of `θ`.
+///
+/// The angle is written `theta` or `θ`, every other variable is a parameter.
+#[derive(Debug, Clone)]
+pub struct PolarCurve {
+    pub name: String,
+    pub expression: String,
+    pub theta_range: (f64, f64),
+    /// Number of evenly spaced angles the curve is drawn with.
+    pub samples: usize,
+    parametrization: Parametrization<CompiledExpression>,
+}
+impl PolarCurve {
+    pub const DEFAULT_SAMPLES: usize = 1000;
+    pub const SAMPLES_RANGE: RangeInclusive<usize> = ParametricCurve::SAMPLES_RANGE;
+    pub const DEFAULT_THETA_RANGE: (f64, f64) = (0.0, std::f64::consts::TAU);
+
+    pub fn new(expression: String, name: String) -> Result<Self, PlotError> {
+        let parsed = Self::parse(&expression, &Definitions::default())?;
+        Ok(Self {
+            name,
+            expression,
+            theta_range: Self::DEFAULT_THETA_RANGE,
+            samples: Self::DEFAULT_SAMPLES,
+            parametrization: Parametrization::new(parsed),
+        })
+    }
+    /// A curve of the text `expression`, drawing nothing until it is parsed by
+    /// [`PolarCurve::reparse_with`].
+    pub fn unparsed(expression: String, name: String) -> Self {
+        Self {
+            name,
+            expression,
+            theta_range: Self::DEFAULT_THETA_RANGE,
+            samples: Self::DEFAULT_SAMPLES,
+            parametrization: Parametrization::unparsed(),
+        }
+    }
+    fn parse(
+        expression: &str,
+        definitions: &Definitions,
+    ) -> Result<(CompiledExpression, Vec<String>), PlotError> {
+        let theta = Expression::Variable("theta".to_owned());
+        let parsed = definitions.expand(&Expression::parse(expression)?.substitute("θ", &theta))?;
+        let (mut compiled, vars_names) = compile_all(&[parsed], "theta")?;
+        Ok((compiled.remove(0), vars_names))
+    }
+    /// Names of the parameters of the expression, sorted and without `theta`.
+    pub fn vars_names(&self) -> &[String] {
+        &self.parametrization.vars_names
+    }
+    pub fn vars_values(&self) -> &[f64] {
+        &self.parametrization.vars_values
+    }
+    /// The error of the last call to [`PolarCurve::reparse`], if it failed.
+    pub fn error(&self) -> Option<&PlotError> {
+        self.parametrization.error.as_ref()
+    }
+    pub fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        self.parametrization.assign_value_to_var(var_name, value)
+    }
+    /// Parses `expression` again after it was edited.
+    ///
+    /// On failure the previous valid curve keeps being drawn and the error is stored, see
+    /// [`PolarCurve::error`].
+    pub fn reparse(&mut self) -> Result<(), PlotError> {
+        self.reparse_with(&Definitions::default())
+    }
+    /// Like [`PolarCurve::reparse`], resolving calls with `definitions`.
+    pub fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        self.parametrization
+            .update(Self::parse(&self.expression, definitions))
+    }
+    /// The radius at the angle `theta`.
+    pub fn eval(&self, theta: f64) -> f64 {
+        let compiled = self.parametrization.compiled.as_ref();
+        compiled.map_or(f64::NAN, |compiled| {
+            compiled.eval(theta, self.vars_values())
+        })
+    }
+    /// The curve in Cartesian coordinates as line segments, split where it is not defined.
+    pub fn sample(&self) -> Vec<Vec<[f64; 2]>> {
+        let point = |theta: f64| {
+            let r = self.eval(theta);
+            [r * theta.cos(), r * theta.sin()]
+        };
+        sampling::sample_parametric(point, self.theta_range, self.samples)
+    }
+}
+impl Parametrized for PolarCurve {
+    fn vars_names(&self) -> &[String] {
+        PolarCurve::vars_names(self)
+    }
+    fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        PolarCurve::assign_value_to_var(self, var_name, value)
+    }
+    fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        PolarCurve::reparse_with(self, definitions)
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn error(&self) -> Option<&PlotError> {
+        PolarCurve::error(self)
+    }
+    fn is_compiled(&self) -> bool {
+        self.parametrization.compiled.is_some()
+    }
+    fn rename_var(&mut self, old: &str, new: &str) {
+        self.expression = parser::rename_variable(&self.expression, old, new);
+    }
+}
+
+/// Circles and spokes of a polar grid covering a view.
+pub struct PolarGrid {
+    /// Radii of the concentric circles, evenly spaced.
+    pub radii: Vec<f64>,
+    /// Angles of the radial spokes.
+    pub angles: Vec<f64>,
+    /// Radius up to which the spokes are drawn, the distance to the farthest corner of the view.
+    pub max_radius: f64,
+}
+impl PolarGrid {
+    /// Angle between two spokes, 30°.
+    pub const ANGLE_STEP: f64 = std::f64::consts::PI / 6.0;
+
+    /// A grid for the view `xlim` × `ylim`, with about `circles` circles across it.
+    pub fn for_view(xlim: (f64, f64), ylim: (f64, f64), circles: usize) -> Self {
+        let corners = [xlim.0, xlim.1]
+            .into_iter()
+            .flat_map(|x| [ylim.0, ylim.1].map(|y| x.hypot(y)));
+        let max_radius = corners.fold(0.0, f64::max);
+        let step = nice_step(max_radius / circles.max(1) as f64);
+        let count = if step > 0.0 {
+            (max_radius / step).ceil() as usize
+        } else {
+            0
+        };
+        Self {
+            radii: (1..=count).map(|i| i as f64 * step).collect(),
+            angles: (0..12).map(|i| i as f64 * Self::ANGLE_STEP).collect(),
+            max_radius,
+        }
+    }
+}
+
+/// The smallest of 1, 2 or 5 times a power of ten that is at least `step`.
+fn nice_step(step: f64) -> f64 {
+    if !(step.is_finite() && step > 0.0) {
+        return 0.0;
+    }
+    let magnitude = 10f64.powf(step.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|factor| factor * magnitude)
+        .find(|nice| *nice >= step)
+        .unwrap_or(10.0 * magnitude)
+}
+
+/// Parses `lhs = rhs` into `lhs - rhs`, the expression whose zeros are the curve.
+///
+/// Without `=` the whole text is the expression equal to 0.
+pub fn parse_equation(text: &str) -> Result<Expression, PlotError> {
+    let parse = |text: &str, offset: usize| {
+        Expression::parse(text).map_err(|mut error| {
+            error.span = error.span.start + offset..error.span.end + offset;
+            error
+        })
+    };
+    // a lone `=`, not part of a comparison like `<=` or `==`
+    let bytes = text.as_bytes();
+    let mut equals = (0..bytes.len()).filter(|&i| {
+        bytes[i] == b'='
+            && !(i > 0 && b"<>=!".contains(&bytes[i - 1]))
+            && bytes.get(i + 1) != Some(&b'=')
+    });
+    match (equals.next(), equals.next()) {
+        (None, _) => Ok(parse(text, 0)?),
+        (Some(i), None) => Ok(Expression::Binary(
+            Operator::Subtract,
+            Box::new(parse(&text[..i], 0)?),
+            Box::new(parse(&text[i + 1..], i + 1)?),
+        )),
+        (Some(_), Some(i)) => Err(PlotError::Parse {
+            message: "expected a single `=`".to_owned(),
+            position: Some(i),
+        }),
+    }
+}
+
+/// Compiles an expression of `x` and `y`, returns it with its parameters.
+fn compile_xy(parsed: &Expression) -> Result<(CompiledExpression, Vec<String>), PlotError> {
+    let mut vars_names = parameters_of(parsed, "x");
+    vars_names.retain(|name| name != "y");
+    let compiled = CompiledExpression::with_variables(parsed, &["x", "y"], &vars_names)?;
+    Ok((compiled, vars_names))
+}
+
+/// A curve `F(x, y) = 0`, like `x^2 + y^2 = r^2`, traced over the view.
+///
+/// Every variable other than `x` and `y` is a parameter.
+#[derive(Debug, Clone)]
+pub struct ImplicitCurve {
+    pub name: String,
+    /// An equation, or an expression of `x` and `y` equal to 0.
+    pub expression: String,
+    /// Number of cells of the grid along each axis of the view, the grid follows the view so
+    /// zooming in refines the curve.
+    pub resolution: usize,
+    parametrization: Parametrization<CompiledExpression>,
+}
+impl ImplicitCurve {
+    pub const DEFAULT_RESOLUTION: usize = 200;
+    /// Bounds of the resolution, the number of cells grows with its square.
+    pub const RESOLUTION_RANGE: RangeInclusive<usize> = 10..=1000;
+
+    pub fn new(expression: String, name: String) -> Result<Self, PlotError> {
+        let parsed = Self::parse(&expression, &Definitions::default())?;
+        Ok(Self {
+            name,
+            expression,
+            resolution: Self::DEFAULT_RESOLUTION,
+            parametrization: Parametrization::new(parsed),
+        })
+    }
+    /// A curve of the text `expression`, drawing nothing until it is parsed by
+    /// [`ImplicitCurve::reparse_with`].
+    pub fn unparsed(expression: String, name: String) -> Self {
+        Self {
+            name,
+            expression,
+            resolution: Self::DEFAULT_RESOLUTION,
+            parametrization: Parametrization::unparsed(),
+        }
+    }
+    fn parse(
+        expression: &str,
+        definitions: &Definitions,
+    ) -> Result<(CompiledExpression, Vec<String>), PlotError> {
+        compile_xy(&definitions.expand(&parse_equation(expression)?)?)
+    }
+    /// Names of the parameters of the expression, sorted and without `x` nor `y`.
+    pub fn vars_names(&self) -> &[String] {
+        &self.parametrization.vars_names
+    }
+    pub fn vars_values(&self) -> &[f64] {
+        &self.parametrization.vars_values
+    }
+    /// The error of the last call to [`ImplicitCurve::reparse`], if it failed.
+    pub fn error(&self) -> Option<&PlotError> {
+        self.parametrization.error.as_ref()
+    }
+    pub fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        self.parametrization.assign_value_to_var(var_name, value)
+    }
+    /// Parses `expression` again after it was edited.
+    ///
+    /// On failure the previous valid curve keeps being drawn and the error is stored, see
+    /// [`ImplicitCurve::error`].
+    pub fn reparse(&mut self) -> Result<(), PlotError> {
+        self.reparse_with(&Definitions::default())
+    }
+    /// Like [`ImplicitCurve::reparse`], resolving calls with `definitions`.
+    pub fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        self.parametrization
+            .update(Self::parse(&self.expression, definitions))
+    }
+    /// `F(x, y)`, the difference between both sides of the equation.
+    pub fn eval(&self, x: f64, y: f64) -> f64 {
+        let compiled = self.parametrization.compiled.as_ref();
+        compiled.map_or(f64::NAN, |compiled| {
+            compiled.eval_xy(x, y, self.vars_values())
+        })
+    }
+    /// The curve over the view `xlim` × `ylim` as line segments.
+    pub fn sample(&self, xlim: (f64, f64), ylim: (f64, f64)) -> Vec<Vec<[f64; 2]>> {
+        if self.parametrization.compiled.is_none() {
+            return vec![];
+        }
+        let f = |x, y| self.eval(x, y);
+        sampling::contour(f, xlim, ylim, (self.resolution, self.resolution))
+    }
+}
+impl Parametrized for ImplicitCurve {
+    fn vars_names(&self) -> &[String] {
+        ImplicitCurve::vars_names(self)
+    }
+    fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        ImplicitCurve::assign_value_to_var(self, var_name, value)
+    }
+    fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        ImplicitCurve::reparse_with(self, definitions)
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn error(&self) -> Option<&PlotError> {
+        ImplicitCurve::error(self)
+    }
+    fn is_compiled(&self) -> bool {
+        self.parametrization.compiled.is_some()
+    }
+    fn rename_var(&mut self, old: &str, new: &str) {
+        self.expression = parser::rename_variable(&self.expression, old, new);
+    }
+}
+
+/// The comparison of an inequality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Inequality {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+impl Inequality {
+    /// Whether the boundary is excluded from the region.
+    pub fn is_strict(self) -> bool {
+        matches!(self, Self::Less | Self::Greater)
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+        }
+    }
+    fn from_operator(op: Operator) -> Option<Self> {
+        match op {
+            Operator::Less => Some(Self::Less),
+            Operator::LessOrEqual => Some(Self::LessOrEqual),
+            Operator::Greater => Some(Self::Greater),
+            Operator::GreaterOrEqual => Some(Self::GreaterOrEqual),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `text` is an inequality, and so describes a [`Region`] rather than a function.
+///
+/// Comparisons inside a function, like the conditions of `{x < 0: -x, x}`, do not count.
+pub fn is_inequality(text: &str) -> bool {
+    matches!(
+        Expression::parse(text),
+        Ok(Expression::Binary(op, ..)) if Inequality::from_operator(op).is_some()
+    )
+}
+
+/// Parses `lhs < rhs`, or any other [`Inequality`], into `lhs - rhs` and the comparison.
+pub fn parse_inequality(text: &str) -> Result<(Expression, Inequality), PlotError> {
+    if let Expression::Binary(op, lhs, rhs) = Expression::parse(text)? {
+        if let Some(inequality) = Inequality::from_operator(op) {
+            return Ok((Expression::Binary(Operator::Subtract, lhs, rhs), inequality));
+        }
+    }
+    Err(PlotError::Parse {
+        message: "expected an inequality such as `y < x`".to_owned(),
+        position: None,
+    })
+}
+
+/// The region where an inequality of `x` and `y` holds, like `y > x^2 - a` or
+/// `x^2 + y^2 <= 4`, shaded over the view.
+///
+/// Every variable other than `x` and `y` is a parameter.
+#[derive(Debug, Clone)]
+pub struct Region {
+    pub name: String,
+    pub expression: String,
+    /// Number of cells of the grid along each axis of the view.
+    pub resolution: usize,
+    // `lhs - rhs` and the comparison
+    parametrization: Parametrization<(CompiledExpression, Inequality)>,
+}
+impl Region {
+    pub const DEFAULT_RESOLUTION: usize = 150;
+    pub const RESOLUTION_RANGE: RangeInclusive<usize> = ImplicitCurve::RESOLUTION_RANGE;
+
+    pub fn new(expression: String, name: String) -> Result<Self, PlotError> {
+        Self::with_definitions(expression, name, &Definitions::default())
+    }
+    /// Like [`Region::new`], `expression` being able to call the functions of `definitions`.
+    pub fn with_definitions(
+        expression: String,
+        name: String,
+        definitions: &Definitions,
+    ) -> Result<Self, PlotError> {
+        let parsed = Self::parse(&expression, definitions)?;
+        Ok(Self {
+            name,
+            expression,
+            resolution: Self::DEFAULT_RESOLUTION,
+            parametrization: Parametrization::new(parsed),
+        })
+    }
+    /// A region of the text `expression`, drawing nothing until it is parsed by
+    /// [`Region::reparse_with`].
+    pub fn unparsed(expression: String, name: String) -> Self {
+        Self {
+            name,
+            expression,
+            resolution: Self::DEFAULT_RESOLUTION,
+            parametrization: Parametrization::unparsed(),
+        }
+    }
+    fn parse(
+        expression: &str,
+        definitions: &Definitions,
+    ) -> Result<((CompiledExpression, Inequality), Vec<String>), PlotError> {
+        let (difference, inequality) = parse_inequality(expression)?;
+        let (compiled, vars_names) = compile_xy(&definitions.expand(&difference)?)?;
+        Ok(((compiled, inequality), vars_names))
+    }
+    /// The comparison of the inequality, `None` until it is parsed.
+    pub fn inequality(&self) -> Option<Inequality> {
+        let compiled = self.parametrization.compiled.as_ref();
+        compiled.map(|(_, inequality)| *inequality)
+    }
+    /// Names of the parameters of the inequality, sorted and without `x` nor `y`.
+    pub fn vars_names(&self) -> &[String] {
+        &self.parametrization.vars_names
+    }
+    pub fn vars_values(&self) -> &[f64] {
+        &self.parametrization.vars_values
+    }
+    /// The error of the last call to [`Region::reparse`], if it failed.
+    pub fn error(&self) -> Option<&PlotError> {
+        self.parametrization.error.as_ref()
+    }
+    pub fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        self.parametrization.assign_value_to_var(var_name, value)
+    }
+    /// Parses `expression` again after it was edited.
+    ///
+    /// On failure the previous valid region keeps being drawn and the error is stored, see
+    /// [`Region::error`].
+    pub fn reparse(&mut self) -> Result<(), PlotError> {
+        self.reparse_with(&Definitions::default())
+    }
+    /// Like [`Region::reparse`], resolving calls with `definitions`.
+    pub fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        self.parametrization
+            .update(Self::parse(&self.expression, definitions))
+    }
+    /// Whether the inequality holds at `(x, y)`, never until it is parsed.
+    pub fn contains(&self, x: f64, y: f64) -> bool {
+        let Some((compiled, inequality)) = &self.parametrization.compiled else {
+            return false;
+        };
+        let difference = compiled.eval_xy(x, y, self.vars_values());
+        match inequality {
+            Inequality::Less => difference < 0.0,
+            Inequality::LessOrEqual => difference <= 0.0,
+            Inequality::Greater => difference > 0.0,
+            Inequality::GreaterOrEqual => difference >= 0.0,
+        }
+    }
+    /// A function that is negative inside the region and 0 on its boundary, `None` until the
+    /// inequality is parsed.
+    fn inside_negative(&self) -> Option<impl Fn(f64, f64) -> f64 + '_> {
+        let (compiled, inequality) = self.parametrization.compiled.as_ref()?;
+        let sign = match inequality {
+            Inequality::Less | Inequality::LessOrEqual => 1.0,
+            Inequality::Greater | Inequality::GreaterOrEqual => -1.0,
+        };
+        let vars_values = self.vars_values();
+        Some(move |x, y| sign * compiled.eval_xy(x, y, vars_values))
+    }
+    /// Convex polygons covering the region over the view `xlim` × `ylim`.
+    pub fn sample_fill(&self, xlim: (f64, f64), ylim: (f64, f64)) -> Vec<Vec<[f64; 2]>> {
+        let resolution = (self.resolution, self.resolution);
+        (self.inside_negative()).map_or(vec![], |f| sampling::fill(f, xlim, ylim, resolution))
+    }
+    /// The boundary of the region over the view `xlim` × `ylim` as line segments.
+    pub fn sample_boundary(&self, xlim: (f64, f64), ylim: (f64, f64)) -> Vec<Vec<[f64; 2]>> {
+        let resolution = (self.resolution, self.resolution);
+        (self.inside_negative()).map_or(vec![], |f| sampling::contour(f, xlim, ylim, resolution))
+    }
+}
+impl Parametrized for Region {
+    fn vars_names(&self) -> &[String] {
+        Region::vars_names(self)
+    }
+    fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        Region::assign_value_to_var(self, var_name, value)
+    }
+    fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        Region::reparse_with(self, definitions)
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn error(&self) -> Option<&PlotError> {
+        Region::error(self)
+    }
+    fn is_compiled(&self) -> bool {
+        self.parametrization.compiled.is_some()
+    }
+    fn rename_var(&mut self, old: &str, new: &str) {
+        self.expression = parser::rename_variable(&self.expression, old, new);
+    }
+}
@@ -0,0 +1,72 @@
+use std::fmt;
+
+use crate::parser::ParseError;
+
+/// Everything that can go wrong while turning text into a curve.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PlotError {
+    /// The expression is not well formed, `position` is the byte offset of the problem when known.
+    Parse {
+        message: String,
+        position: Option<usize>,
+    },
+    /// A call to a function that does not exist.
+    UnknownFunction(String),
+    /// A variable that has no value assigned.
+    UnboundVariable(String),
+    /// The expression has no real value at `x`, e.g. `sqrt(x)` for negative `x`.
+    Domain { x: f64 },
+    /// The derivative of an expression using this function or operator cannot be computed.
+    NotDifferentiable(String),
+    /// A user-defined function calling itself, the names are the chain of calls back to it.
+    CyclicDefinition(Vec<String>),
+    /// A name given to something while another thing already has it.
+    DuplicateName(String),
+    /// A saved workspace that could not be read or written, with the reason.
+    Document(String),
+    /// An image of the plot, or values printed by the command line, that could not be written,
+    /// with the reason.
+    Export(String),
+    /// Arguments of the command line that do not make sense, with the reason.
+    Usage(String),
+}
+
+impl From<ParseError> for PlotError {
+    fn from(error: ParseError) -> Self {
+        Self::Parse {
+            message: error.message,
+            position: Some(error.span.start),
+        }
+    }
+}
+
+impl fmt::Display for PlotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse {
+                message,
+                position: Some(position),
+            } => write!(f, "Parse error at {}: {}", position, message),
+            Self::Parse {
+                message,
+                position: None,
+            } => write!(f, "Parse error: {}", message),
+            Self::UnknownFunction(name) => write!(f, "Unknown function `{}`", name),
+            Self::UnboundVariable(name) => write!(f, "Variable `{}` has no value", name),
+            Self::Domain { x } => write!(f, "Not defined at x = {}", x),
+            Self::NotDifferentiable(name) => write!(f, "`{}` cannot be differentiated", name),
+            Self::CyclicDefinition(names) => write!(
+                f,
+                "`{}` is defined in terms of itself: {}",
+                names[0],
+                names.join(" → ")
+            ),
+            Self::DuplicateName(name) => write!(f, "`{}` is already defined", name),
+            Self::Document(message) => write!(f, "Document error: {}", message),
+            Self::Export(message) => write!(f, "Export error: {}", message),
+            Self::Usage(message) => write!(f, "Usage error: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for PlotError {}
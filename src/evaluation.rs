@@ -0,0 +1,312 @@
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::compiled::{CompiledExpression, CONSTANTS};
+use crate::definitions::Definitions;
+use crate::error::PlotError;
+use crate::parser::{self, Expression};
+use crate::sweep::ParameterSweep;
+
+pub const DEFAULT_VALUE_PARAMETER: f64 = 1.0;
+
+/// A derivative of the expression, kept both symbolic to be shown and compiled to be plotted.
+#[derive(Debug, Clone)]
+struct Derivative {
+    expression: Expression,
+    compiled: CompiledExpression,
+}
+
+/// The first and second derivatives, or why they could not be computed.
+type Derivatives = Result<[Derivative; 2], PlotError>;
+
+/// How a curve is drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CurveStyle {
+    /// `None` picks a color from the position of the curve in the plot.
+    pub color: Option<[u8; 3]>,
+    pub width: f32,
+}
+impl Default for CurveStyle {
+    fn default() -> Self {
+        Self {
+            color: None,
+            width: Self::DEFAULT_WIDTH,
+        }
+    }
+}
+impl CurveStyle {
+    pub const DEFAULT_WIDTH: f32 = 1.5;
+    pub const WIDTH_RANGE: RangeInclusive<f32> = 0.5..=8.0;
+}
+
+/// A plottable `y = f(x)` expression together with the values of its parameters.
+///
+/// Every variable other than `x` found in the expression is treated as a parameter.
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    pub expression: String,
+    // rebuilt only when the expression, and so its parameters, changes, with the derivatives
+    // compiled with the same parameters so they follow the parameter values; `None` until the
+    // expression parses, a function loaded with an invalid text draws nothing
+    compiled: Option<(CompiledExpression, Derivatives)>,
+    // the expression as typed, simplified along with the compilation for the interface
+    simplified: Option<String>,
+    pub(crate) vars_names: Vec<String>,
+    pub(crate) vars_values: Vec<f64>, //bind_expression:
+    // error of the last edit of `expression`, the previous valid expression is kept meanwhile
+    error: Option<PlotError>,
+    /// Whether the first derivative is drawn along with the function.
+    pub show_derivative: bool,
+    /// Whether the second derivative is drawn along with the function.
+    pub show_second_derivative: bool,
+    /// Draws the function once per value of a parameter instead of only for its current value.
+    pub sweep: Option<ParameterSweep>,
+    pub style: CurveStyle,
+}
+impl Function {
+    pub fn new(expression: String, name: String) -> Result<Self, PlotError> {
+        Self::with_definitions(expression, name, &Definitions::default())
+    }
+    /// Like [`Function::new`], `expression` being able to call the functions of `definitions`.
+    pub fn with_definitions(
+        expression: String,
+        name: String,
+        definitions: &Definitions,
+    ) -> Result<Self, PlotError> {
+        let (compiled, derivatives, vars_names) = Self::parse(&expression, definitions)?;
+        let vars_values = vars_names.iter().map(|_| DEFAULT_VALUE_PARAMETER).collect();
+        Ok(Self {
+            name,
+            simplified: Self::simplify(&expression),
+            expression,
+            compiled: Some((compiled, derivatives)),
+            vars_names,
+            vars_values,
+            error: None,
+            show_derivative: false,
+            show_second_derivative: false,
+            sweep: None,
+            style: CurveStyle::default(),
+        })
+    }
+    /// A function of the text `expression`, drawing nothing until it is parsed by
+    /// [`Function::reparse_with`].
+    pub fn unparsed(expression: String, name: String) -> Self {
+        Self {
+            name,
+            expression,
+            compiled: None,
+            simplified: None,
+            vars_names: vec![],
+            vars_values: vec![],
+            error: None,
+            show_derivative: false,
+            show_second_derivative: false,
+            sweep: None,
+            style: CurveStyle::default(),
+        }
+    }
+    /// Parses and compiles `expression` and its derivatives, returns them with its parameters.
+    fn parse(
+        expression: &str,
+        definitions: &Definitions,
+    ) -> Result<(CompiledExpression, Derivatives, Vec<String>), PlotError> {
+        let parsed_expression = definitions.expand(&Expression::parse(expression)?)?;
+        let (vars_names, _) = Self::extract_vars(&parsed_expression);
+        let compiled = CompiledExpression::new(&parsed_expression, &vars_names)?;
+        let derivatives = Self::derivatives(&parsed_expression, &vars_names);
+        Ok((compiled, derivatives, vars_names))
+    }
+    /// `expression` simplified, the calls to other functions being kept.
+    fn simplify(expression: &str) -> Option<String> {
+        let expression = Expression::parse(expression).ok()?;
+        Some(expression.simplify().to_string())
+    }
+    fn derivatives(expression: &Expression, vars_names: &[String]) -> Derivatives {
+        let derivative = |expression: &Expression| -> Result<Derivative, PlotError> {
+            let expression = expression.derivative("x")?.simplify();
+            let compiled = CompiledExpression::new(&expression, vars_names)?;
+            Ok(Derivative {
+                expression,
+                compiled,
+            })
+        };
+        let first = derivative(expression)?;
+        let second = derivative(&first.expression)?;
+        Ok([first, second])
+    }
+    /// Names of the parameters of the expression, sorted and without `x`.
+    pub fn vars_names(&self) -> &[String] {
+        &self.vars_names
+    }
+    /// Current parameter values, in the same order as [`Function::vars_names`].
+    pub fn vars_values(&self) -> &[f64] {
+        &self.vars_values
+    }
+    /// The expression simplified, `None` until it parses. Like the compiled expression, it is
+    /// the last valid one after a failed [`Function::reparse`].
+    pub fn simplified(&self) -> Option<&str> {
+        self.simplified.as_deref()
+    }
+    /// The error of the last call to [`Function::reparse`], if it failed.
+    pub fn error(&self) -> Option<&PlotError> {
+        self.error.as_ref()
+    }
+    pub fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        let index = self.vars_names.iter().position(|x| x == &var_name);
+        match index {
+            Some(i) => {
+                self.vars_values[i] = value;
+                Ok(())
+            }
+            None => Err(PlotError::UnboundVariable(var_name)),
+        }
+    }
+    /// Parses `expression` again after it was edited.
+    ///
+    /// On failure the previous valid expression keeps being evaluated and the error is stored,
+    /// see [`Function::error`].
+    pub fn reparse(&mut self) -> Result<(), PlotError> {
+        self.reparse_with(&Definitions::default())
+    }
+    /// Like [`Function::reparse`], resolving calls with `definitions`.
+    pub fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        match Self::parse(&self.expression, definitions) {
+            Ok((compiled, derivatives, vars_names)) => {
+                self.compiled = Some((compiled, derivatives));
+                self.simplified = Self::simplify(&self.expression);
+                self.vars_values = vars_names.iter().map(|_| DEFAULT_VALUE_PARAMETER).collect();
+                self.vars_names = vars_names;
+                self.error = None;
+                Ok(())
+            }
+            Err(error) => {
+                self.error = Some(error.clone());
+                Err(error)
+            }
+        }
+    }
+    /// Like [`Function::eval`], but reports a [`PlotError::Domain`] where the value is not defined.
+    pub fn try_eval(&self, x: f64) -> Result<f64, PlotError> {
+        let y = self.eval(x);
+        if y.is_nan() {
+            Err(PlotError::Domain { x })
+        } else {
+            Ok(y)
+        }
+    }
+    pub fn eval(&self, x: f64) -> f64 {
+        let compiled = self.compiled.as_ref();
+        compiled.map_or(f64::NAN, |(compiled, _)| compiled.eval(x, &self.vars_values))
+    }
+    /// Identifies the piece of a piecewise definition active at `x`, see
+    /// [`CompiledExpression::branch`]. The derivatives share the pieces of the function.
+    pub fn branch(&self, x: f64) -> u64 {
+        let compiled = self.compiled.as_ref();
+        compiled.map_or(0, |(compiled, _)| compiled.branch(x, &self.vars_values))
+    }
+    /// The `order`-th derivative with respect to `x`, `order` being 1 or 2.
+    ///
+    /// Returns `None` for other orders or when the expression could not be differentiated, see
+    /// [`Function::derivative_error`].
+    pub fn derivative(&self, order: usize) -> Option<&Expression> {
+        self.derivative_at(order)
+            .map(|derivative| &derivative.expression)
+    }
+    /// Evaluates the `order`-th derivative at `x`, NaN when it is not available.
+    pub fn eval_derivative(&self, order: usize, x: f64) -> f64 {
+        self.derivative_at(order).map_or(f64::NAN, |derivative| {
+            derivative.compiled.eval(x, &self.vars_values)
+        })
+    }
+    /// Why the expression could not be differentiated, if it could not.
+    pub fn derivative_error(&self) -> Option<&PlotError> {
+        self.compiled.as_ref()?.1.as_ref().err()
+    }
+    fn derivative_at(&self, order: usize) -> Option<&Derivative> {
+        let derivatives = self.compiled.as_ref()?.1.as_ref().ok()?;
+        order.checked_sub(1).and_then(|i| derivatives.get(i))
+    }
+    /// The parameters of an expression with their default values. An expression without `x`
+    /// is valid, it is plotted as a constant.
+    pub fn extract_vars(parsed_expression: &Expression) -> (Vec<String>, Vec<f64>) {
+        let vars_names = parameters_of(parsed_expression, "x");
+        // default var values to 1.0
+        let vars_values: Vec<f64> = vars_names.iter().map(|_| DEFAULT_VALUE_PARAMETER).collect();
+        (vars_names, vars_values)
+    }
+}
+
+impl Parametrized for Function {
+    fn vars_names(&self) -> &[String] {
+        Function::vars_names(self)
+    }
+    fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError> {
+        Function::assign_value_to_var(self, var_name, value)
+    }
+    fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError> {
+        Function::reparse_with(self, definitions)
+    }
+    fn name(&self) -> &str {
+        &self.name
+    }
+    fn error(&self) -> Option<&PlotError> {
+        Function::error(self)
+    }
+    fn is_compiled(&self) -> bool {
+        self.compiled.is_some()
+    }
+    fn rename_var(&mut self, old: &str, new: &str) {
+        self.expression = parser::rename_variable(&self.expression, old, new);
+        if let Some(sweep) = self.sweep.as_mut().filter(|sweep| sweep.parameter == old) {
+            sweep.parameter = new.to_owned();
+        }
+    }
+}
+
+/// An element whose expressions use the shared parameters and functions of the plot.
+pub trait Parametrized {
+    /// Names of the parameters used by the element.
+    fn vars_names(&self) -> &[String];
+    fn assign_value_to_var(&mut self, var_name: String, value: f64) -> Result<(), PlotError>;
+    /// Parses the expressions of the element again, calling the functions of `definitions`.
+    fn reparse_with(&mut self, definitions: &Definitions) -> Result<(), PlotError>;
+    fn name(&self) -> &str;
+    /// The error of the last parse of the expressions, if it failed.
+    fn error(&self) -> Option<&PlotError>;
+    /// Whether the expressions parsed once, an element whose saved text never did draws
+    /// nothing.
+    fn is_compiled(&self) -> bool;
+    /// Renames a parameter in the expressions of the element, to be parsed again afterwards.
+    fn rename_var(&mut self, old: &str, new: &str);
+}
+
+/// Variables of an expression of `variable` that are parameters, sorted and without the
+/// constants.
+pub fn parameters_of(parsed_expression: &Expression, variable: &str) -> Vec<String> {
+    let mut names = parsed_expression.variables();
+    names
+        .retain(|name| name != variable && !CONSTANTS.iter().any(|(constant, _)| constant == name));
+    names
+}
+
+/// Compiles expressions of `variable` sharing their parameters, returns them with the
+/// parameters of all of them.
+pub(crate) fn compile_all(
+    expressions: &[Expression],
+    variable: &str,
+) -> Result<(Vec<CompiledExpression>, Vec<String>), PlotError> {
+    let mut vars_names: Vec<String> = expressions
+        .iter()
+        .flat_map(|expression| parameters_of(expression, variable))
+        .collect();
+    vars_names.sort();
+    vars_names.dedup();
+    let compiled = expressions
+        .iter()
+        .map(|expression| CompiledExpression::with_variable(expression, variable, &vars_names))
+        .collect::<Result<_, _>>()?;
+    Ok((compiled, vars_names))
+}
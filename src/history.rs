@@ -0,0 +1,543 @@
+//! Undo and redo of the edits of a workspace.
+//!
+//! The edits are found by comparing the [`Document`] of the workspace between two checkpoints,
+//! each edit knowing how to apply and revert itself on a document.
+use std::collections::VecDeque;
+
+use crate::document::{
+    Document, FunctionEntry, GridElementEntry, ParameterEntry, ParametricCurveEntry,
+    PolarCurveEntry, Settings, View,
+};
+
+/// Number of edits that can be undone, the oldest ones are forgotten first.
+pub const HISTORY_LIMIT: usize = 100;
+
+/// Seconds without any edit after which the next edit starts a step of its own, for gestures
+/// without a release like scrolling to zoom.
+pub const MERGE_PAUSE: f64 = 1.0;
+
+/// A change of a workspace, undone by [`Edit::revert`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    AddFunction {
+        index: usize,
+        function: FunctionEntry,
+    },
+    RemoveFunction {
+        index: usize,
+        function: FunctionEntry,
+    },
+    RenameFunction {
+        index: usize,
+        old: String,
+        new: String,
+    },
+    EditExpression {
+        index: usize,
+        old: String,
+        new: String,
+    },
+    /// Any other change of a function, like its style or its sweep.
+    EditFunction {
+        index: usize,
+        old: FunctionEntry,
+        new: FunctionEntry,
+    },
+    /// An element other than a function, `index` being its position among those of its kind.
+    AddElement {
+        index: usize,
+        element: ElementEntry,
+    },
+    RemoveElement {
+        index: usize,
+        element: ElementEntry,
+    },
+    EditElement {
+        index: usize,
+        old: ElementEntry,
+        new: ElementEntry,
+    },
+    AddParameter {
+        index: usize,
+        parameter: ParameterEntry,
+    },
+    RemoveParameter {
+        index: usize,
+        parameter: ParameterEntry,
+    },
+    /// Any other change of a parameter, like its expression or its animation.
+    EditParameter {
+        index: usize,
+        old: ParameterEntry,
+        new: ParameterEntry,
+    },
+    SetParameter {
+        name: String,
+        old: f64,
+        new: f64,
+    },
+    /// The range and step of a parameter.
+    SetLimits {
+        name: String,
+        old: ((f64, f64), f64),
+        new: ((f64, f64), f64),
+    },
+    SetView {
+        old: View,
+        new: View,
+    },
+    SetSettings {
+        old: Settings,
+        new: Settings,
+    },
+    /// Any other change, or several at once.
+    Replace {
+        old: Box<Document>,
+        new: Box<Document>,
+    },
+}
+impl Edit {
+    /// The edit turning `before` into `after`, the most specific one that does.
+    pub fn between(before: &Document, after: &Document) -> Self {
+        let target = comparable(after);
+        Self::candidates(before, after)
+            .into_iter()
+            .find(|edit| {
+                let mut document = before.clone();
+                edit.apply(&mut document);
+                comparable(&document) == target
+            })
+            .unwrap_or_else(|| Self::Replace {
+                old: Box::new(before.clone()),
+                new: Box::new(after.clone()),
+            })
+    }
+    /// Edits that may explain the difference between `before` and `after`, to be checked.
+    fn candidates(before: &Document, after: &Document) -> Vec<Self> {
+        let mut candidates = vec![];
+        match ListChange::between(&before.functions, &after.functions) {
+            Some(ListChange::Add(index, function)) => candidates.push(Self::AddFunction {
+                index,
+                function: function.clone(),
+            }),
+            Some(ListChange::Remove(index, function)) => candidates.push(Self::RemoveFunction {
+                index,
+                function: function.clone(),
+            }),
+            Some(ListChange::Change(index, old, new)) => {
+                candidates.push(Self::RenameFunction {
+                    index,
+                    old: old.name.to_owned(),
+                    new: new.name.to_owned(),
+                });
+                candidates.push(Self::EditExpression {
+                    index,
+                    old: old.expression.to_owned(),
+                    new: new.expression.to_owned(),
+                });
+                candidates.push(Self::EditFunction {
+                    index,
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+            None => {}
+        }
+        candidates.extend(
+            ListChange::between(&before.parametric_curves, &after.parametric_curves)
+                .map(|change| change.element_edit(ElementEntry::ParametricCurve)),
+        );
+        candidates.extend(
+            ListChange::between(&before.polar_curves, &after.polar_curves)
+                .map(|change| change.element_edit(ElementEntry::PolarCurve)),
+        );
+        candidates.extend(
+            ListChange::between(&before.implicit_curves, &after.implicit_curves)
+                .map(|change| change.element_edit(ElementEntry::ImplicitCurve)),
+        );
+        candidates.extend(
+            ListChange::between(&before.regions, &after.regions)
+                .map(|change| change.element_edit(ElementEntry::Region)),
+        );
+        // the derived parameters follow their inputs, they are not edited themselves
+        let old_parameters = comparable_parameters(&before.parameters);
+        let new_parameters = comparable_parameters(&after.parameters);
+        match ListChange::between(&old_parameters, &new_parameters) {
+            Some(ListChange::Add(index, parameter)) => candidates.push(Self::AddParameter {
+                index,
+                parameter: parameter.clone(),
+            }),
+            Some(ListChange::Remove(index, parameter)) => candidates.push(Self::RemoveParameter {
+                index,
+                parameter: parameter.clone(),
+            }),
+            Some(ListChange::Change(index, old, new)) => {
+                if old.expression.is_none() && old.name == new.name {
+                    candidates.push(Self::SetParameter {
+                        name: old.name.to_owned(),
+                        old: old.value,
+                        new: new.value,
+                    });
+                    candidates.push(Self::SetLimits {
+                        name: old.name.to_owned(),
+                        old: (old.range, old.step),
+                        new: (new.range, new.step),
+                    });
+                }
+                candidates.push(Self::EditParameter {
+                    index,
+                    old: old.clone(),
+                    new: new.clone(),
+                });
+            }
+            None => {}
+        }
+        if before.view != after.view {
+            candidates.push(Self::SetView {
+                old: before.view,
+                new: after.view,
+            });
+        }
+        if before.settings != after.settings {
+            candidates.push(Self::SetSettings {
+                old: before.settings.clone(),
+                new: after.settings.clone(),
+            });
+        }
+        candidates
+    }
+    pub fn apply(&self, document: &mut Document) {
+        self.set(document, false);
+    }
+    pub fn revert(&self, document: &mut Document) {
+        self.set(document, true);
+    }
+    fn set(&self, document: &mut Document, revert: bool) {
+        match self {
+            Self::AddFunction { index, function } | Self::RemoveFunction { index, function } => {
+                let add = matches!(self, Self::AddFunction { .. }) != revert;
+                if add {
+                    document.functions.insert(*index, function.clone());
+                } else {
+                    document.functions.remove(*index);
+                }
+            }
+            Self::RenameFunction { index, old, new } => {
+                document.functions[*index].name = pick(revert, old, new).to_owned();
+            }
+            Self::EditExpression { index, old, new } => {
+                document.functions[*index].expression = pick(revert, old, new).to_owned();
+            }
+            Self::EditFunction { index, old, new } => {
+                document.functions[*index] = pick(revert, old, new).clone();
+            }
+            Self::AddElement { index, element } | Self::RemoveElement { index, element } => {
+                let add = matches!(self, Self::AddElement { .. }) != revert;
+                if add {
+                    element.insert(document, *index);
+                } else {
+                    element.remove(document, *index);
+                }
+            }
+            Self::EditElement { index, old, new } => {
+                let element = pick(revert, old, new);
+                element.remove(document, *index);
+                element.insert(document, *index);
+            }
+            Self::AddParameter { index, parameter }
+            | Self::RemoveParameter { index, parameter } => {
+                let add = matches!(self, Self::AddParameter { .. }) != revert;
+                if add {
+                    document.parameters.insert(*index, parameter.clone());
+                } else {
+                    document.parameters.remove(*index);
+                }
+            }
+            Self::EditParameter { index, old, new } => {
+                document.parameters[*index] = pick(revert, old, new).clone();
+            }
+            Self::SetParameter { name, old, new } => {
+                if let Some(parameter) = document.parameters.iter_mut().find(|p| &p.name == name) {
+                    parameter.value = *pick(revert, old, new);
+                }
+            }
+            Self::SetLimits { name, old, new } => {
+                if let Some(parameter) = document.parameters.iter_mut().find(|p| &p.name == name) {
+                    (parameter.range, parameter.step) = *pick(revert, old, new);
+                }
+            }
+            Self::SetView { old, new } => document.view = *pick(revert, old, new),
+            Self::SetSettings { old, new } => document.settings = pick(revert, old, new).clone(),
+            Self::Replace { old, new } => *document = pick(revert, old, new).as_ref().clone(),
+        }
+    }
+    /// Merges `next` into this edit when both change the same thing, like the steps of a drag.
+    fn merge(&mut self, next: &Self) -> bool {
+        match (self, next) {
+            (
+                Self::RenameFunction { index, new, .. },
+                Self::RenameFunction {
+                    index: next_index,
+                    new: next_new,
+                    ..
+                },
+            )
+            | (
+                Self::EditExpression { index, new, .. },
+                Self::EditExpression {
+                    index: next_index,
+                    new: next_new,
+                    ..
+                },
+            ) if index == next_index => {
+                *new = next_new.to_owned();
+                true
+            }
+            (
+                Self::SetParameter { name, new, .. },
+                Self::SetParameter {
+                    name: next_name,
+                    new: next_new,
+                    ..
+                },
+            ) if name == next_name => {
+                *new = *next_new;
+                true
+            }
+            (
+                Self::SetLimits { name, new, .. },
+                Self::SetLimits {
+                    name: next_name,
+                    new: next_new,
+                    ..
+                },
+            ) if name == next_name => {
+                *new = *next_new;
+                true
+            }
+            (Self::SetView { new, .. }, Self::SetView { new: next_new, .. }) => {
+                *new = *next_new;
+                true
+            }
+            (
+                Self::EditFunction { index, new, .. },
+                Self::EditFunction {
+                    index: next_index,
+                    new: next_new,
+                    ..
+                },
+            ) if index == next_index => {
+                *new = next_new.clone();
+                true
+            }
+            (
+                Self::EditElement { index, new, .. },
+                Self::EditElement {
+                    index: next_index,
+                    new: next_new,
+                    ..
+                },
+            ) if index == next_index && new.is_same_kind(next_new) => {
+                *new = next_new.clone();
+                true
+            }
+            (
+                Self::EditParameter { index, new, .. },
+                Self::EditParameter {
+                    index: next_index,
+                    new: next_new,
+                    ..
+                },
+            ) if index == next_index => {
+                *new = next_new.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// An element other than a function, as saved in the list of its kind.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ElementEntry {
+    ParametricCurve(ParametricCurveEntry),
+    PolarCurve(PolarCurveEntry),
+    ImplicitCurve(GridElementEntry),
+    Region(GridElementEntry),
+}
+impl ElementEntry {
+    fn insert(&self, document: &mut Document, index: usize) {
+        match self {
+            Self::ParametricCurve(curve) => document.parametric_curves.insert(index, curve.clone()),
+            Self::PolarCurve(curve) => document.polar_curves.insert(index, curve.clone()),
+            Self::ImplicitCurve(curve) => document.implicit_curves.insert(index, curve.clone()),
+            Self::Region(region) => document.regions.insert(index, region.clone()),
+        }
+    }
+    fn remove(&self, document: &mut Document, index: usize) {
+        match self {
+            Self::ParametricCurve(_) => {
+                document.parametric_curves.remove(index);
+            }
+            Self::PolarCurve(_) => {
+                document.polar_curves.remove(index);
+            }
+            Self::ImplicitCurve(_) => {
+                document.implicit_curves.remove(index);
+            }
+            Self::Region(_) => {
+                document.regions.remove(index);
+            }
+        }
+    }
+    fn is_same_kind(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// How a list of a document changed, at the first position where it differs.
+enum ListChange<'a, T> {
+    Add(usize, &'a T),
+    Remove(usize, &'a T),
+    Change(usize, &'a T, &'a T),
+}
+impl<'a, T: Clone + PartialEq> ListChange<'a, T> {
+    /// `None` if the lists are the same, the change to check otherwise.
+    fn between(old: &'a [T], new: &'a [T]) -> Option<Self> {
+        let index = old
+            .iter()
+            .zip(new)
+            .position(|(old, new)| old != new)
+            .unwrap_or(old.len().min(new.len()));
+        if new.len() == old.len() + 1 {
+            Some(Self::Add(index, &new[index]))
+        } else if new.len() + 1 == old.len() {
+            Some(Self::Remove(index, &old[index]))
+        } else {
+            Some(Self::Change(index, old.get(index)?, new.get(index)?))
+        }
+    }
+    /// The edit of a list of elements of the kind made by `entry`.
+    fn element_edit(self, entry: impl Fn(T) -> ElementEntry) -> Edit {
+        match self {
+            Self::Add(index, element) => Edit::AddElement {
+                index,
+                element: entry(element.clone()),
+            },
+            Self::Remove(index, element) => Edit::RemoveElement {
+                index,
+                element: entry(element.clone()),
+            },
+            Self::Change(index, old, new) => Edit::EditElement {
+                index,
+                old: entry(old.clone()),
+                new: entry(new.clone()),
+            },
+        }
+    }
+}
+
+fn pick<T>(revert: bool, old: T, new: T) -> T {
+    if revert {
+        old
+    } else {
+        new
+    }
+}
+
+/// The document without the values of the derived parameters, which follow the others.
+fn comparable(document: &Document) -> Document {
+    let mut document = document.clone();
+    document.parameters = comparable_parameters(&document.parameters);
+    document
+}
+
+fn comparable_parameters(parameters: &[ParameterEntry]) -> Vec<ParameterEntry> {
+    let mut parameters = parameters.to_vec();
+    for parameter in &mut parameters {
+        if parameter.expression.is_some() {
+            parameter.value = 0.0;
+        }
+    }
+    parameters
+}
+
+/// The edits that can be undone and redone, at most [`HISTORY_LIMIT`] of them.
+#[derive(Debug, Clone, Default)]
+pub struct History {
+    undo: VecDeque<Edit>,
+    redo: Vec<Edit>,
+    // the workspace at the last checkpoint
+    current: Option<Document>,
+    // whether the next edit may be merged into the last one
+    merge: bool,
+}
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+    /// Records the changes since the last checkpoint as an edit, merged into the previous one
+    /// when both change the same thing.
+    ///
+    /// The first checkpoint only remembers the workspace. Returns whether an edit was
+    /// recorded.
+    pub fn checkpoint(&mut self, document: Document) -> bool {
+        let Some(current) = &self.current else {
+            self.current = Some(document);
+            return false;
+        };
+        if *current == document {
+            return false;
+        }
+        let edit = Edit::between(current, &document);
+        self.current = Some(document);
+        self.redo.clear();
+        if self.merge {
+            if let Some(last) = self.undo.back_mut() {
+                if last.merge(&edit) {
+                    return true;
+                }
+            }
+        }
+        self.undo.push_back(edit);
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+        self.merge = true;
+        true
+    }
+    /// Ends the current step, the next edit is not merged into it, like at the end of a drag.
+    pub fn end_step(&mut self) {
+        self.merge = false;
+    }
+    /// Takes the workspace as the last checkpoint without recording an edit, for changes that
+    /// are not edits like a playing animation.
+    pub fn rebase(&mut self, document: Document) {
+        self.current = Some(document);
+        self.merge = false;
+    }
+    /// The workspace before the last edit, `None` if there is nothing to undo.
+    pub fn undo(&mut self) -> Option<Document> {
+        let edit = self.undo.pop_back()?;
+        let mut document = self.current.take().unwrap_or_default();
+        edit.revert(&mut document);
+        self.redo.push(edit);
+        self.rebase(document.clone());
+        Some(document)
+    }
+    /// The workspace after the last undone edit, `None` if there is nothing to redo.
+    pub fn redo(&mut self) -> Option<Document> {
+        let edit = self.redo.pop()?;
+        let mut document = self.current.take().unwrap_or_default();
+        edit.apply(&mut document);
+        self.undo.push_back(edit);
+        self.rebase(document.clone());
+        Some(document)
+    }
+}
@@ -0,0 +1,130 @@
+//! Where the parts of a figure go in an image, shared by the renderers so that the SVG and PNG
+//! images of a scene match.
+use egui::Color32;
+
+use crate::scene::{ticks, Scene};
+
+/// Space around the plot area for the tick labels, in pixels: left, right, top, bottom.
+const MARGINS: [f64; 4] = [56.0, 12.0, 12.0, 36.0];
+/// Roughly how many ticks each axis gets.
+const TICKS: usize = 8;
+pub(crate) const FONT_SIZE: f64 = 12.0;
+/// Length of the tick marks outside the plot area.
+pub(crate) const TICK_LENGTH: f64 = 5.0;
+/// Space between the plot area and the tick labels.
+pub(crate) const LABEL_GAP: f64 = 8.0;
+pub(crate) const GRID_COLOR: Color32 = Color32::from_rgb(0xdc, 0xdc, 0xdc);
+pub(crate) const AXIS_COLOR: Color32 = Color32::from_rgb(0x60, 0x60, 0x60);
+// points further away are drawn at this distance, they are clipped anyway
+const MAX_PIXELS: f64 = 1e6;
+
+/// Maps the coordinates of the plot to pixels of the image.
+pub(crate) struct Frame {
+    scene_x: (f64, f64),
+    scene_y: (f64, f64),
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+    /// The ticks of both axes, in the coordinates of the plot.
+    pub x_ticks: Vec<f64>,
+    pub y_ticks: Vec<f64>,
+}
+impl Frame {
+    pub fn new(scene: &Scene, width: u32, height: u32) -> Self {
+        let [left, right, top, bottom] = MARGINS;
+        Self {
+            scene_x: scene.xlim,
+            scene_y: scene.ylim,
+            left,
+            right: (width as f64 - right).max(left + 1.0),
+            top,
+            bottom: (height as f64 - bottom).max(top + 1.0),
+            x_ticks: ticks(scene.xlim, TICKS),
+            y_ticks: ticks(scene.ylim, TICKS),
+        }
+    }
+    pub fn width(&self) -> f64 {
+        self.right - self.left
+    }
+    pub fn height(&self) -> f64 {
+        self.bottom - self.top
+    }
+    pub fn x(&self, x: f64) -> f64 {
+        let (min, max) = self.scene_x;
+        let x = self.left + (x - min) / (max - min) * self.width();
+        x.clamp(-MAX_PIXELS, MAX_PIXELS)
+    }
+    pub fn y(&self, y: f64) -> f64 {
+        let (min, max) = self.scene_y;
+        let y = self.bottom - (y - min) / (max - min) * self.height();
+        y.clamp(-MAX_PIXELS, MAX_PIXELS)
+    }
+}
+
+/// The legend in the top right corner of the plot area, like the one of `egui_plot`.
+pub(crate) struct Legend<'a> {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub entries: Vec<(&'a str, Color32)>,
+}
+impl<'a> Legend<'a> {
+    const LINE_HEIGHT: f64 = FONT_SIZE + 6.0;
+
+    /// `None` when no item has a name.
+    pub fn new(frame: &Frame, scene: &'a Scene) -> Option<Self> {
+        let entries = scene.legend();
+        // the width of the text is estimated, fonts are not measured
+        let longest = entries.iter().map(|(name, _)| name.chars().count()).max()?;
+        let width = 36.0 + longest as f64 * FONT_SIZE * 0.6;
+        Some(Self {
+            x: frame.right - width - 8.0,
+            y: frame.top + 8.0,
+            width,
+            height: entries.len() as f64 * Self::LINE_HEIGHT + 8.0,
+            entries,
+        })
+    }
+    /// Height of the middle of the `k`-th entry.
+    pub fn center(&self, k: usize) -> f64 {
+        self.y + 4.0 + (k as f64 + 0.5) * Self::LINE_HEIGHT
+    }
+    /// Horizontal extent of the sample lines of the entries.
+    pub fn sample(&self) -> (f64, f64) {
+        (self.x + 6.0, self.x + 26.0)
+    }
+    /// Start of the names of the entries.
+    pub fn text_x(&self) -> f64 {
+        self.x + 32.0
+    }
+}
+
+/// The labels of evenly spaced ticks without trailing zeros, like `0.5` or `-2`, with as many
+/// decimals as their step needs.
+pub(crate) fn tick_labels(ticks: &[f64]) -> Vec<String> {
+    let step = match ticks {
+        [first, second, ..] => second - first,
+        [only] => only.abs(),
+        [] => return vec![],
+    };
+    let decimals = if step.is_finite() && step > 0.0 {
+        (-step.log10().floor()).max(0.0) as usize
+    } else {
+        0
+    };
+    let label = |value: &f64| {
+        let label = format!("{:.*}", decimals, value);
+        let label = if label.contains('.') {
+            label.trim_end_matches('0').trim_end_matches('.')
+        } else {
+            &label
+        };
+        match label {
+            "-0" => "0".to_owned(),
+            label => label.to_owned(),
+        }
+    };
+    ticks.iter().map(label).collect()
+}
@@ -1 +1,19 @@
-pub mod plotting;
\ No newline at end of file
+pub mod animation;
+pub mod cli;
+pub mod compiled;
+pub mod definitions;
+pub mod document;
+pub mod elements;
+pub mod error;
+pub mod evaluation;
+pub mod history;
+mod layout;
+pub mod parameters;
+pub mod parser;
+pub mod plotting;
+pub mod png;
+pub mod sampling;
+pub mod scene;
+pub mod svg;
+pub mod sweep;
+pub mod symbolic;
@@ -1,11 +1,32 @@
-
 #![warn(clippy::all, rust_2018_idioms)]
-#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release, see `attach_console`
 use eframe::egui;
+#[cfg(target_arch = "wasm32")]
+use plotting_rs::document::Document;
+#[cfg(not(target_arch = "wasm32"))]
+use plotting_rs::cli::{self, Command};
+#[cfg(not(target_arch = "wasm32"))]
+use plotting_rs::error::PlotError;
 use plotting_rs::plotting::PlottingApp;
+
 // When compiling natively:
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> eframe::Result<()> {
+    // the subcommands need no window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        #[cfg(windows)]
+        attach_console();
+        let result = Command::parse(&args).and_then(|command| command.run(&mut std::io::stdout()));
+        if let Err(error) = result {
+            eprintln!("{}", error);
+            if matches!(error, PlotError::Usage(_)) {
+                eprintln!("{}", cli::USAGE);
+            }
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
     let native_options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -16,10 +37,26 @@ fn main() -> eframe::Result<()> {
     eframe::run_native(
         "eframe template",
         native_options,
-        Box::new(|cc| Box::<PlottingApp>::default()),
+        Box::new(|cc| Box::new(PlottingApp::new(cc))),
     )
 }
 
+/// Writes the output of the subcommands to the console they were run from, a release build on
+/// Windows having none of its own.
+#[cfg(windows)]
+fn attach_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    // SAFETY: `AttachConsole` takes no pointer, it fails harmlessly when there is no console to
+    // attach to or one is already attached
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
 // When compiling to web using trunk:
 #[cfg(target_arch = "wasm32")]
 fn main() {
@@ -35,7 +72,31 @@ fn main() {
             .start(
                 "the_canvas_id", // hardcode it
                 web_options,
-                Box::new(|cc| Box::<PlottingApp>::default()),
+                Box::new(|cc| {
+                    // a shared link wins over the workspace of the last visit
+                    let window = web_sys::window();
+                    let shared = window
+                        .as_ref()
+                        .and_then(|window| window.location().hash().ok())
+                        .and_then(|hash| Document::from_fragment(&hash).ok());
+                    if let (Some(window), Some(_)) = (&window, &shared) {
+                        // once opened, the edits are saved in the storage and a reload has to
+                        // restore them instead of the shared workspace
+                        let location = window.location();
+                        let address = location.pathname().unwrap_or_default()
+                            + &location.search().unwrap_or_default();
+                        if let Ok(history) = window.history() {
+                            let state = wasm_bindgen::JsValue::NULL;
+                            history
+                                .replace_state_with_url(&state, "", Some(&address))
+                                .ok();
+                        }
+                    }
+                    Box::new(match shared {
+                        Some(document) => PlottingApp::from_document(&document),
+                        None => PlottingApp::new(cc),
+                    })
+                }),
             )
             .await
             .expect("failed to start eframe");
@@ -0,0 +1,379 @@
+//! Parameters shared by the elements of a plot.
+use crate::animation::Animation;
+use crate::compiled::CompiledExpression;
+use crate::error::PlotError;
+use crate::evaluation::{parameters_of, DEFAULT_VALUE_PARAMETER};
+use crate::parser::{self, Expression};
+
+/// A parameter whose value is computed from other parameters, like `omega = 2*pi*freq`.
+#[derive(Debug, Clone)]
+pub struct DerivedParameter {
+    pub expression: String,
+    // compiled without free variable, every variable being an input, `None` until the
+    // expression parses
+    compiled: Option<CompiledExpression>,
+    inputs: Vec<String>,
+    // error of the last edit of `expression`, the previous valid expression is kept meanwhile
+    error: Option<PlotError>,
+    // why the value could not be computed by the last `update_derived`
+    update_error: Option<PlotError>,
+}
+impl DerivedParameter {
+    pub fn new(expression: String) -> Result<Self, PlotError> {
+        let (compiled, inputs) = Self::parse(&expression)?;
+        Ok(Self {
+            expression,
+            compiled: Some(compiled),
+            inputs,
+            error: None,
+            update_error: None,
+        })
+    }
+    /// A parameter computed from the text `expression`, without value until it is parsed by
+    /// [`DerivedParameter::reparse`].
+    pub fn unparsed(expression: String) -> Self {
+        Self {
+            expression,
+            compiled: None,
+            inputs: vec![],
+            error: None,
+            update_error: None,
+        }
+    }
+    fn parse(expression: &str) -> Result<(CompiledExpression, Vec<String>), PlotError> {
+        let parsed = Expression::parse(expression)?;
+        let inputs = parameters_of(&parsed, "");
+        let compiled = CompiledExpression::with_variable(&parsed, "", &inputs)?;
+        Ok((compiled, inputs))
+    }
+    /// Names of the parameters the value is computed from, sorted.
+    pub fn inputs(&self) -> &[String] {
+        &self.inputs
+    }
+    /// The error of the last call to [`DerivedParameter::reparse`], or else why the value could
+    /// not be computed, like a dependency cycle.
+    pub fn error(&self) -> Option<&PlotError> {
+        self.error.as_ref().or(self.update_error.as_ref())
+    }
+    /// Parses `expression` again after it was edited.
+    ///
+    /// On failure the previous valid expression is kept and the error is stored, see
+    /// [`DerivedParameter::error`].
+    pub fn reparse(&mut self) -> Result<(), PlotError> {
+        match Self::parse(&self.expression) {
+            Ok((compiled, inputs)) => {
+                self.compiled = Some(compiled);
+                self.inputs = inputs;
+                self.error = None;
+                Ok(())
+            }
+            Err(error) => {
+                self.error = Some(error.clone());
+                Err(error)
+            }
+        }
+    }
+    /// The value for the values of the inputs, in the order of [`DerivedParameter::inputs`].
+    pub fn eval(&self, inputs: &[f64]) -> f64 {
+        let compiled = self.compiled.as_ref();
+        compiled.map_or(f64::NAN, |compiled| compiled.eval(f64::NAN, inputs))
+    }
+    /// Follows the renaming of one of its inputs.
+    fn rename_input(&mut self, old: &str, new: &str) {
+        self.expression = parser::rename_variable(&self.expression, old, new);
+        // the errors are stored in the parameter
+        self.reparse().ok();
+    }
+}
+
+/// A parameter shared by the elements of a plot.
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub value: f64,
+    /// Bounds of the value of a parameter set freely, `(min, max)`.
+    pub range: (f64, f64),
+    /// Change of the value per pixel dragged.
+    pub step: f64,
+    /// The expression the value is computed from, `None` for a parameter set freely.
+    pub derived: Option<DerivedParameter>,
+    /// Sweeps the value of a parameter set freely between the bounds of its range.
+    pub animation: Animation,
+}
+impl Parameter {
+    pub const DEFAULT_RANGE: (f64, f64) = (-10.0, 10.0);
+    pub const DEFAULT_STEP: f64 = 0.1;
+
+    /// A parameter set freely, with the default range and step.
+    pub fn new(value: f64) -> Self {
+        Self {
+            value,
+            range: Self::DEFAULT_RANGE,
+            step: Self::DEFAULT_STEP,
+            derived: None,
+            animation: Animation::default(),
+        }
+    }
+    pub fn is_derived(&self) -> bool {
+        self.derived.is_some()
+    }
+    /// Whether `(min, max)` can be the range of a parameter: finite bounds with `min <= max`.
+    pub fn is_valid_range((min, max): (f64, f64)) -> bool {
+        min.is_finite() && max.is_finite() && min <= max
+    }
+    /// The range `(min, max)` put back in order, the default range if a bound is not finite.
+    pub fn sorted_range((a, b): (f64, f64)) -> (f64, f64) {
+        if !a.is_finite() || !b.is_finite() {
+            Self::DEFAULT_RANGE
+        } else {
+            (a.min(b), a.max(b))
+        }
+    }
+    /// `value` moved inside the range, which never panics unlike [`f64::clamp`].
+    pub(crate) fn clamped(&self, value: f64) -> f64 {
+        let (min, max) = self.range;
+        value.max(min).min(max)
+    }
+}
+
+/// The shared parameters of a plot, keyed by their name and kept in the order they were added.
+#[derive(Debug, Clone, Default)]
+pub struct ParameterRegistry {
+    // names are unique
+    entries: Vec<(String, Parameter)>,
+}
+impl ParameterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    fn index(&self, name: &str) -> Option<usize> {
+        self.entries.iter().position(|(n, _)| n == name)
+    }
+    pub fn contains(&self, name: &str) -> bool {
+        self.index(name).is_some()
+    }
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(name, _)| name.as_str())
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Parameter)> {
+        self.entries
+            .iter()
+            .map(|(name, parameter)| (name.as_str(), parameter))
+    }
+    /// Iterates over the parameters to edit them, their names cannot change, see
+    /// [`ParameterRegistry::rename`].
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&str, &mut Parameter)> {
+        self.entries
+            .iter_mut()
+            .map(|(name, parameter)| (name.as_str(), parameter))
+    }
+    pub fn get(&self, name: &str) -> Option<&Parameter> {
+        self.index(name).map(|i| &self.entries[i].1)
+    }
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Parameter> {
+        self.index(name).map(|i| &mut self.entries[i].1)
+    }
+    pub fn value(&self, name: &str) -> Option<f64> {
+        self.get(name).map(|parameter| parameter.value)
+    }
+    /// Adds a parameter, or replaces the one with the same name, which is returned.
+    pub fn insert(&mut self, name: String, parameter: Parameter) -> Option<Parameter> {
+        match self.index(&name) {
+            Some(i) => Some(std::mem::replace(&mut self.entries[i].1, parameter)),
+            None => {
+                self.entries.push((name, parameter));
+                None
+            }
+        }
+    }
+    /// The parameter named `name`, added with `value` if it does not exist yet.
+    pub fn get_or_insert(&mut self, name: &str, value: f64) -> &mut Parameter {
+        let i = match self.index(name) {
+            Some(i) => i,
+            None => {
+                self.entries.push((name.to_owned(), Parameter::new(value)));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[i].1
+    }
+    /// Sets the value of a parameter set freely, clamped to its range.
+    ///
+    /// Returns `false` if the parameter does not exist or is derived, or if `value` is NaN.
+    pub fn set_value(&mut self, name: &str, value: f64) -> bool {
+        match self.get_mut(name) {
+            Some(parameter) if !parameter.is_derived() && !value.is_nan() => {
+                parameter.value = parameter.clamped(value);
+                true
+            }
+            _ => false,
+        }
+    }
+    /// Sets the range of a parameter, moving its value inside if it was not.
+    ///
+    /// Returns `false` if the parameter does not exist or the range is not valid, see
+    /// [`Parameter::is_valid_range`].
+    pub fn set_range(&mut self, name: &str, range: (f64, f64)) -> bool {
+        match self.get_mut(name) {
+            Some(parameter) if Parameter::is_valid_range(range) => {
+                parameter.range = range;
+                if !parameter.is_derived() {
+                    parameter.value = parameter.clamped(parameter.value);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+    pub fn remove(&mut self, name: &str) -> Option<Parameter> {
+        self.index(name).map(|i| self.entries.remove(i).1)
+    }
+    /// Renames a parameter, the derived parameters computed from it follow.
+    ///
+    /// Fails if `new` is not a valid name or is already taken, or if `old` does not exist.
+    pub fn rename(&mut self, old: &str, new: &str) -> Result<(), PlotError> {
+        if !matches!(Expression::parse(new), Ok(Expression::Variable(_))) {
+            return Err(PlotError::Parse {
+                message: format!("`{}` is not a valid name", new),
+                position: None,
+            });
+        }
+        if old == new {
+            return Ok(());
+        }
+        if self.contains(new) {
+            return Err(PlotError::DuplicateName(new.to_owned()));
+        }
+        let i = self
+            .index(old)
+            .ok_or_else(|| PlotError::UnboundVariable(old.to_owned()))?;
+        self.entries[i].0 = new.to_owned();
+        for (_, parameter) in &mut self.entries {
+            if let Some(derived) = &mut parameter.derived {
+                if derived.inputs().iter().any(|input| input == old) {
+                    derived.rename_input(old, new);
+                }
+            }
+        }
+        self.update_derived();
+        Ok(())
+    }
+    /// Adds the parameters among `names` that do not exist yet, with the default value.
+    pub fn add_missing<'a>(&mut self, names: impl IntoIterator<Item = &'a str>) {
+        for name in names {
+            self.get_or_insert(name, DEFAULT_VALUE_PARAMETER);
+        }
+    }
+    /// Adds the inputs of the derived parameters that do not exist yet.
+    pub fn add_missing_inputs(&mut self) {
+        let inputs: Vec<String> = self
+            .entries
+            .iter()
+            .filter_map(|(_, parameter)| parameter.derived.as_ref())
+            .flat_map(|derived| derived.inputs().to_vec())
+            .collect();
+        self.add_missing(inputs.iter().map(String::as_str));
+    }
+    /// Advances the animated parameters by `dt` seconds, returns whether any is still playing.
+    ///
+    /// Derived parameters follow their inputs and are never animated.
+    pub fn animate(&mut self, dt: f64) -> bool {
+        let mut playing = false;
+        for (_, parameter) in &mut self.entries {
+            if parameter.is_derived() {
+                continue;
+            }
+            parameter.value = parameter
+                .animation
+                .advance(parameter.value, parameter.range, dt);
+            playing |= parameter.animation.playing;
+        }
+        playing
+    }
+    /// Computes the value of every derived parameter from the other parameters.
+    ///
+    /// Derived parameters depending on each other are computed in dependency order, the ones
+    /// that cannot be computed keep their value and get an error, like
+    /// [`PlotError::CyclicDefinition`] for a cycle.
+    pub fn update_derived(&mut self) {
+        let mut resolver = Resolver {
+            states: vec![State::Pending; self.entries.len()],
+            entries: &mut self.entries,
+            stack: vec![],
+        };
+        for i in 0..resolver.entries.len() {
+            // the errors are stored in the parameters
+            resolver.visit(i).ok();
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Pending,
+    Visiting,
+    Done,
+}
+
+/// Depth first traversal of the dependencies of the derived parameters.
+struct Resolver<'a> {
+    entries: &'a mut [(String, Parameter)],
+    states: Vec<State>,
+    // the parameters being visited, outermost first
+    stack: Vec<usize>,
+}
+impl Resolver<'_> {
+    fn visit(&mut self, i: usize) -> Result<(), PlotError> {
+        match self.states[i] {
+            State::Done => return Ok(()),
+            State::Visiting => {
+                let start = self.stack.iter().position(|j| *j == i).unwrap_or(0);
+                let cycle = self.stack[start..]
+                    .iter()
+                    .chain([&i])
+                    .map(|j| self.entries[*j].0.to_owned())
+                    .collect();
+                return Err(PlotError::CyclicDefinition(cycle));
+            }
+            State::Pending => {}
+        }
+        let Some(inputs) = self.entries[i].1.derived.as_ref().map(|d| d.inputs.clone()) else {
+            self.states[i] = State::Done;
+            return Ok(());
+        };
+        self.states[i] = State::Visiting;
+        self.stack.push(i);
+        let input_values = inputs
+            .iter()
+            .map(|input| {
+                let j = self
+                    .entries
+                    .iter()
+                    .position(|(name, _)| name == input)
+                    .ok_or_else(|| PlotError::UnboundVariable(input.to_owned()))?;
+                self.visit(j)?;
+                Ok(self.entries[j].1.value)
+            })
+            .collect::<Result<Vec<f64>, PlotError>>();
+        self.stack.pop();
+        self.states[i] = State::Done;
+        let parameter = &mut self.entries[i].1;
+        let derived = parameter.derived.as_mut().expect("a derived parameter");
+        match input_values {
+            Ok(input_values) => {
+                parameter.value = derived.eval(&input_values);
+                derived.update_error = None;
+                Ok(())
+            }
+            Err(error) => {
+                derived.update_error = Some(error.clone());
+                Err(error)
+            }
+        }
+    }
+}
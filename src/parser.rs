@@ -1,180 +1,552 @@
-#[derive(Debug)]
+//! Tokenizer and recursive-descent parser turning text into an [`Expression`] tree.
+//!
+//! The arithmetic follows `meval`, and conditions the usual C-like rules. From lowest to highest
+//! precedence: `||`, `&&`, the comparisons `< <= > >= == !=` (which cannot be chained),
+//! `+ -` (left), `* / %` (left), unary `+ - !`, `^` (right). So `-x^2` is `-(x^2)` and
+//! `2^3^x` is `2^(3^x)`.
+//!
+//! Conditions evaluate to 1 when true and 0 when false. Besides `if(condition, then, else)`,
+//! piecewise definitions are written `{x < 0: -x, x >= 0: x^2}`, optionally ending with a value
+//! taken when no condition holds, as in `{x < 0: -x, x}`.
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Operator {
     Add,
     Subtract,
     Multiply,
     Divide,
+    Remainder,
     Pow,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
 }
 impl Operator {
-    fn from_string(string: char) -> Self {
-        match string {
-            '+' => Self::Add,
-            '-' => Self::Subtract,
-            '*' => Self::Multiply,
-            '/' => Self::Divide,
-            '^' => Self::Pow,
-            _ => panic!("Invalid operator"),
+    const ALL: [Self; 14] = [
+        Self::Add,
+        Self::Subtract,
+        Self::Multiply,
+        Self::Divide,
+        Self::Remainder,
+        Self::Pow,
+        Self::Less,
+        Self::LessOrEqual,
+        Self::Greater,
+        Self::GreaterOrEqual,
+        Self::Equal,
+        Self::NotEqual,
+        Self::And,
+        Self::Or,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Subtract => "-",
+            Self::Multiply => "*",
+            Self::Divide => "/",
+            Self::Remainder => "%",
+            Self::Pow => "^",
+            Self::Less => "<",
+            Self::LessOrEqual => "<=",
+            Self::Greater => ">",
+            Self::GreaterOrEqual => ">=",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::And => "&&",
+            Self::Or => "||",
         }
     }
-    fn to_char(&self) -> char {
+    /// Binding power, operators with a higher one are applied first.
+    pub fn precedence(self) -> u8 {
         match self {
-            Self::Add => '+',
-            Self::Subtract => '-',
-            Self::Multiply => '*',
-            Self::Divide => '/',
-            Self::Pow => '^',
+            Self::Or => 1,
+            Self::And => 2,
+            Self::Less
+            | Self::LessOrEqual
+            | Self::Greater
+            | Self::GreaterOrEqual
+            | Self::Equal
+            | Self::NotEqual => 3,
+            Self::Add | Self::Subtract => 4,
+            Self::Multiply | Self::Divide | Self::Remainder => 5,
+            Self::Pow => 7,
         }
     }
-    fn get_operator_list() -> Vec<char> {
-        vec!['+', '-', '*', '/', '^']
+    pub fn is_right_associative(self) -> bool {
+        self == Self::Pow
+    }
+    pub fn is_comparison(self) -> bool {
+        self.precedence() == 3
     }
-
 }
 
-pub struct Expression {
-    value: String,
-    children: Option<Vec<Expression>>,
-    operator: Option<Operator>
-    
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOperator {
+    Plus,
+    Minus,
+    Not,
 }
-impl Expression {
-    pub fn new(string_expression: String) -> Self {
-        // remove spaces
-        let string_expression = string_expression.replace(" ", "");
-        let (index, op) = Expression::get_index_and_operator_char(&string_expression);
-        if index.is_none() {
-            return Self {
-                value: string_expression.clone(),
-                children: None,
-                operator: None
-            };
-        }
-        let (lh, rh) = string_expression.split_at(index.expect("something went worng"));
-        let cropped_rh = &rh[1..];
-        println!(" ({})     {}    ({})", lh, op.expect("a"), &rh[1..]);
-        Self {
-            value: string_expression.clone(),
-            children: Some(vec![Expression::new(lh.to_owned()), Expression::new(cropped_rh.to_owned()) ]),
-            operator: Some(Operator::from_string(op.expect("could not parse operator"))),
-        }
-    }
-    fn get_index_and_operator_char(string_expression: &String) -> (Option<usize>, Option<char>) {
-        let mut index: Option<usize> = None;
-        let mut operator_char: Option<char> = None;
+impl UnaryOperator {
+    /// Binds tighter than `*` but looser than `^`.
+    pub const PRECEDENCE: u8 = 6;
 
-        for (i, c) in string_expression.chars().enumerate() {
-            if Operator::get_operator_list().contains(&c) {
-                index = Some(i);
-                operator_char = Some(c);
-                break;
-            }
+    pub fn to_char(self) -> char {
+        match self {
+            Self::Plus => '+',
+            Self::Minus => '-',
+            Self::Not => '!',
         }
-        return (index, operator_char);
     }
-    pub fn print_tree(expression: &Expression, depth: usize) {
-        // Create an indentation string based on the current depth
-        let indent = "  ".repeat(depth*2);
-    
-        if let Some(operator) = &expression.operator {
-            println!(" {}{}", indent, operator.to_char());
-        }
-    
-        if let Some(children) = &expression.children {
+}
 
-            // Print the branch and left child with increased indentation
-            println!("{}|  \\", indent);
-            println!("{}{}  {}", indent, children[0].value, children[1].value);
+/// A parsed expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    Variable(String),
+    Unary(UnaryOperator, Box<Expression>),
+    Binary(Operator, Box<Expression>, Box<Expression>),
+    Call(String, Vec<Expression>),
+    /// Parentheses written in the source, kept so the text can be reproduced.
+    Paren(Box<Expression>),
+    /// `{condition: value, ...}`, the value of the first condition that holds, or the optional
+    /// last value when none does.
+    Piecewise(Vec<(Expression, Expression)>, Option<Box<Expression>>),
+}
 
-            // Recursively call print_tree on the right child, increasing the depth
-            Expression::print_tree(&children[1], depth + 1);
-            }
+/// A problem found while parsing, `span` is the byte range of the input it refers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub span: Range<usize>,
+}
+impl ParseError {
+    fn new(message: impl Into<String>, span: Range<usize>) -> Self {
+        Self {
+            message: message.into(),
+            span,
         }
-    
-    
+    }
 }
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.message, self.span.start, self.span.end)
+    }
+}
+impl std::error::Error for ParseError {}
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Number(f64),
+    Identifier(String),
+    Operator(Operator),
+    Not,
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Colon,
+    Comma,
+}
 
-// pub struct Expression {
-//     current: Option<String>,
-//     left: Option<Box<Expression>>, // also principal
-//     right: Option<Box<Expression>>,
-//     operator: Option<Operator>,
-//     value: Option<String>,
-// }
-// impl Expression {
-//     pub fn new(string_expression: String) -> Self {
-//         // remove spaces
-//         let string_expression = string_expression.replace(" ", "");
-//         let (index, op) = Expression::get_index_and_operator_char(&string_expression);
-//         if index.is_none() {
-//             return Self {
-//                 current: Some(string_expression.clone()),
-//                 left: None,
-//                 right: None,
-//                 operator: None,
-//                 value: Some(string_expression),
-//             };
-//         }
-//         let (lh, rh) = string_expression.split_at(index.expect("something went worng"));
-//         let cropped_rh = &rh[1..];
-//         println!(" ({})     {}    ({})", lh, op.expect("a"), &rh[1..]);
-//         Self {
-//             current: Some(string_expression.clone()),
-//             left: Some(Box::new(Expression::new(lh.to_owned()))),
-//             right: Some(Box::new(Expression::new(cropped_rh.to_owned()))),
-//             operator: Some(Operator::from_string(op.expect("could not parse operator"))),
-//             value: None,
-//         }
-//     }
-//     fn get_index_and_operator_char(string_expression: &String) -> (Option<usize>, Option<char>) {
-//         let mut index: Option<usize> = None;
-//         let mut operator_char: Option<char> = None;
-
-//         for (i, c) in string_expression.chars().enumerate() {
-//             if Operator::get_operator_list().contains(&c) {
-//                 index = Some(i);
-//                 operator_char = Some(c);
-//                 break;
-//             }
-//         }
-//         return (index, operator_char);
-//     }
-//     pub fn print_tree(expresion: Expression) {
-//         //if let Some(operator) = expresion.operator;
-//         if expresion.operator.is_some(){
-//             println!("  {}", expresion.operator.expect("failed").to_char());
-//         }
-//         if expresion.left.is_some(){
-//             if expresion.right.is_some(){
-//                 println!("|    \\");
-//                 println!("{}  {}", expresion.left.expect("").current.expect(""), expresion.right.expect("").current.expect(""));
-//                 //let right =;
-//                 match expresion.right {
-//                     Some(exp_right) => Expression::print_tree(*exp_right),
-//                     None => return
-                    
-//                 };
-                
-//             }
-
-//         }
-
-        
-
-//     }
-// }
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
 
+/// Renames the variable `old` to `new` in the text of an expression, keeping the rest of the
+/// text as written. Calls to a function named `old` are left alone.
+///
+/// The text is returned unchanged when it cannot be split into tokens.
+pub fn rename_variable(input: &str, old: &str, new: &str) -> String {
+    let Ok(tokens) = tokenize(input) else {
+        return input.to_owned();
+    };
+    let mut renamed = String::with_capacity(input.len());
+    let mut end = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        let is_call = matches!(tokens.get(i + 1), Some(next) if next.kind == TokenKind::LParen);
+        if matches!(&token.kind, TokenKind::Identifier(name) if name == old) && !is_call {
+            renamed.push_str(&input[end..token.span.start]);
+            renamed.push_str(new);
+            end = token.span.end;
+        }
+    }
+    renamed.push_str(&input[end..]);
+    renamed
+}
 
-///////// in main
-// use cas::{self, Expression};
+/// Splits `input` into tokens, skipping whitespace.
+pub fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = input[i..].chars().next().expect("`i` is at a character boundary");
+        let start = i;
+        let kind = if c.is_ascii_whitespace() {
+            i += 1;
+            continue;
+        } else if c.is_ascii_digit() {
+            i = number_end(bytes, i);
+            let value = input[start..i]
+                .parse()
+                .map_err(|_| ParseError::new("invalid number", start..i))?;
+            TokenKind::Number(value)
+        } else if c.is_alphabetic() || c == '_' {
+            // letters other than ASCII ones are allowed, so `θ` can be a variable
+            let length: usize = input[start..]
+                .chars()
+                .take_while(|c| c.is_alphanumeric() || *c == '_')
+                .map(char::len_utf8)
+                .sum();
+            i += length;
+            TokenKind::Identifier(input[start..i].to_owned())
+        } else if let Some(op) = Operator::ALL
+            .into_iter()
+            .filter(|op| input[start..].starts_with(op.as_str()))
+            .max_by_key(|op| op.as_str().len())
+        {
+            // the longest operator matching, `<=` rather than `<`
+            i += op.as_str().len();
+            TokenKind::Operator(op)
+        } else {
+            i += c.len_utf8();
+            match c {
+                '(' => TokenKind::LParen,
+                ')' => TokenKind::RParen,
+                '{' => TokenKind::LBrace,
+                '}' => TokenKind::RBrace,
+                ':' => TokenKind::Colon,
+                ',' => TokenKind::Comma,
+                '!' => TokenKind::Not,
+                _ => {
+                    return Err(ParseError::new(
+                        format!("unexpected character `{}`", c),
+                        start..i,
+                    ));
+                }
+            }
+        };
+        tokens.push(Token {
+            kind,
+            span: start..i,
+        });
+    }
+    Ok(tokens)
+}
 
+/// End of the number starting at `i`: digits, an optional fraction and an optional exponent.
+fn number_end(bytes: &[u8], mut i: usize) -> usize {
+    let digits = |mut i: usize| {
+        while i < bytes.len() && bytes[i].is_ascii_digit() {
+            i += 1;
+        }
+        i
+    };
+    i = digits(i);
+    if bytes.get(i) == Some(&b'.') {
+        i = digits(i + 1);
+    }
+    if matches!(bytes.get(i), Some(b'e' | b'E')) {
+        let mut j = i + 1;
+        if matches!(bytes.get(j), Some(b'+' | b'-')) {
+            j += 1;
+        }
+        // only an exponent when digits follow, as in `meval`
+        if bytes.get(j).is_some_and(u8::is_ascii_digit) {
+            i = digits(j);
+        }
+    }
+    i
+}
 
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+    input_len: usize,
+}
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+    fn unexpected_end(&self, expected: &str) -> ParseError {
+        ParseError::new(
+            format!("expected {}, found the end of the expression", expected),
+            self.input_len..self.input_len,
+        )
+    }
+    /// Parses binary operations whose operators bind at least as tight as `min_precedence`.
+    fn expression(&mut self, min_precedence: u8) -> Result<Expression, ParseError> {
+        let mut left = self.prefix()?;
+        while let Some(Token {
+            kind: TokenKind::Operator(op),
+            span,
+        }) = self.peek()
+        {
+            let op = *op;
+            if op.precedence() < min_precedence {
+                break;
+            }
+            if op.is_comparison() && matches!(&left, Expression::Binary(l, ..) if l.is_comparison()) {
+                return Err(ParseError::new(
+                    "comparisons cannot be chained, combine them with `&&`",
+                    span.clone(),
+                ));
+            }
+            self.next();
+            let next_precedence = if op.is_right_associative() {
+                op.precedence()
+            } else {
+                op.precedence() + 1
+            };
+            let right = self.expression(next_precedence)?;
+            left = Expression::Binary(op, Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+    fn prefix(&mut self) -> Result<Expression, ParseError> {
+        let token = self.next();
+        let Some(Token { kind, span }) = token else {
+            return Err(self.unexpected_end("a number, a variable or `(`"));
+        };
+        match kind {
+            TokenKind::Number(value) => Ok(Expression::Number(value)),
+            TokenKind::Operator(op @ (Operator::Add | Operator::Subtract)) => {
+                let op = match op {
+                    Operator::Add => UnaryOperator::Plus,
+                    _ => UnaryOperator::Minus,
+                };
+                let operand = self.expression(UnaryOperator::PRECEDENCE)?;
+                Ok(Expression::Unary(op, Box::new(operand)))
+            }
+            TokenKind::Not => {
+                let operand = self.expression(UnaryOperator::PRECEDENCE)?;
+                Ok(Expression::Unary(UnaryOperator::Not, Box::new(operand)))
+            }
+            TokenKind::LParen => {
+                let inner = self.expression(0)?;
+                self.close_paren(span)?;
+                Ok(Expression::Paren(Box::new(inner)))
+            }
+            TokenKind::LBrace => self.piecewise(span),
+            TokenKind::Identifier(name) => {
+                if matches!(self.peek(), Some(Token { kind: TokenKind::LParen, .. })) {
+                    let open = self.next().expect("peeked").span;
+                    let args = self.arguments(open)?;
+                    Ok(Expression::Call(name, args))
+                } else {
+                    Ok(Expression::Variable(name))
+                }
+            }
+            TokenKind::Operator(_)
+            | TokenKind::RParen
+            | TokenKind::RBrace
+            | TokenKind::Colon
+            | TokenKind::Comma => Err(ParseError::new("expected a number, a variable or `(`", span)),
+        }
+    }
+    fn close_paren(&mut self, open: Range<usize>) -> Result<(), ParseError> {
+        match self.next() {
+            Some(Token {
+                kind: TokenKind::RParen,
+                ..
+            }) => Ok(()),
+            Some(token) => Err(ParseError::new("expected `)`", token.span)),
+            None => Err(ParseError::new("unclosed parenthesis", open)),
+        }
+    }
+    /// Parses the branches of a piecewise definition, after its `{`.
+    fn piecewise(&mut self, open: Range<usize>) -> Result<Expression, ParseError> {
+        let mut branches = vec![];
+        loop {
+            let condition = self.expression(0)?;
+            match self.next().map(|token| (token.kind, token.span)) {
+                Some((TokenKind::Colon, _)) => {
+                    let value = self.expression(0)?;
+                    branches.push((condition, value));
+                    match self.next().map(|token| (token.kind, token.span)) {
+                        Some((TokenKind::Comma, _)) => continue,
+                        Some((TokenKind::RBrace, _)) => {
+                            return Ok(Expression::Piecewise(branches, None))
+                        }
+                        Some((_, span)) => return Err(ParseError::new("expected `,` or `}`", span)),
+                        None => return Err(ParseError::new("unclosed brace", open)),
+                    }
+                }
+                // a last value without condition
+                Some((TokenKind::RBrace, _)) if !branches.is_empty() => {
+                    return Ok(Expression::Piecewise(branches, Some(Box::new(condition))))
+                }
+                Some((_, span)) => return Err(ParseError::new("expected `:`", span)),
+                None => return Err(ParseError::new("unclosed brace", open)),
+            }
+        }
+    }
+    fn arguments(&mut self, open: Range<usize>) -> Result<Vec<Expression>, ParseError> {
+        let mut args = vec![];
+        if matches!(self.peek(), Some(Token { kind: TokenKind::RParen, .. })) {
+            self.next();
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression(0)?);
+            match self.next() {
+                Some(Token {
+                    kind: TokenKind::Comma,
+                    ..
+                }) => continue,
+                Some(Token {
+                    kind: TokenKind::RParen,
+                    ..
+                }) => return Ok(args),
+                Some(token) => return Err(ParseError::new("expected `,` or `)`", token.span)),
+                None => return Err(ParseError::new("unclosed parenthesis", open)),
+            }
+        }
+    }
+}
 
-// fn main() {
-//     let a = cas::Operator::Add;
-//     println!("{:?}", a);
+impl Expression {
+    /// Parses `input` into an expression tree.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+            input_len: input.len(),
+        };
+        let expression = parser.expression(0)?;
+        match parser.next() {
+            None => Ok(expression),
+            Some(Token {
+                kind: TokenKind::RParen,
+                span,
+            }) => Err(ParseError::new("unmatched `)`", span)),
+            Some(token) => Err(ParseError::new("expected an operator", token.span)),
+        }
+    }
+    /// Names of the variables used, sorted and without duplicates.
+    pub fn variables(&self) -> Vec<String> {
+        let mut names = vec![];
+        self.visit(&mut |expression| {
+            if let Self::Variable(name) = expression {
+                names.push(name.to_owned());
+            }
+        });
+        names.sort();
+        names.dedup();
+        names
+    }
+    /// Calls `f` on this expression and on every sub-expression.
+    pub fn visit(&self, f: &mut impl FnMut(&Self)) {
+        f(self);
+        match self {
+            Self::Number(_) | Self::Variable(_) => {}
+            Self::Unary(_, operand) | Self::Paren(operand) => operand.visit(f),
+            Self::Binary(_, left, right) => {
+                left.visit(f);
+                right.visit(f);
+            }
+            Self::Call(_, args) => args.iter().for_each(|arg| arg.visit(f)),
+            Self::Piecewise(branches, otherwise) => {
+                for (condition, value) in branches {
+                    condition.visit(f);
+                    value.visit(f);
+                }
+                if let Some(otherwise) = otherwise {
+                    otherwise.visit(f);
+                }
+            }
+        }
+    }
+    /// Precedence of the outermost operation, used to decide where parentheses are needed.
+    fn precedence(&self) -> u8 {
+        match self {
+            Self::Binary(op, _, _) => op.precedence(),
+            Self::Unary(..) => UnaryOperator::PRECEDENCE,
+            Self::Number(value) if value.is_sign_negative() => UnaryOperator::PRECEDENCE,
+            _ => u8::MAX,
+        }
+    }
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        if self.precedence() < min_precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
 
-//     let exp = Expression::new("x^2 + 3*x + 1+a".to_owned());
-//     Expression::print_tree(&exp, 0);
-// }
\ No newline at end of file
+/// Prints the expression back as text, adding parentheses only where the tree needs them.
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(value) => write!(f, "{}", value),
+            Self::Variable(name) => write!(f, "{}", name),
+            Self::Paren(inner) => write!(f, "({})", inner),
+            Self::Unary(op, operand) => {
+                write!(f, "{}", op.to_char())?;
+                // `--x` is fine, but a negative number needs parentheses to stay a single token
+                let min_precedence = match operand.as_ref() {
+                    Self::Number(value) if value.is_sign_negative() => u8::MAX,
+                    _ => UnaryOperator::PRECEDENCE,
+                };
+                operand.fmt_operand(f, min_precedence)
+            }
+            Self::Binary(op, left, right) => {
+                let (left_min, right_min) = if op.is_right_associative() {
+                    (op.precedence() + 1, op.precedence())
+                } else {
+                    (op.precedence(), op.precedence() + 1)
+                };
+                left.fmt_operand(f, left_min)?;
+                match op {
+                    Operator::Pow => write!(f, "^")?,
+                    _ => write!(f, " {} ", op.as_str())?,
+                }
+                match right.as_ref() {
+                    // a sign can start any operand, as in `2^-x` or `a * -b`
+                    Self::Unary(..) => write!(f, "{}", right),
+                    _ => right.fmt_operand(f, right_min),
+                }
+            }
+            Self::Call(name, args) => {
+                write!(f, "{}(", name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg)?;
+                }
+                write!(f, ")")
+            }
+            Self::Piecewise(branches, otherwise) => {
+                write!(f, "{{")?;
+                for (i, (condition, value)) in branches.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", condition, value)?;
+                }
+                if let Some(otherwise) = otherwise {
+                    write!(f, ", {}", otherwise)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
@@ -1,180 +1,647 @@
+use std::ops::RangeInclusive;
+use std::path::Path;
+
 use eframe::egui;
 
-use egui::{Color32, Stroke, Vec2b};
-use egui_plot::{Legend, Line, Plot, PlotPoints};
-use meval::{Context, Expr};
+use egui::Vec2b;
+use egui_plot::{Legend, Line, Plot, PlotBounds, PlotPoints, Polygon};
 
-use meval::tokenizer::Token;
+use crate::animation::{Animation, AnimationMode};
+use crate::definitions::{self, Definitions};
+use crate::document::{
+    self, Document, FunctionEntry, GridElementEntry, ParameterEntry, ParametricCurveEntry,
+    PolarCurveEntry, Settings, View,
+};
+use crate::elements::{self, ImplicitCurve, ParametricCurve, PolarCurve, Region};
+use crate::error::PlotError;
+use crate::evaluation::{CurveStyle, Function, Parametrized, DEFAULT_VALUE_PARAMETER};
+use crate::history::{History, MERGE_PAUSE};
+use crate::parameters::{DerivedParameter, Parameter, ParameterRegistry};
+use crate::parser::Expression;
+use crate::png;
+use crate::scene::{auto_color, Item, Scene};
+use crate::svg;
+use crate::sweep::{ParameterSweep, SweepValues};
 
-const DEFAULT_VALUE_PARAMETER: f64 = 1.0;
-const DEFAULT_PARAMETERS_LIMIT: (f64, f64) = (-10.0, 10.0);
+/// A link opening the workspace of `fragment`, only the fragment itself on native builds.
+#[cfg(not(target_arch = "wasm32"))]
+fn link_to(fragment: &str) -> String {
+    format!("#{}", fragment)
+}
 
-#[derive(Debug, Clone)]
-struct Function {
-    name: String,
-    expression: String,
-    parsed_expression: Expr,
-    vars_names: Vec<String>,
-    vars_values: Vec<f64>, //bind_expression:
+/// A link to the current page opening the workspace of `fragment`.
+///
+/// The address of the page is left alone, a reload opens the workspace saved in the storage.
+#[cfg(target_arch = "wasm32")]
+fn link_to(fragment: &str) -> String {
+    let Some(location) = web_sys::window().map(|window| window.location()) else {
+        return format!("#{}", fragment);
+    };
+    let origin = location.origin().unwrap_or_default();
+    let path = location.pathname().unwrap_or_default();
+    format!("{}{}#{}", origin, path, fragment)
 }
-impl Function {
-    pub fn new(expression: String, name: String) -> Self {
-        let parsed_expression: Expr = expression.parse().expect("Could not parse the expression");
-        let (vars_names, vars_values) = Self::extract_vars(&parsed_expression);
-        Self {
-            name,
-            expression,
-            parsed_expression,
-            vars_names,
-            vars_values,
-        }
-    }
-    pub fn assign_value_to_var(&mut self, var_name: String, value: f64) {
-        let index = self.vars_names.iter().position(|x| x == &var_name);
-        match index {
-            Some(i) => self.vars_values[i] = value,
-            None => println!("Variable not found"),
-        }
-    }
-    pub fn reparse(&mut self){
-        self.parsed_expression = self.expression.parse().expect("Could not parse the expression");
-        let vars = Self::extract_vars(&self.parsed_expression);
-        self.vars_names = vars.0;
-        self.vars_values = vars.1;
 
-    } 
-    pub fn eval(&self, x: f64) -> f64 {
-        // if it is slow, look at mapping only one var x like in the example
-        // Apparently the library uses bind2, bind3, depending on the number of variables, investigate bindn
-        let parsed_expression = self.parsed_expression.clone();
-        match self.vars_names.len() {
-            0 => {
-                let f = parsed_expression.bind("x").unwrap();
-                return f(x);
-            }
-            1 => {
-                let f = parsed_expression.bind2("x", &self.vars_names[0]).unwrap();
-                return f(x, self.vars_values[0]);
-            }
-            2 => {
-                let f = parsed_expression
-                    .bind3("x", &self.vars_names[0], &self.vars_names[1])
-                    .unwrap();
-                return f(x, self.vars_values[0], self.vars_values[1]);
-            }
-            3 => {
-                let f = parsed_expression
-                    .bind4(
-                        "x",
-                        &self.vars_names[0],
-                        &self.vars_names[1],
-                        &self.vars_names[2],
-                    )
-                    .unwrap();
-                return f(
-                    x,
-                    self.vars_values[0],
-                    self.vars_values[1],
-                    self.vars_values[2],
-                );
-            }
-            4 => {
-                let f = parsed_expression
-                    .bind5(
-                        "x",
-                        &self.vars_names[0],
-                        &self.vars_names[1],
-                        &self.vars_names[2],
-                        &self.vars_names[3],
-                    )
-                    .unwrap();
-                return f(
-                    x,
-                    self.vars_values[0],
-                    self.vars_values[1],
-                    self.vars_values[2],
-                    self.vars_values[3],
-                );
-            }
-            _ => panic!("Too many number of parameters, maximum supported is 4 + x"),
-        }
-    }
-    pub fn extract_vars(parsed_expression: &Expr) -> (Vec<String>, Vec<f64>) {
-        let vars = parsed_expression.to_vec();
-        // keep only the names of Token::Var
-        let mut vars_names: Vec<String> = vars
-            .iter()
-            .filter_map(|x| match x {
-                Token::Var(name) => Some(name.to_string()),
-                _ => None,
-            })
-            .collect();
-        if !vars_names.contains(&"x".to_owned()) {
-            println!("It cannot plot anything without an x var")
-        }
-        vars_names.retain(|element| element != "x");
-        vars_names.sort();
-        vars_names.dedup();
-        // default var values to 1.0
-        let vars_values: Vec<f64> = vars_names.iter().map(|_| DEFAULT_VALUE_PARAMETER).collect();
-        return (vars_names, vars_values);
+/// Lets the browser download `text` as a file named `file_name` of the MIME type `mime`.
+#[cfg(target_arch = "wasm32")]
+fn download(file_name: &str, mime: &str, text: &str) -> Result<(), PlotError> {
+    use wasm_bindgen::JsCast;
+
+    let js_error = |error: wasm_bindgen::JsValue| PlotError::Document(format!("{:?}", error));
+    let parts = js_sys::Array::of1(&wasm_bindgen::JsValue::from_str(text));
+    let mut options = web_sys::BlobPropertyBag::new();
+    options.type_(mime);
+    let blob =
+        web_sys::Blob::new_with_str_sequence_and_options(&parts, &options).map_err(js_error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(js_error)?;
+    let document = web_sys::window()
+        .and_then(|window| window.document())
+        .ok_or_else(|| PlotError::Document("no page to download from".to_owned()))?;
+    let anchor: web_sys::HtmlAnchorElement = document
+        .create_element("a")
+        .map_err(js_error)?
+        .dyn_into()
+        .map_err(|element: web_sys::Element| js_error(element.into()))?;
+    anchor.set_href(&url);
+    anchor.set_download(file_name);
+    anchor.click();
+    web_sys::Url::revoke_object_url(&url).map_err(js_error)
+}
+
+/// `range` if both of its bounds are finite, `default` otherwise.
+fn finite_or(range: (f64, f64), default: (f64, f64)) -> (f64, f64) {
+    if range.0.is_finite() && range.1.is_finite() {
+        range
+    } else {
+        default
     }
 }
 
+/// `value` moved inside `bounds`, the ones of the widget editing it.
+fn clamp_to(value: usize, bounds: &RangeInclusive<usize>) -> usize {
+    value.clamp(*bounds.start(), *bounds.end())
+}
+
+/// The interactive plotting workspace.
+///
+/// It can be run on its own as an [`eframe::App`], or embedded in another app by calling
+/// [`PlottingApp::show`] every frame. Functions, parameters and the view can also be driven
+/// programmatically through its methods.
 pub struct PlottingApp {
     new_function_name: String,
+    new_function_error: Option<PlotError>,
     new_parameter_name: String,
+    new_parameter_error: Option<PlotError>,
     functions: Vec<Function>,
-    parameters_names: Vec<String>,
-    parameters_values: Vec<f64>,
-    parameters_lim: Vec<(f64,f64)>,
+    parametric_curves: Vec<ParametricCurve>,
+    polar_curves: Vec<PolarCurve>,
+    polar_grid: bool,
+    implicit_curves: Vec<ImplicitCurve>,
+    regions: Vec<Region>,
+    parameters: ParameterRegistry,
+    // the parameter being renamed and its new name as typed so far
+    parameter_rename: Option<(String, String)>,
+    // stops every animated parameter without losing which ones are playing
+    animations_paused: bool,
+    // why the last save, open or export failed
+    file_error: Option<PlotError>,
+    // size of the plot on screen in pixels, the size of the exported images
+    plot_size: [u32; 2],
+    history: History,
+    // time of the last edit recorded in the history, in seconds since the app started
+    last_edit_time: f64,
+    // the name and content of a file picked in the browser, it arrives a few frames later
+    #[cfg(target_arch = "wasm32")]
+    uploaded: std::rc::Rc<std::cell::RefCell<Option<(String, Vec<u8>)>>>,
     xlim: (f64, f64),
-    //ylim: (f64, f64)
+    ylim: (f64, f64),
+    // set when the view was changed programmatically and has to be pushed to the plot
+    view_changed: bool,
 }
 impl PlottingApp {
+    /// Creates a workspace without any function or parameter.
+    pub fn empty() -> Self {
+        Self {
+            new_function_name: "".to_owned(),
+            new_function_error: None,
+            new_parameter_name: "".to_owned(),
+            new_parameter_error: None,
+            xlim: (-10.0, 10.0),
+            ylim: (-10.0, 10.0),
+            functions: vec![],
+            parametric_curves: vec![],
+            polar_curves: vec![],
+            polar_grid: false,
+            implicit_curves: vec![],
+            regions: vec![],
+            parameters: ParameterRegistry::new(),
+            parameter_rename: None,
+            animations_paused: false,
+            file_error: None,
+            plot_size: [800, 600],
+            history: History::new(),
+            last_edit_time: 0.0,
+            #[cfg(target_arch = "wasm32")]
+            uploaded: Default::default(),
+            view_changed: false,
+        }
+    }
+    /// Registers the parameters used by the elements, or by the derived parameters, that are not
+    /// shared parameters yet.
     pub fn update_parameters_names_from_functions(&mut self) {
-        let mut parameters_names = self.parameters_names.clone();
-        let mut parameters_values = self.parameters_values.clone();
-        let mut parameters_lim = self.parameters_lim.clone();
-
-        for function in &self.functions {
-            let (function_parameter_names, function_parameter_values) =
-                Function::extract_vars(&function.parsed_expression);
-            parameters_names.append(&mut function_parameter_names.clone());
-            parameters_values.append(&mut function_parameter_values.clone());
-            parameters_lim.push(DEFAULT_PARAMETERS_LIMIT);
-        }
-        parameters_names.sort();
-        parameters_names.dedup();
-        // TODO: how to get rid of duplicates names without removing duplicated values
-        //parameters_values.sort();
-        //parameters_names.dedup();
-
-        self.parameters_names = parameters_names;
-        self.parameters_values = parameters_values;
-        self.parameters_lim = parameters_lim;
+        let names: Vec<String> = self
+            .parametrized()
+            .flat_map(|element| element.vars_names().to_vec())
+            .collect();
+        self.parameters.add_missing(names.iter().map(String::as_str));
+        self.parameters.add_missing_inputs();
+        self.sync_parameters_to_functions();
+    }
+    /// Parses every element again, so calls to the named functions follow their definitions.
+    fn reparse_elements(&mut self) {
+        let definitions = self.definitions();
+        for element in self.parametrized_mut() {
+            // on error the element keeps its last valid expression and shows the error
+            element.reparse_with(&definitions).ok();
+        }
+        self.update_parameters_names_from_functions();
+    }
+    /// Brings the workspace up to date after an element using the parameters `names` was
+    /// removed: the callers of a removed function are parsed again, and the parameters used by
+    /// nothing else go away with the element.
+    fn element_removed(&mut self, names: &[String]) {
+        self.reparse_elements();
+        let used: Vec<String> = self
+            .parametrized()
+            .flat_map(|element| element.vars_names().to_vec())
+            .collect();
+        for name in names {
+            let input = self.parameters.iter().any(|(_, parameter)| {
+                (parameter.derived.as_ref()).is_some_and(|derived| derived.inputs().contains(name))
+            });
+            let derived = self.parameters.get(name).is_some_and(Parameter::is_derived);
+            // a derived parameter was defined on purpose, it stays
+            if !used.contains(name) && !input && !derived {
+                self.parameters.remove(name);
+            }
+        }
+    }
+    /// The functions callable by name from the expressions of the elements, like `f1(x - 1)`.
+    pub fn definitions(&self) -> Definitions {
+        Definitions::from_functions(&self.functions)
+    }
+    /// Pushes the values of the shared parameters into every element using them, after
+    /// computing the derived parameters.
+    fn sync_parameters_to_functions(&mut self) {
+        self.parameters.update_derived();
+        let parameters: Vec<(String, f64)> = self
+            .parameters()
+            .map(|(name, value)| (name.to_owned(), value))
+            .collect();
+        for element in self.parametrized_mut() {
+            for (name, value) in &parameters {
+                // elements without this parameter just report it as unbound
+                element.assign_value_to_var(name.to_owned(), *value).ok();
+            }
+        }
+    }
+    /// Every element whose expressions use the shared parameters.
+    fn parametrized(&self) -> impl Iterator<Item = &dyn Parametrized> {
+        let functions = self.functions.iter().map(|f| f as &dyn Parametrized);
+        let curves = self.parametric_curves.iter().map(|c| c as &dyn Parametrized);
+        let polar_curves = self.polar_curves.iter().map(|c| c as &dyn Parametrized);
+        let implicit_curves = self.implicit_curves.iter().map(|c| c as &dyn Parametrized);
+        let regions = self.regions.iter().map(|r| r as &dyn Parametrized);
+        functions
+            .chain(curves)
+            .chain(polar_curves)
+            .chain(implicit_curves)
+            .chain(regions)
+    }
+    /// The first element, or derived parameter, whose expression does not parse, with its error.
+    pub fn first_error(&self) -> Option<(&str, &PlotError)> {
+        let elements = self
+            .parametrized()
+            .filter_map(|element| Some((element.name(), element.error()?)));
+        let parameters = self.parameters.iter().filter_map(|(name, parameter)| {
+            Some((name, parameter.derived.as_ref()?.error()?))
+        });
+        elements.chain(parameters).next()
+    }
+    fn parametrized_mut(&mut self) -> impl Iterator<Item = &mut dyn Parametrized> {
+        let functions = self.functions.iter_mut().map(|f| f as &mut dyn Parametrized);
+        let curves = self
+            .parametric_curves
+            .iter_mut()
+            .map(|c| c as &mut dyn Parametrized);
+        let polar_curves = self
+            .polar_curves
+            .iter_mut()
+            .map(|c| c as &mut dyn Parametrized);
+        let implicit_curves = self
+            .implicit_curves
+            .iter_mut()
+            .map(|c| c as &mut dyn Parametrized);
+        let regions = self.regions.iter_mut().map(|r| r as &mut dyn Parametrized);
+        functions
+            .chain(curves)
+            .chain(polar_curves)
+            .chain(implicit_curves)
+            .chain(regions)
+    }
+    pub fn functions(&self) -> &[Function] {
+        &self.functions
     }
-    fn add_new_function(&mut self, new_function: Function) {
+    /// Adds a function to the plot, registering any new parameter it uses.
+    ///
+    /// The elements calling the function by its name are updated.
+    pub fn add_function(&mut self, new_function: Function) {
         self.functions.push(new_function);
+        self.reparse_elements()
+    }
+    pub fn remove_function(&mut self, index: usize) -> Option<Function> {
+        if index < self.functions.len() {
+            let function = self.functions.remove(index);
+            self.element_removed(function.vars_names());
+            Some(function)
+        } else {
+            None
+        }
+    }
+    pub fn parametric_curves(&self) -> &[ParametricCurve] {
+        &self.parametric_curves
+    }
+    /// Adds a parametric curve to the plot, registering any new parameter it uses.
+    pub fn add_parametric_curve(&mut self, curve: ParametricCurve) {
+        self.parametric_curves.push(curve);
+        self.update_parameters_names_from_functions()
+    }
+    pub fn remove_parametric_curve(&mut self, index: usize) -> Option<ParametricCurve> {
+        if index < self.parametric_curves.len() {
+            let curve = self.parametric_curves.remove(index);
+            self.element_removed(curve.vars_names());
+            Some(curve)
+        } else {
+            None
+        }
+    }
+    pub fn polar_curves(&self) -> &[PolarCurve] {
+        &self.polar_curves
+    }
+    /// Adds a polar curve to the plot, registering any new parameter it uses.
+    pub fn add_polar_curve(&mut self, curve: PolarCurve) {
+        self.polar_curves.push(curve);
         self.update_parameters_names_from_functions()
     }
+    pub fn remove_polar_curve(&mut self, index: usize) -> Option<PolarCurve> {
+        if index < self.polar_curves.len() {
+            let curve = self.polar_curves.remove(index);
+            self.element_removed(curve.vars_names());
+            Some(curve)
+        } else {
+            None
+        }
+    }
+    pub fn implicit_curves(&self) -> &[ImplicitCurve] {
+        &self.implicit_curves
+    }
+    /// Adds an implicit curve to the plot, registering any new parameter it uses.
+    pub fn add_implicit_curve(&mut self, curve: ImplicitCurve) {
+        self.implicit_curves.push(curve);
+        self.update_parameters_names_from_functions()
+    }
+    pub fn remove_implicit_curve(&mut self, index: usize) -> Option<ImplicitCurve> {
+        if index < self.implicit_curves.len() {
+            let curve = self.implicit_curves.remove(index);
+            self.element_removed(curve.vars_names());
+            Some(curve)
+        } else {
+            None
+        }
+    }
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+    /// Adds a shaded region to the plot, registering any new parameter it uses.
+    pub fn add_region(&mut self, region: Region) {
+        self.regions.push(region);
+        self.update_parameters_names_from_functions()
+    }
+    pub fn remove_region(&mut self, index: usize) -> Option<Region> {
+        if index < self.regions.len() {
+            let region = self.regions.remove(index);
+            self.element_removed(region.vars_names());
+            Some(region)
+        } else {
+            None
+        }
+    }
+    pub fn polar_grid(&self) -> bool {
+        self.polar_grid
+    }
+    /// Draws a polar grid instead of the Cartesian one.
+    pub fn set_polar_grid(&mut self, polar_grid: bool) {
+        self.polar_grid = polar_grid;
+    }
+    /// Iterates over the shared parameters as `(name, value)` pairs.
+    pub fn parameters(&self) -> impl Iterator<Item = (&str, f64)> {
+        self.parameters
+            .iter()
+            .map(|(name, parameter)| (name, parameter.value))
+    }
+    /// The shared parameters with their range, step and expression if derived.
+    pub fn parameter_registry(&self) -> &ParameterRegistry {
+        &self.parameters
+    }
+    pub fn parameter(&self, name: &str) -> Option<f64> {
+        self.parameters.value(name)
+    }
+    /// Sets the value of a shared parameter, creating it if it does not exist yet.
+    ///
+    /// The value is clamped to the limits of the parameter. A derived parameter keeps the value
+    /// computed from its expression.
+    pub fn set_parameter(&mut self, name: &str, value: f64) {
+        if !self.parameters.set_value(name, value) && !self.parameters.contains(name) {
+            self.parameters.insert(name.to_owned(), Parameter::new(value));
+        }
+        self.sync_parameters_to_functions();
+    }
+    /// Defines a parameter as an expression of other parameters, like `2*pi*freq`, creating it
+    /// if it does not exist yet. `None` makes it a parameter set freely again.
+    ///
+    /// The inputs that are not parameters yet are created. The value is recomputed whenever
+    /// the inputs change.
+    pub fn set_parameter_expression(
+        &mut self,
+        name: &str,
+        expression: Option<&str>,
+    ) -> Result<(), PlotError> {
+        let derived = expression
+            .map(|expression| DerivedParameter::new(expression.to_owned()))
+            .transpose()?;
+        self.parameters
+            .get_or_insert(name, DEFAULT_VALUE_PARAMETER)
+            .derived = derived;
+        self.parameters.add_missing_inputs();
+        self.sync_parameters_to_functions();
+        Ok(())
+    }
+    /// The definition of a derived parameter, `None` for a parameter set freely.
+    pub fn derived_parameter(&self, name: &str) -> Option<&DerivedParameter> {
+        self.parameters
+            .get(name)
+            .and_then(|parameter| parameter.derived.as_ref())
+    }
+    pub fn parameter_limits(&self, name: &str) -> Option<(f64, f64)> {
+        self.parameters.get(name).map(|parameter| parameter.range)
+    }
+    /// Sets the limits of an existing parameter, returns `false` if it does not exist or if the
+    /// limits are reversed.
+    pub fn set_parameter_limits(&mut self, name: &str, limits: (f64, f64)) -> bool {
+        let set = self.parameters.set_range(name, limits);
+        self.sync_parameters_to_functions();
+        set
+    }
+    /// Removes a shared parameter.
+    ///
+    /// A parameter still used by an element or a derived parameter comes back right away, with
+    /// the default value.
+    pub fn remove_parameter(&mut self, name: &str) -> Option<Parameter> {
+        let removed = self.parameters.remove(name);
+        self.update_parameters_names_from_functions();
+        removed
+    }
+    /// Renames a shared parameter in the registry and in the expressions of every element and
+    /// derived parameter using it.
+    pub fn rename_parameter(&mut self, old: &str, new: &str) -> Result<(), PlotError> {
+        self.parameters.rename(old, new)?;
+        for element in self.parametrized_mut() {
+            element.rename_var(old, new);
+        }
+        self.reparse_elements();
+        Ok(())
+    }
+    /// Sets how a parameter is animated, returns `false` if it does not exist.
+    pub fn set_parameter_animation(&mut self, name: &str, animation: Animation) -> bool {
+        match self.parameters.get_mut(name) {
+            Some(parameter) => {
+                parameter.animation = animation;
+                true
+            }
+            None => false,
+        }
+    }
+    pub fn animations_paused(&self) -> bool {
+        self.animations_paused
+    }
+    /// Pauses or resumes every animated parameter at once.
+    pub fn set_animations_paused(&mut self, paused: bool) {
+        self.animations_paused = paused;
+    }
+    /// Moves the animated parameters `dt` seconds forward, returns whether any is still playing
+    /// and so whether the plot has to be drawn again.
+    pub fn animate(&mut self, dt: f64) -> bool {
+        let playing = |app: &Self| {
+            app.parameters
+                .iter()
+                .any(|(_, parameter)| parameter.animation.playing)
+        };
+        if self.animations_paused || !playing(self) {
+            return false;
+        }
+        let still_playing = self.parameters.animate(dt);
+        self.sync_parameters_to_functions();
+        still_playing
+    }
+    pub fn xlim(&self) -> (f64, f64) {
+        self.xlim
+    }
+    pub fn ylim(&self) -> (f64, f64) {
+        self.ylim
+    }
+    /// Moves the view of the plot, it is applied on the next frame.
+    pub fn set_view_bounds(&mut self, xlim: (f64, f64), ylim: (f64, f64)) {
+        self.xlim = xlim;
+        self.ylim = ylim;
+        self.view_changed = true;
+    }
+    /// The saved form of the workspace, see [`PlottingApp::from_document`].
+    pub fn to_document(&self) -> Document {
+        let functions = self
+            .functions
+            .iter()
+            .map(|function| FunctionEntry {
+                name: function.name.to_owned(),
+                expression: function.expression.to_owned(),
+                style: function.style,
+                show_derivative: function.show_derivative,
+                show_second_derivative: function.show_second_derivative,
+                sweep: function.sweep.clone(),
+            })
+            .collect();
+        let parametric_curves = self
+            .parametric_curves
+            .iter()
+            .map(|curve| ParametricCurveEntry {
+                name: curve.name.to_owned(),
+                x_expression: curve.x_expression.to_owned(),
+                y_expression: curve.y_expression.to_owned(),
+                t_range: curve.t_range,
+                samples: curve.samples,
+            })
+            .collect();
+        let polar_curves = self
+            .polar_curves
+            .iter()
+            .map(|curve| PolarCurveEntry {
+                name: curve.name.to_owned(),
+                expression: curve.expression.to_owned(),
+                theta_range: curve.theta_range,
+                samples: curve.samples,
+            })
+            .collect();
+        let implicit_curves = self
+            .implicit_curves
+            .iter()
+            .map(|curve| GridElementEntry {
+                name: curve.name.to_owned(),
+                expression: curve.expression.to_owned(),
+                resolution: curve.resolution,
+            })
+            .collect();
+        let regions = self
+            .regions
+            .iter()
+            .map(|region| GridElementEntry {
+                name: region.name.to_owned(),
+                expression: region.expression.to_owned(),
+                resolution: region.resolution,
+            })
+            .collect();
+        let parameters = self
+            .parameters
+            .iter()
+            .map(|(name, parameter)| ParameterEntry {
+                name: name.to_owned(),
+                value: parameter.value,
+                range: parameter.range,
+                step: parameter.step,
+                expression: parameter
+                    .derived
+                    .as_ref()
+                    .map(|derived| derived.expression.to_owned()),
+                animation: parameter.animation.clone(),
+            })
+            .collect();
+        Document {
+            version: document::CURRENT_VERSION,
+            functions,
+            parametric_curves,
+            polar_curves,
+            implicit_curves,
+            regions,
+            parameters,
+            view: View {
+                xlim: self.xlim,
+                ylim: self.ylim,
+            },
+            settings: Settings {
+                polar_grid: self.polar_grid,
+            },
+        }
+    }
+    /// Restores a workspace saved with [`PlottingApp::to_document`].
+    ///
+    /// The expressions are kept as they were saved, one that is not valid shows its error like
+    /// after an edit.
+    pub fn from_document(document: &Document) -> Self {
+        // the saved texts are parsed together at the end since they may call the functions, an
+        // element whose text does not parse keeps it with the error and draws nothing
+        let mut app = Self::empty();
+        for entry in &document.parameters {
+            let mut parameter = Parameter::new(entry.value);
+            // a range edited by hand may be reversed, a value set freely is kept inside it
+            parameter.range = Parameter::sorted_range(entry.range);
+            if entry.step.is_finite() && entry.step >= 0.0 {
+                parameter.step = entry.step;
+            }
+            parameter.animation = entry.animation.clone();
+            if !(parameter.animation.speed.is_finite() && parameter.animation.speed >= 0.0) {
+                parameter.animation.speed = Animation::DEFAULT_SPEED;
+            }
+            parameter.derived = entry.expression.as_ref().map(|expression| {
+                let mut derived = DerivedParameter::unparsed(expression.to_owned());
+                // on error the parameter shows it
+                derived.reparse().ok();
+                derived
+            });
+            if !parameter.is_derived() {
+                parameter.value = parameter.clamped(parameter.value);
+            }
+            app.parameters.insert(entry.name.to_owned(), parameter);
+        }
+        for entry in &document.functions {
+            let name = entry.name.to_owned();
+            let mut function = Function::unparsed(entry.expression.to_owned(), name);
+            function.style = entry.style;
+            let (min, max) = CurveStyle::WIDTH_RANGE.into_inner();
+            function.style.width = function.style.width.max(min).min(max);
+            function.show_derivative = entry.show_derivative;
+            function.show_second_derivative = entry.show_second_derivative;
+            function.sweep = entry.sweep.clone();
+            if let Some(SweepValues::Range { from, to, steps }) =
+                function.sweep.as_mut().map(|sweep| &mut sweep.values)
+            {
+                (*from, *to) = finite_or((*from, *to), Parameter::DEFAULT_RANGE);
+                *steps = clamp_to(*steps, &SweepValues::STEPS_RANGE);
+            }
+            app.functions.push(function);
+        }
+        for entry in &document.parametric_curves {
+            let (x, y) = (entry.x_expression.to_owned(), entry.y_expression.to_owned());
+            let mut curve = ParametricCurve::unparsed(x, y, entry.name.to_owned());
+            curve.t_range = finite_or(entry.t_range, ParametricCurve::DEFAULT_T_RANGE);
+            curve.samples = clamp_to(entry.samples, &ParametricCurve::SAMPLES_RANGE);
+            app.parametric_curves.push(curve);
+        }
+        for entry in &document.polar_curves {
+            let expression = entry.expression.to_owned();
+            let mut curve = PolarCurve::unparsed(expression, entry.name.to_owned());
+            curve.theta_range = finite_or(entry.theta_range, PolarCurve::DEFAULT_THETA_RANGE);
+            curve.samples = clamp_to(entry.samples, &PolarCurve::SAMPLES_RANGE);
+            app.polar_curves.push(curve);
+        }
+        for entry in &document.implicit_curves {
+            let expression = entry.expression.to_owned();
+            let mut curve = ImplicitCurve::unparsed(expression, entry.name.to_owned());
+            curve.resolution = clamp_to(entry.resolution, &ImplicitCurve::RESOLUTION_RANGE);
+            app.implicit_curves.push(curve);
+        }
+        for entry in &document.regions {
+            let expression = entry.expression.to_owned();
+            let mut region = Region::unparsed(expression, entry.name.to_owned());
+            region.resolution = clamp_to(entry.resolution, &Region::RESOLUTION_RANGE);
+            app.regions.push(region);
+        }
+        let view = document.view;
+        let is_valid = |(min, max): (f64, f64)| min.is_finite() && max.is_finite() && min < max;
+        if is_valid(view.xlim) && is_valid(view.ylim) {
+            app.set_view_bounds(view.xlim, view.ylim);
+        }
+        app.polar_grid = document.settings.polar_grid;
+        app.reparse_elements();
+        app
+    }
 }
 
 impl Default for PlottingApp {
     fn default() -> Self {
-        let f1 = Function::new("sin(x*a)*b".to_owned(), "f1".to_owned());
-        let f2 = Function::new("cos(x*a)/b".to_owned(), "f2".to_owned());
-        // let parameters_names = Self::get_parameters_names_from_functions(functions)
-        let mut out = Self {
-            new_function_name: "".to_owned(),
-            new_parameter_name: "".to_owned(),
-            xlim: (-10.0,10.0),
-            //ylim: (-10.0,10.0),
-            functions: vec![f1, f2],
-            parameters_names: vec![],
-            parameters_values: vec![],
-            parameters_lim: vec![]
-            };
+        let f1 = Function::new("sin(x*a)*b".to_owned(), "f1".to_owned())
+            .expect("the default expressions are valid");
+        let f2 = Function::new("cos(x*a)/b".to_owned(), "f2".to_owned())
+            .expect("the default expressions are valid");
+        let mut out = Self::empty();
+        out.functions = vec![f1, f2];
         out.update_parameters_names_from_functions();
         out
     }
@@ -182,10 +649,294 @@ impl Default for PlottingApp {
 
 impl eframe::App for PlottingApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.show(ctx);
+    }
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, Self::STORAGE_KEY, &self.to_document());
+    }
+}
+
+impl PlottingApp {
+    /// Key of the workspace in the storage of eframe.
+    pub const STORAGE_KEY: &'static str = "workspace";
+
+    /// Creates the app with the workspace of the last session, see [`PlottingApp::restore`].
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self::restore(cc.storage)
+    }
+    /// The workspace saved in `storage` by [`eframe::App::save`], or the default one if there
+    /// is none or it cannot be read.
+    pub fn restore(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage
+            .and_then(|storage| eframe::get_value::<Document>(storage, Self::STORAGE_KEY))
+            .and_then(|document| document.migrate().ok())
+            .map(|document| Self::from_document(&document))
+            .unwrap_or_default()
+    }
+    /// Goes back to the default workspace, this can be undone.
+    pub fn reset(&mut self) {
+        self.replace_workspace(Self::default());
+        // the plot keeps its bounds unless they are pushed to it
+        self.view_changed = true;
+    }
+    /// Takes the workspace of `app`, keeping the history so this can be undone.
+    fn replace_workspace(&mut self, app: Self) {
+        let history = std::mem::take(&mut self.history);
+        *self = app;
+        self.history = history;
+    }
+    /// Records the edits made since the last checkpoint as one step of the history, merged
+    /// with the previous step when both change the same thing, like the steps of a drag.
+    ///
+    /// Called by [`PlottingApp::show`] at the end of the frames with input, returns whether an
+    /// edit was recorded. The edits made through the methods of the app are recorded with the
+    /// next edit in the interface, unless this is called after them.
+    pub fn checkpoint(&mut self) -> bool {
+        self.history.checkpoint(self.to_document())
+    }
+    /// Ends the current step of the history, the next edit is a step of its own even if it
+    /// changes the same thing.
+    ///
+    /// Called by [`PlottingApp::show`] when the pointer is released, and after a pause of
+    /// [`MERGE_PAUSE`] seconds without edits.
+    pub fn end_step(&mut self) {
+        self.history.end_step();
+    }
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+    /// Reverts the last step of the history, returns `false` if there is none.
+    pub fn undo(&mut self) -> bool {
+        match self.history.undo() {
+            Some(document) => {
+                self.restore_document(&document);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Applies again the last undone step, returns `false` if there is none.
+    pub fn redo(&mut self) -> bool {
+        match self.history.redo() {
+            Some(document) => {
+                self.restore_document(&document);
+                true
+            }
+            None => false,
+        }
+    }
+    /// Takes the workspace of `document`, keeping the state of the interface like the text
+    /// typed in the fields, the errors shown and the playing animations.
+    fn restore_document(&mut self, document: &Document) {
+        let Self {
+            functions,
+            parametric_curves,
+            polar_curves,
+            polar_grid,
+            implicit_curves,
+            regions,
+            mut parameters,
+            xlim,
+            ylim,
+            ..
+        } = Self::from_document(document);
+        for (name, parameter) in parameters.iter_mut() {
+            if let Some(current) = self.parameters.get(name) {
+                parameter.animation.playing = current.animation.playing;
+            }
+        }
+        self.functions = functions;
+        self.parametric_curves = parametric_curves;
+        self.polar_curves = polar_curves;
+        self.polar_grid = polar_grid;
+        self.implicit_curves = implicit_curves;
+        self.regions = regions;
+        self.parameters = parameters;
+        if let Some((name, _)) = &self.parameter_rename {
+            if !self.parameters.contains(name) {
+                self.parameter_rename = None;
+            }
+        }
+        self.set_view_bounds(xlim, ylim);
+        // restoring may add what the document lacks, like a parameter still in use
+        self.history.rebase(self.to_document());
+    }
+}
+
+impl PlottingApp {
+    /// Saves the workspace to a file, as RON for a `.ron` extension and as JSON otherwise.
+    pub fn save_file(&self, path: &Path) -> Result<(), PlotError> {
+        self.to_document().save(path)
+    }
+    /// Opens a workspace saved with [`PlottingApp::save_file`].
+    pub fn open_file(path: &Path) -> Result<Self, PlotError> {
+        Document::load(path).map(|document| Self::from_document(&document))
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    fn file_buttons(&mut self, ui: &mut egui::Ui) {
+        let dialog = || rfd::FileDialog::new().add_filter("Workspace", &["json", "ron"]);
+        if ui
+            .button("💾 Save")
+            .on_hover_text("Save the workspace as JSON, or as RON with a .ron extension")
+            .clicked()
+        {
+            if let Some(path) = dialog().set_file_name("workspace.json").save_file() {
+                self.file_error = self.save_file(&path).err();
+            }
+        }
+        if ui.button("📂 Open").on_hover_text("Open a saved workspace").clicked() {
+            if let Some(path) = dialog().pick_file() {
+                match Self::open_file(&path) {
+                    Ok(app) => self.replace_workspace(app),
+                    Err(error) => self.file_error = Some(error),
+                }
+            }
+        }
+    }
+    #[cfg(target_arch = "wasm32")]
+    fn file_buttons(&mut self, ui: &mut egui::Ui) {
+        let uploaded = self.uploaded.borrow_mut().take();
+        if let Some((file_name, bytes)) = uploaded {
+            let format = document::Format::from_path(Path::new(&file_name));
+            let document = String::from_utf8(bytes)
+                .map_err(|error| PlotError::Document(error.to_string()))
+                .and_then(|text| Document::from_str(&text, format));
+            match document {
+                Ok(document) => self.replace_workspace(Self::from_document(&document)),
+                Err(error) => self.file_error = Some(error),
+            }
+        }
+        if ui
+            .button("💾 Download")
+            .on_hover_text("Download the workspace as JSON")
+            .clicked()
+        {
+            let text = self.to_document().to_string(document::Format::Json);
+            self.file_error = text
+                .and_then(|text| download("workspace.json", "application/json", &text))
+                .err();
+        }
+        if ui
+            .button("📂 Upload")
+            .on_hover_text("Open a saved workspace")
+            .clicked()
+        {
+            let uploaded = self.uploaded.clone();
+            let ctx = ui.ctx().clone();
+            wasm_bindgen_futures::spawn_local(async move {
+                let file = rfd::AsyncFileDialog::new()
+                    .add_filter("Workspace", &["json", "ron"])
+                    .pick_file()
+                    .await;
+                if let Some(file) = file {
+                    *uploaded.borrow_mut() = Some((file.file_name(), file.read().await));
+                    ctx.request_repaint();
+                }
+            });
+        }
+    }
+    fn export_buttons(&mut self, ui: &mut egui::Ui) {
+        if ui
+            .button("🖼 Export SVG")
+            .on_hover_text("Export the plot as it is shown as an SVG image")
+            .clicked()
+        {
+            let [width, height] = self.plot_size;
+            let svg = self.export_svg(width, height);
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                let dialog = rfd::FileDialog::new()
+                    .add_filter("SVG image", &["svg"])
+                    .set_file_name("plot.svg");
+                if let Some(path) = dialog.save_file() {
+                    self.file_error = std::fs::write(path, svg)
+                        .map_err(|error| PlotError::Export(error.to_string()))
+                        .err();
+                }
+            }
+            #[cfg(target_arch = "wasm32")]
+            {
+                self.file_error = download("plot.svg", "image/svg+xml", &svg).err();
+            }
+        }
+    }
+    /// The plot at its current bounds as an SVG image of `width` by `height` pixels.
+    pub fn export_svg(&self, width: u32, height: u32) -> String {
+        svg::render_svg(&Scene::new(self), width, height)
+    }
+    /// The plot at its current bounds as a PNG image of `width` by `height` pixels, rendered
+    /// on the CPU.
+    pub fn export_png(&self, width: u32, height: u32) -> Result<Vec<u8>, PlotError> {
+        png::render_png(&Scene::new(self), width, height)
+    }
+    /// Draws the side panel and the plot.
+    pub fn show(&mut self, ctx: &egui::Context) {
         //let idx_function_to_re
+        // a long pause between frames, like the first one played, does not make the values jump
+        let dt = ctx.input(|input| input.stable_dt).min(0.1);
+        let playing = self.animate(dt.into());
+        if playing {
+            ctx.request_repaint();
+            // the values moved by the animation are not edits to undo
+            self.history.rebase(self.to_document());
+        }
+        // a focused text field undoes its own edits
+        if !ctx.wants_keyboard_input() {
+            let shortcut = |modifiers| egui::KeyboardShortcut::new(modifiers, egui::Key::Z);
+            let redo = shortcut(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT);
+            // checked first, undo matches with shift held too
+            if ctx.input_mut(|input| input.consume_shortcut(&redo)) {
+                self.redo();
+            } else if ctx.input_mut(|input| input.consume_shortcut(&shortcut(egui::Modifiers::COMMAND))) {
+                self.undo();
+            }
+        }
         egui::SidePanel::left("my_left_panel").show(ctx, |ui| {
             let mut is_update_app_necessary = false;
             let mut show_parameter_name_warning: bool = false;
+            ui.horizontal(|ui| {
+                self.file_buttons(ui);
+                self.export_buttons(ui);
+                if ui
+                    .button("🔗 Copy link")
+                    .on_hover_text("Copy a link opening this workspace")
+                    .clicked()
+                {
+                    match self.to_document().to_fragment() {
+                        Ok(fragment) => {
+                            ui.ctx().output_mut(|output| output.copied_text = link_to(&fragment));
+                        }
+                        Err(error) => self.file_error = Some(error),
+                    }
+                }
+                if ui
+                    .button("⟲ Reset workspace")
+                    .on_hover_text("Go back to the default functions and parameters")
+                    .clicked()
+                {
+                    self.reset();
+                }
+                if ui
+                    .add_enabled(self.can_undo(), egui::Button::new("↶"))
+                    .on_hover_text("Undo (Ctrl+Z)")
+                    .clicked()
+                {
+                    self.undo();
+                }
+                if ui
+                    .add_enabled(self.can_redo(), egui::Button::new("↷"))
+                    .on_hover_text("Redo (Ctrl+Shift+Z)")
+                    .clicked()
+                {
+                    self.redo();
+                }
+            });
+            if let Some(error) = &self.file_error {
+                ui.colored_label(egui::Color32::RED, error.to_string());
+            }
             ui.heading("Elements");
             ui.vertical(|ui| {
                 let mut id_to_remove = None;
@@ -204,10 +955,26 @@ impl eframe::App for PlottingApp {
                                     id_to_remove = Some(i);
                                 }
 
+                                // other elements may call the function by its name
+                                is_update_app_necessary |= ui
+                                    .add(
+                                        egui::TextEdit::singleline(&mut function.name)
+                                            .desired_width(85.0),
+                                    )
+                                    .changed();
+                                let mut color = function.style.color.unwrap_or_else(|| {
+                                    let color = auto_color(i);
+                                    [color.r(), color.g(), color.b()]
+                                });
+                                if ui.color_edit_button_srgb(&mut color).changed() {
+                                    function.style.color = Some(color);
+                                }
                                 ui.add(
-                                    egui::TextEdit::singleline(&mut function.name)
-                                        .desired_width(85.0),
-                                );
+                                    egui::DragValue::new(&mut function.style.width)
+                                        .speed(0.1)
+                                        .clamp_range(CurveStyle::WIDTH_RANGE),
+                                )
+                                .on_hover_text("Line width");
                             });
                             // get id to avoid collisions based on same name
                             let id = ui.make_persistent_id(i);
@@ -222,25 +989,130 @@ impl eframe::App for PlottingApp {
                                         egui::TextEdit::singleline(&mut function.expression)
                                             .desired_width(100.0),
                                     );
-                                    if modified_response.clicked() {
-                                        // re-create function
-                                        function.reparse();
-                                        is_update_app_necessary=true;
-                                        //self.update_parameters_names_from_functions();
-
-                                        //self.functions[i] = Function::new(function.expression.to_owned(), function.name.to_owned())
+                                    // simplified when the expression is parsed, not every frame
+                                    let modified_response = match function.simplified() {
+                                        Some(simplified) => modified_response
+                                            .on_hover_text(format!("f(x) = {}", simplified)),
+                                        None => modified_response,
+                                    };
+                                    if modified_response.changed() {
+                                        // reparsed with the other elements, on error the last valid one keeps being drawn
+                                        is_update_app_necessary = true;
                                     }
                                 });
                                     
                                     
-                                    for (i, name) in function.vars_names.iter().enumerate() {
+                                    // the shared parameters, the function gets their values on the next sync
+                                    for name in function.vars_names.iter() {
+                                        let Some(parameter) = self.parameters.get_mut(name) else {
+                                            continue;
+                                        };
+                                        if parameter.is_derived() {
+                                            ui.label(format!("{}: {:.4}", name, parameter.value));
+                                            continue;
+                                        }
+                                        let ((min, max), step) = (parameter.range, parameter.step);
                                         ui.add(
-                                            egui::DragValue::new(&mut function.vars_values[i])
-                                                .speed(0.1)
-                                                .clamp_range(self.parameters_lim[i].0..=self.parameters_lim[i].1)
+                                            egui::DragValue::new(&mut parameter.value)
+                                                .speed(step)
+                                                .clamp_range(min..=max)
                                                 .prefix(format!("{}: ", name)),
                                         );
-                                        //TODO:  should we this update the app vars?
+                                    }
+                                });
+                            if let Some(error) = function.error() {
+                                ui.colored_label(egui::Color32::RED, error.to_string());
+                            }
+                            ui.horizontal(|ui| {
+                                let hover_text = |order: usize, label: &str| {
+                                    function
+                                        .derivative(order)
+                                        .map(|derivative| format!("{} = {}", label, derivative))
+                                };
+                                let hover_texts = [hover_text(1, "f'(x)"), hover_text(2, "f''(x)")];
+                                let checkboxes = [
+                                    (&mut function.show_derivative, "f'(x)"),
+                                    (&mut function.show_second_derivative, "f''(x)"),
+                                ];
+                                for ((show, label), hover_text) in checkboxes.into_iter().zip(hover_texts) {
+                                    let checkbox = ui.checkbox(show, label);
+                                    if let Some(hover_text) = hover_text {
+                                        checkbox.on_hover_text(hover_text);
+                                    }
+                                }
+                            });
+                            if function.show_derivative || function.show_second_derivative {
+                                if let Some(error) = function.derivative_error() {
+                                    ui.colored_label(egui::Color32::RED, error.to_string());
+                                }
+                            }
+                            egui::CollapsingHeader::new("Sweep")
+                                .id_source(ui.make_persistent_id(("sweep", i)))
+                                .show(ui, |ui| {
+                                    let mut enabled = function.sweep.is_some();
+                                    let checkbox = ui
+                                        .checkbox(&mut enabled, "Family of curves")
+                                        .on_hover_text("Draw the function once per value of a parameter");
+                                    if checkbox.changed() {
+                                        function.sweep = enabled.then(|| {
+                                            let parameter = function.vars_names.first().cloned().unwrap_or_default();
+                                            // the values span the limits of the parameter by default
+                                            let (from, to) = self
+                                                .parameters
+                                                .get(&parameter)
+                                                .map_or(Parameter::DEFAULT_RANGE, |parameter| parameter.range);
+                                            let steps = SweepValues::DEFAULT_STEPS;
+                                            ParameterSweep::new(parameter, SweepValues::Range { from, to, steps })
+                                        });
+                                    }
+                                    let Some(sweep) = &mut function.sweep else {
+                                        return;
+                                    };
+                                    egui::ComboBox::from_id_source(ui.make_persistent_id(("sweep parameter", i)))
+                                        .selected_text(sweep.parameter.as_str())
+                                        .show_ui(ui, |ui| {
+                                            for name in &function.vars_names {
+                                                ui.selectable_value(&mut sweep.parameter, name.to_owned(), name);
+                                            }
+                                        });
+                                    ui.horizontal(|ui| {
+                                        let is_range = matches!(sweep.values, SweepValues::Range { .. });
+                                        if ui.radio(is_range, "range").clicked() && !is_range {
+                                            let (from, to) = self
+                                                .parameters
+                                                .get(&sweep.parameter)
+                                                .map_or(Parameter::DEFAULT_RANGE, |parameter| parameter.range);
+                                            let steps = SweepValues::DEFAULT_STEPS;
+                                            sweep.values = SweepValues::Range { from, to, steps };
+                                        }
+                                        if ui.radio(!is_range, "list").clicked() && is_range {
+                                            let values = sweep.values.values().unwrap_or_default();
+                                            let text = values.iter().map(f64::to_string).collect::<Vec<_>>();
+                                            sweep.values = SweepValues::List(text.join(", "));
+                                        }
+                                    });
+                                    match &mut sweep.values {
+                                        SweepValues::Range { from, to, steps } => {
+                                            ui.horizontal(|ui| {
+                                                ui.add(egui::DragValue::new(from).speed(0.1).prefix("from: "));
+                                                ui.add(egui::DragValue::new(to).speed(0.1).prefix("to: "));
+                                                ui.add(
+                                                    egui::DragValue::new(steps)
+                                                        .clamp_range(SweepValues::STEPS_RANGE)
+                                                        .suffix(" curves"),
+                                                );
+                                            });
+                                        }
+                                        SweepValues::List(text) => {
+                                            ui.add(
+                                                egui::TextEdit::singleline(text)
+                                                    .hint_text("0.5, 1, 2")
+                                                    .desired_width(120.0),
+                                            );
+                                        }
+                                    }
+                                    if let Some(Err(error)) = function.sweep.as_ref().map(|sweep| sweep.family(function)) {
+                                        ui.colored_label(egui::Color32::RED, error.to_string());
                                     }
                                 });
 
@@ -249,80 +1121,351 @@ impl eframe::App for PlottingApp {
                         })
                     });
                 }
-                //let idx = 0;
-                let mut param_id_to_remove = None;
-                for (i, param_name) in self.parameters_names.iter().enumerate() {
-                    // let mut_values = &mut self.parameters_values.unwrap();
+                let mut curve_to_remove = None;
+                for (i, curve) in self.parametric_curves.iter_mut().enumerate() {
                     let frame = egui::Frame::default()
                         .inner_margin(4.0)
-                        .stroke(egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE)); // Black border
+                        .stroke(egui::Stroke::new(1.0, egui::Color32::LIGHT_GRAY));
                     frame.show(ui, |ui: &mut egui::Ui| {
-                        ui.horizontal(|ui| {
-
-                        if ui
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                if ui
                                     .button("⊗")
-                                    .on_hover_text("Remove this parameter")
+                                    .on_hover_text("Remove this curve")
                                     .clicked()
                                 {
-                                    param_id_to_remove = Some(i);
+                                    curve_to_remove = Some(i);
                                 }
-                    ui.add(
-                        egui::DragValue::new(&mut self.parameters_values[i])
-                            .speed(0.1)
-                            .clamp_range(self.parameters_lim[i].0..=self.parameters_lim[i].1)
-                            .prefix(format!("{}: ", param_name)),
-                    )});
-                    ui.vertical(|ui| {
-                    let id = ui.make_persistent_id(i+100);
-                    egui::CollapsingHeader::new("Parameters limits")
-                        .id_source(id)
-                        .show(ui, |ui| {
-                            // ui.push_id("a", |ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut curve.name)
+                                        .desired_width(85.0),
+                                );
+                            });
+                            let mut expression_changed = false;
+                            for (label, expression) in [
+                                ("x(t) = ", &mut curve.x_expression),
+                                ("y(t) = ", &mut curve.y_expression),
+                            ] {
+                                ui.horizontal(|ui| {
+                                    ui.label(label);
+                                    expression_changed |= ui
+                                        .add(
+                                            egui::TextEdit::singleline(expression)
+                                                .desired_width(100.0),
+                                        )
+                                        .changed();
+                                });
+                            }
+                            // on error the last valid curve keeps being drawn
+                            is_update_app_necessary |= expression_changed;
                             ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut curve.t_range.0).speed(0.1));
+                                ui.label("≤ t ≤");
+                                ui.add(egui::DragValue::new(&mut curve.t_range.1).speed(0.1));
+                            });
+                            ui.add(
+                                egui::DragValue::new(&mut curve.samples)
+                                    .clamp_range(ParametricCurve::SAMPLES_RANGE)
+                                    .prefix("samples: "),
+                            );
+                            if let Some(error) = curve.error() {
+                                ui.colored_label(egui::Color32::RED, error.to_string());
+                            }
+                            ui.add_space(10.0);
+                        })
+                    });
+                }
+                if let Some(index) = curve_to_remove {
+                    self.remove_parametric_curve(index);
+                }
+                let mut polar_to_remove = None;
+                for (i, curve) in self.polar_curves.iter_mut().enumerate() {
+                    let frame = egui::Frame::default()
+                        .inner_margin(4.0)
+                        .stroke(egui::Stroke::new(1.0, egui::Color32::LIGHT_GRAY));
+                    frame.show(ui, |ui: &mut egui::Ui| {
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .button("⊗")
+                                    .on_hover_text("Remove this curve")
+                                    .clicked()
+                                {
+                                    polar_to_remove = Some(i);
+                                }
                                 ui.add(
-                                    egui::DragValue::new(&mut self.parameters_lim[i].0)
-                                        .speed(0.1)
-                                        .speed(0.1)
-                                        .clamp_range(f64::NEG_INFINITY..=f64::INFINITY)
+                                    egui::TextEdit::singleline(&mut curve.name)
+                                        .desired_width(85.0),
                                 );
-                                ui.label(format!("≤ {} ≤ ", self.parameters_names[i]));
-                                //ui.wrap_text()
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label("r(θ) = ");
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut curve.expression)
+                                        .desired_width(100.0),
+                                );
+                                // on error the last valid curve keeps being drawn
+                                is_update_app_necessary |= response.changed();
+                            });
+                            ui.horizontal(|ui| {
+                                ui.add(egui::DragValue::new(&mut curve.theta_range.0).speed(0.1));
+                                ui.label("≤ θ ≤");
+                                ui.add(egui::DragValue::new(&mut curve.theta_range.1).speed(0.1));
+                            });
+                            ui.add(
+                                egui::DragValue::new(&mut curve.samples)
+                                    .clamp_range(PolarCurve::SAMPLES_RANGE)
+                                    .prefix("samples: "),
+                            );
+                            if let Some(error) = curve.error() {
+                                ui.colored_label(egui::Color32::RED, error.to_string());
+                            }
+                            ui.add_space(10.0);
+                        })
+                    });
+                }
+                if let Some(index) = polar_to_remove {
+                    self.remove_polar_curve(index);
+                }
+                let mut implicit_to_remove = None;
+                for (i, curve) in self.implicit_curves.iter_mut().enumerate() {
+                    let frame = egui::Frame::default()
+                        .inner_margin(4.0)
+                        .stroke(egui::Stroke::new(1.0, egui::Color32::LIGHT_GRAY));
+                    frame.show(ui, |ui: &mut egui::Ui| {
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .button("⊗")
+                                    .on_hover_text("Remove this curve")
+                                    .clicked()
+                                {
+                                    implicit_to_remove = Some(i);
+                                }
                                 ui.add(
-                                    egui::DragValue::new(&mut self.parameters_lim[i].1)
-                                        .speed(0.1)
-                                        .clamp_range(f64::NEG_INFINITY..=f64::INFINITY)
-                                        
+                                    egui::TextEdit::singleline(&mut curve.name)
+                                        .desired_width(85.0),
+                                );
+                            });
+                            let response = ui.add(
+                                egui::TextEdit::singleline(&mut curve.expression)
+                                    .hint_text("x^2 + y^2 = 4")
+                                    .desired_width(140.0),
+                            );
+                            // on error the last valid curve keeps being drawn
+                            is_update_app_necessary |= response.changed();
+                            ui.add(
+                                egui::DragValue::new(&mut curve.resolution)
+                                    .clamp_range(ImplicitCurve::RESOLUTION_RANGE)
+                                    .prefix("grid: "),
+                            );
+                            if let Some(error) = curve.error() {
+                                ui.colored_label(egui::Color32::RED, error.to_string());
+                            }
+                            ui.add_space(10.0);
+                        })
+                    });
+                }
+                if let Some(index) = implicit_to_remove {
+                    self.remove_implicit_curve(index);
+                }
+                let mut region_to_remove = None;
+                for (i, region) in self.regions.iter_mut().enumerate() {
+                    let frame = egui::Frame::default()
+                        .inner_margin(4.0)
+                        .stroke(egui::Stroke::new(1.0, egui::Color32::LIGHT_GRAY));
+                    frame.show(ui, |ui: &mut egui::Ui| {
+                        ui.vertical(|ui| {
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .button("⊗")
+                                    .on_hover_text("Remove this region")
+                                    .clicked()
+                                {
+                                    region_to_remove = Some(i);
+                                }
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut region.name)
+                                        .desired_width(85.0),
+                                );
+                            });
+                            let response = ui.add(
+                                egui::TextEdit::singleline(&mut region.expression)
+                                    .hint_text("y > x^2 - a")
+                                    .desired_width(140.0),
+                            );
+                            // on error the last valid region keeps being drawn
+                            is_update_app_necessary |= response.changed();
+                            ui.add(
+                                egui::DragValue::new(&mut region.resolution)
+                                    .clamp_range(Region::RESOLUTION_RANGE)
+                                    .prefix("grid: "),
+                            );
+                            if let Some(error) = region.error() {
+                                ui.colored_label(egui::Color32::RED, error.to_string());
+                            }
+                            ui.add_space(10.0);
+                        })
+                    });
+                }
+                if let Some(index) = region_to_remove {
+                    self.remove_region(index);
+                }
+                if self.parameters.iter().any(|(_, parameter)| parameter.animation.playing) {
+                    let (label, hover_text) = if self.animations_paused {
+                        ("▶ Resume all", "Resume the animated parameters")
+                    } else {
+                        ("⏸ Pause all", "Pause every animated parameter")
+                    };
+                    if ui.button(label).on_hover_text(hover_text).clicked() {
+                        self.animations_paused = !self.animations_paused;
+                    }
+                }
+                let mut param_to_remove = None;
+                let mut param_to_rename = None;
+                let mut derived_changed = false;
+                for (i, (param_name, parameter)) in self.parameters.iter_mut().enumerate() {
+                    let frame = egui::Frame::default()
+                        .inner_margin(4.0)
+                        .stroke(egui::Stroke::new(1.0, egui::Color32::LIGHT_BLUE));
+                    frame.show(ui, |ui: &mut egui::Ui| {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("⊗")
+                                .on_hover_text("Remove this parameter")
+                                .clicked()
+                            {
+                                param_to_remove = Some(param_name.to_owned());
+                            }
+                            match &mut self.parameter_rename {
+                                Some((old, new)) if old == param_name => {
+                                    let response = ui.add(
+                                        egui::TextEdit::singleline(new).desired_width(60.0),
+                                    );
+                                    if ui.button("✔").on_hover_text("Rename").clicked()
+                                        || response.lost_focus()
+                                    {
+                                        param_to_rename = Some((old.to_owned(), new.to_owned()));
+                                    }
+                                }
+                                _ => {
+                                    if ui
+                                        .button("✏")
+                                        .on_hover_text("Rename this parameter everywhere it is used")
+                                        .clicked()
+                                    {
+                                        self.parameter_rename =
+                                            Some((param_name.to_owned(), param_name.to_owned()));
+                                    }
+                                }
+                            }
+                            if let Some(derived) = &mut parameter.derived {
+                                // computed from the other parameters, only its expression can be edited
+                                ui.label(format!("{} = ", param_name));
+                                let response = ui.add(
+                                    egui::TextEdit::singleline(&mut derived.expression)
+                                        .desired_width(80.0),
+                                );
+                                // on error the last valid expression keeps being used
+                                if response.changed() && derived.reparse().is_ok() {
+                                    derived_changed = true;
+                                }
+                                ui.label(format!("= {:.4}", parameter.value));
+                            } else {
+                                let (label, hover_text) = if parameter.animation.playing {
+                                    ("⏸", "Stop sweeping the value")
+                                } else {
+                                    ("▶", "Sweep the value between its limits")
+                                };
+                                if ui.button(label).on_hover_text(hover_text).clicked() {
+                                    parameter.animation.toggle();
+                                }
+                                let (min, max) = parameter.range;
+                                ui.add(
+                                    egui::DragValue::new(&mut parameter.value)
+                                        .speed(parameter.step)
+                                        .clamp_range(min..=max)
+                                        .prefix(format!("{}: ", param_name)),
                                 );
-        
-                                
                             }
-
-                            )
                         });
-                });
-                });
-                    // for all the functions, modify the var name values
-                    for function in self.functions.iter_mut() {
-                        if function.vars_names.contains(param_name) {
-                            function.assign_value_to_var(
-                                param_name.to_owned(),
-                                self.parameters_values[i],
-                            )
+                        if let Some(error) = parameter.derived.as_ref().and_then(|d| d.error()) {
+                            ui.colored_label(egui::Color32::RED, error.to_string());
                         }
-                    }
+                        // the limits only apply to the parameters set freely
+                        if !parameter.is_derived() {
+                            egui::CollapsingHeader::new("Parameters limits")
+                                .id_source(ui.make_persistent_id(i + 100))
+                                .show(ui, |ui| {
+                                    ui.horizontal(|ui| {
+                                        // each limit stays on its side of the other
+                                        let (min, max) = parameter.range;
+                                        ui.add(
+                                            egui::DragValue::new(&mut parameter.range.0)
+                                                .speed(0.1)
+                                                .clamp_range(f64::MIN..=max),
+                                        );
+                                        ui.label(format!("≤ {} ≤ ", param_name));
+                                        ui.add(
+                                            egui::DragValue::new(&mut parameter.range.1)
+                                                .speed(0.1)
+                                                .clamp_range(min..=f64::MAX),
+                                        );
+                                        parameter.value = parameter.clamped(parameter.value);
+                                    });
+                                    ui.add(
+                                        egui::DragValue::new(&mut parameter.step)
+                                            .speed(0.01)
+                                            .clamp_range(0.0..=f64::INFINITY)
+                                            .prefix("step: "),
+                                    );
+                                    ui.horizontal(|ui| {
+                                        let animation = &mut parameter.animation;
+                                        egui::ComboBox::from_id_source(ui.make_persistent_id(i + 200))
+                                            .selected_text(animation.mode.label())
+                                            .width(70.0)
+                                            .show_ui(ui, |ui| {
+                                                for mode in AnimationMode::ALL {
+                                                    ui.selectable_value(&mut animation.mode, mode, mode.label());
+                                                }
+                                            });
+                                        ui.add(
+                                            egui::DragValue::new(&mut animation.speed)
+                                                .speed(0.05)
+                                                .clamp_range(0.0..=f64::INFINITY)
+                                                .prefix("speed: ")
+                                                .suffix("/s"),
+                                        );
+                                    });
+                                });
+                        }
+                    });
                 }
 
                 // remove id_to_remove from elements
                 if let Some(index) = id_to_remove {
-                    self.functions.remove(index);
+                    self.remove_function(index);
                 }
-                if let Some(index) = param_id_to_remove {
-                    self.parameters_names.remove(index);
-                    self.parameters_values.remove(index);
+                if let Some(name) = param_to_remove {
+                    self.remove_parameter(&name);
+                }
+                if let Some((old, new)) = param_to_rename {
+                    match self.rename_parameter(&old, new.trim()) {
+                        Ok(()) => {
+                            self.parameter_rename = None;
+                            self.new_parameter_error = None;
+                        }
+                        Err(error) => self.new_parameter_error = Some(error),
+                    }
                 }
                 if is_update_app_necessary {
-                    self.update_parameters_names_from_functions();
+                    self.reparse_elements();
+                }
+                if derived_changed {
+                    self.parameters.add_missing_inputs();
                 }
+                // every element follows the values just edited, derived parameters included
+                self.sync_parameters_to_functions();
                 
                 ui.horizontal(|ui| {
                     if ui
@@ -330,16 +1473,70 @@ impl eframe::App for PlottingApp {
                         .on_hover_text("Add a new function to the plot")
                         .clicked()
                     {
-                        self.add_new_function(Function::new(
-                            self.new_function_name.to_owned(),
-                            self.new_function_name.to_owned(),
-                        ));
-                        self.new_function_name = "".to_owned();
+                        let text = self.new_function_name.to_owned();
+                        let definitions = self.definitions();
+                        // an inequality such as `y > x^2` is a region rather than a function
+                        let added = if let Some((name, expression)) =
+                            definitions::parse_definition(&text)
+                        {
+                            Function::with_definitions(expression.to_owned(), name, &definitions)
+                                .map(|function| self.add_function(function))
+                        } else if elements::is_inequality(&text) {
+                            Region::with_definitions(text.clone(), text, &definitions)
+                                .map(|region| self.add_region(region))
+                        } else {
+                            Function::with_definitions(text.clone(), text, &definitions)
+                                .map(|function| self.add_function(function))
+                        };
+                        match added {
+                            Ok(()) => {
+                                self.new_function_name = "".to_owned();
+                                self.new_function_error = None;
+                            }
+                            Err(error) => self.new_function_error = Some(error),
+                        }
                     };
-                    let label = ui.label("f(x): ");
+                    let label = ui.label("f(x): ").on_hover_text(
+                        "An expression of x, a named function such as f1(x) = sin(a*x) that other \
+                         elements can call, or an inequality of x and y such as y > x^2",
+                    );
                     ui.text_edit_singleline(&mut self.new_function_name)
                         .labelled_by(label.id);
                 });
+                if let Some(error) = &self.new_function_error {
+                    ui.colored_label(egui::Color32::RED, error.to_string());
+                }
+                if ui
+                    .button("➕ Parametric curve")
+                    .on_hover_text("Add a curve (x(t), y(t))")
+                    .clicked()
+                {
+                    let name = format!("p{}", self.parametric_curves.len() + 1);
+                    let curve = ParametricCurve::new("cos(t)".to_owned(), "sin(t)".to_owned(), name)
+                        .expect("the default curve is valid");
+                    self.add_parametric_curve(curve);
+                }
+                if ui
+                    .button("➕ Polar curve")
+                    .on_hover_text("Add a curve r(θ)")
+                    .clicked()
+                {
+                    let name = format!("r{}", self.polar_curves.len() + 1);
+                    let curve = PolarCurve::new("cos(3*θ)".to_owned(), name)
+                        .expect("the default curve is valid");
+                    self.add_polar_curve(curve);
+                }
+                if ui
+                    .button("➕ Implicit curve")
+                    .on_hover_text("Add a curve F(x, y) = 0")
+                    .clicked()
+                {
+                    let name = format!("c{}", self.implicit_curves.len() + 1);
+                    let curve = ImplicitCurve::new("x^2 + y^2 = 4".to_owned(), name)
+                        .expect("the default curve is valid");
+                    self.add_implicit_curve(curve);
+                }
+                ui.checkbox(&mut self.polar_grid, "Polar grid");
                 ui.horizontal(|ui| {
                     // parameter
                     if ui
@@ -347,24 +1544,46 @@ impl eframe::App for PlottingApp {
                         .on_hover_text("Add a new parameter")
                         .clicked()
                     {
-                        if self.parameters_names.contains(&self.new_parameter_name) | show_parameter_name_warning{
+                        // `omega = 2*pi*freq` defines a derived parameter
+                        if let Some((name, expression)) = self.new_parameter_name.clone().split_once('=') {
+                            let name = name.trim();
+                            let added = if matches!(Expression::parse(name), Ok(Expression::Variable(_))) {
+                                self.set_parameter_expression(name, Some(expression))
+                            } else {
+                                Err(PlotError::Parse {
+                                    message: "expected a parameter name before `=`".to_owned(),
+                                    position: Some(0),
+                                })
+                            };
+                            match added {
+                                Ok(()) => {
+                                    self.new_parameter_name = "".to_owned();
+                                    self.new_parameter_error = None;
+                                }
+                                Err(error) => self.new_parameter_error = Some(error),
+                            }
+                        } else if self.parameters.contains(&self.new_parameter_name) | show_parameter_name_warning{
                             // TODO: warning only showing first time
                             show_parameter_name_warning = true;
                             ui.colored_label(egui::Color32::RED, format!("{} is already defined, choose another name.", &self.new_parameter_name));
         
                         } else {
-                            self.parameters_names.push(self.new_parameter_name.to_owned());
-                            self.parameters_values.push(DEFAULT_VALUE_PARAMETER);
-                            self.parameters_lim.push(DEFAULT_PARAMETERS_LIMIT);
+                            self.parameters.insert(self.new_parameter_name.to_owned(), Parameter::new(DEFAULT_VALUE_PARAMETER));
                             self.new_parameter_name = "".to_owned();
+                            self.new_parameter_error = None;
                             show_parameter_name_warning = false;
 
                         }
                     };
-                    let label = ui.label("Param: ");
+                    let label = ui
+                        .label("Param: ")
+                        .on_hover_text("A name, or a parameter computed from others such as omega = 2*pi*freq");
                     ui.text_edit_singleline(&mut self.new_parameter_name)
                         .labelled_by(label.id);
                 });
+                if let Some(error) = &self.new_parameter_error {
+                    ui.colored_label(egui::Color32::RED, error.to_string());
+                }
             });
         });
         //let frame = egui::Frame::default().inner_margin(40.0).outer_margin(50.0);
@@ -376,33 +1595,68 @@ impl eframe::App for PlottingApp {
                 .show_axes(true)
                 //.view_aspect(1.0)
                 .auto_bounds(Vec2b::new(false, false))
+                .show_grid(!self.polar_grid)
                 .allow_double_click_reset(false);
 
 
-            plot.show(ui, |plot_ui| {
-                let plot_bounds = plot_ui.plot_bounds();
-                //plot_ui.set_plot_bounds(plot_bounds)
-                self.xlim.0 = plot_bounds.min()[0];
-                self.xlim.1 = plot_bounds.max()[0];
-                for function in &self.functions {
-                    plot_ui.line(
-                        Line::new(PlotPoints::from_parametric_callback(
-                            |t| {
-                                let x = t;
-                                let y = function.eval(t);
-                                (x, y)
-                            },
-                            // make the plot adaptative to reduce number of points for high resolution. We can use also from_explicit_callback but I can figure out the static requirements
-                            self.xlim.0..=self.xlim.1,
-                            500,
-                        ))
-                        .name(&function.name),
-                    );
+            let response = plot.show(ui, |plot_ui| {
+                if self.view_changed {
+                    plot_ui.set_plot_bounds(PlotBounds::from_min_max(
+                        [self.xlim.0, self.ylim.0],
+                        [self.xlim.1, self.ylim.1],
+                    ));
+                    self.view_changed = false;
+                } else {
+                    let plot_bounds = plot_ui.plot_bounds();
+                    self.xlim = (plot_bounds.min()[0], plot_bounds.max()[0]);
+                    self.ylim = (plot_bounds.min()[1], plot_bounds.max()[1]);
+                }
+                for item in Scene::new(self).items {
+                    match item {
+                        Item::Curve(curve) => {
+                            for segment in curve.segments {
+                                let line = Line::new(PlotPoints::new(segment))
+                                    .color(curve.color)
+                                    .width(curve.width)
+                                    .style(curve.dash.into());
+                                // the lines of a grid have no legend entry
+                                plot_ui.line(match &curve.name {
+                                    Some(name) => line.name(name),
+                                    None => line,
+                                });
+                            }
+                        }
+                        Item::Fill(fill) => {
+                            for polygon in fill.polygons {
+                                plot_ui.polygon(
+                                    Polygon::new(PlotPoints::new(polygon))
+                                        .fill_color(fill.color)
+                                        .stroke(egui::Stroke::new(0.0, fill.color))
+                                        .name(&fill.name),
+                                );
+                            }
+                        }
+                    }
                 }
 
-            })
-            .response
+            });
+            let size = response.response.rect.size();
+            self.plot_size = [size.x.round() as u32, size.y.round() as u32];
         });
+        // a drag or a burst of scrolling is one step of the history
+        let (now, released) = ctx.input(|input| (input.time, input.pointer.any_released()));
+        if now - self.last_edit_time > MERGE_PAUSE {
+            self.end_step();
+        }
+        // the workspace is only edited on input, the frames without any, like the ones of an
+        // animation, are not worth comparing it
+        let has_input = ctx.input(|input| !input.events.is_empty());
+        if has_input && self.checkpoint() {
+            self.last_edit_time = now;
+        }
+        if released {
+            self.end_step();
+        }
 
         // egui::SidePanel::right("plot").show(ctx, |ui| {
 
@@ -0,0 +1,273 @@
+//! Renders a [`Scene`] as a PNG image on the CPU, without a window or a GPU.
+//!
+//! The image has the layout of [`crate::svg`], the text being drawn with the font of the app.
+use ab_glyph::{point, Font, FontArc, PxScale, ScaleFont};
+use egui::Color32;
+use tiny_skia::{
+    FillRule, LineCap, LineJoin, Mask, Paint, PathBuilder, Pixmap, PremultipliedColorU8, Rect,
+    Stroke, StrokeDash, Transform,
+};
+
+use crate::error::PlotError;
+use crate::layout::{
+    tick_labels, Frame, Legend, AXIS_COLOR, FONT_SIZE, GRID_COLOR, LABEL_GAP, TICK_LENGTH,
+};
+use crate::scene::{Curve, Dash, Fill, Item, Scene};
+
+/// Where a text is placed relative to its position.
+#[derive(Clone, Copy)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+/// The scene as a PNG image of `width` by `height` pixels, with its grid, axes, ticks and
+/// legend.
+pub fn render_png(scene: &Scene, width: u32, height: u32) -> Result<Vec<u8>, PlotError> {
+    let mut pixmap = Pixmap::new(width, height)
+        .ok_or_else(|| PlotError::Export(format!("cannot render {}x{} pixels", width, height)))?;
+    pixmap.fill(tiny_skia::Color::WHITE);
+    let frame = Frame::new(scene, width, height);
+    let font = font()?;
+
+    if scene.cartesian_grid {
+        for x in &frame.x_ticks {
+            let x = frame.x(*x);
+            line(&mut pixmap, [x, frame.top], [x, frame.bottom], GRID_COLOR);
+        }
+        for y in &frame.y_ticks {
+            let y = frame.y(*y);
+            line(&mut pixmap, [frame.left, y], [frame.right, y], GRID_COLOR);
+        }
+    }
+
+    let plot_area = Rect::from_ltrb(
+        frame.left as f32,
+        frame.top as f32,
+        frame.right as f32,
+        frame.bottom as f32,
+    )
+    .map(PathBuilder::from_rect);
+    let mut clip = Mask::new(width, height).unwrap();
+    if let Some(plot_area) = &plot_area {
+        clip.fill_path(plot_area, FillRule::Winding, false, Transform::identity());
+    }
+    for item in &scene.items {
+        match item {
+            Item::Curve(curve) => draw_curve(&mut pixmap, &frame, curve, &clip),
+            Item::Fill(fill) => draw_fill(&mut pixmap, &frame, fill, &clip),
+        }
+    }
+
+    // the frame with its ticks, labelled below and on the left
+    if let Some(plot_area) = &plot_area {
+        let stroke = Stroke::default();
+        let paint = paint(AXIS_COLOR);
+        pixmap.stroke_path(plot_area, &paint, &stroke, Transform::identity(), None);
+    }
+    for (x, label) in frame.x_ticks.iter().zip(tick_labels(&frame.x_ticks)) {
+        let px = frame.x(*x);
+        line(
+            &mut pixmap,
+            [px, frame.bottom],
+            [px, frame.bottom + TICK_LENGTH],
+            AXIS_COLOR,
+        );
+        let baseline = frame.bottom + LABEL_GAP + FONT_SIZE;
+        text(&mut pixmap, &font, &label, [px, baseline], Anchor::Middle);
+    }
+    for (y, label) in frame.y_ticks.iter().zip(tick_labels(&frame.y_ticks)) {
+        let py = frame.y(*y);
+        line(
+            &mut pixmap,
+            [frame.left - TICK_LENGTH, py],
+            [frame.left, py],
+            AXIS_COLOR,
+        );
+        let position = [frame.left - LABEL_GAP, py + FONT_SIZE / 3.0];
+        text(&mut pixmap, &font, &label, position, Anchor::End);
+    }
+
+    if let Some(legend) = Legend::new(&frame, scene) {
+        draw_legend(&mut pixmap, &font, &legend);
+    }
+    pixmap
+        .encode_png()
+        .map_err(|error| PlotError::Export(error.to_string()))
+}
+
+/// The font of the labels, the one `egui` uses for its text.
+fn font() -> Result<FontArc, PlotError> {
+    let mut definitions = egui::FontDefinitions::default();
+    let data = definitions
+        .font_data
+        .remove("Ubuntu-Light")
+        .ok_or_else(|| PlotError::Export("the font of the labels is missing".to_owned()))?;
+    FontArc::try_from_vec(data.font.into_owned())
+        .map_err(|error| PlotError::Export(error.to_string()))
+}
+
+fn paint(color: Color32) -> Paint<'static> {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(r, g, b, a);
+    paint.anti_alias = true;
+    paint
+}
+
+fn line(pixmap: &mut Pixmap, [x1, y1]: [f64; 2], [x2, y2]: [f64; 2], color: Color32) {
+    let mut builder = PathBuilder::new();
+    builder.move_to(x1 as f32, y1 as f32);
+    builder.line_to(x2 as f32, y2 as f32);
+    if let Some(path) = builder.finish() {
+        let stroke = Stroke::default();
+        pixmap.stroke_path(&path, &paint(color), &stroke, Transform::identity(), None);
+    }
+}
+
+/// Path of a polyline, broken where a point is not finite.
+fn path(frame: &Frame, points: &[[f64; 2]], closed: bool) -> Option<tiny_skia::Path> {
+    let mut builder = PathBuilder::new();
+    let mut pen_down = false;
+    for [x, y] in points {
+        if !x.is_finite() || !y.is_finite() {
+            pen_down = false;
+            continue;
+        }
+        let (x, y) = (frame.x(*x) as f32, frame.y(*y) as f32);
+        if pen_down {
+            builder.line_to(x, y);
+        } else {
+            builder.move_to(x, y);
+        }
+        pen_down = true;
+    }
+    if closed {
+        builder.close();
+    }
+    builder.finish()
+}
+
+fn draw_curve(pixmap: &mut Pixmap, frame: &Frame, curve: &Curve, clip: &Mask) {
+    let dash = match curve.dash {
+        Dash::Solid => None,
+        Dash::Dashed { length } => StrokeDash::new(vec![length, length], 0.0),
+        // nearly zero length dashes with round caps are dots, empty ones are not drawn
+        Dash::Dotted { spacing } => StrokeDash::new(vec![0.01, spacing], 0.0),
+    };
+    let stroke = Stroke {
+        width: curve.width,
+        line_cap: LineCap::Round,
+        line_join: LineJoin::Round,
+        dash,
+        ..Stroke::default()
+    };
+    let paint = paint(curve.color);
+    for segment in &curve.segments {
+        if let Some(path) = path(frame, segment, false) {
+            pixmap.stroke_path(&path, &paint, &stroke, Transform::identity(), Some(clip));
+        }
+    }
+}
+
+fn draw_fill(pixmap: &mut Pixmap, frame: &Frame, fill: &Fill, clip: &Mask) {
+    let mut paint = paint(fill.color);
+    // antialiased edges would show as seams between the cells of a region, its boundary is
+    // drawn over them
+    paint.anti_alias = false;
+    for polygon in &fill.polygons {
+        if let Some(path) = path(frame, polygon, true) {
+            let rule = FillRule::Winding;
+            pixmap.fill_path(&path, &paint, rule, Transform::identity(), Some(clip));
+        }
+    }
+}
+
+fn draw_legend(pixmap: &mut Pixmap, font: &FontArc, legend: &Legend<'_>) {
+    let (x, y) = (legend.x as f32, legend.y as f32);
+    let Some(rect) = Rect::from_xywh(x, y, legend.width as f32, legend.height as f32) else {
+        return;
+    };
+    let background = paint(Color32::from_white_alpha(204));
+    pixmap.fill_rect(rect, &background, Transform::identity(), None);
+    let border = PathBuilder::from_rect(rect);
+    let stroke = Stroke::default();
+    pixmap.stroke_path(
+        &border,
+        &paint(GRID_COLOR),
+        &stroke,
+        Transform::identity(),
+        None,
+    );
+    let (start, end) = legend.sample();
+    for (k, (name, color)) in legend.entries.iter().enumerate() {
+        let center = legend.center(k);
+        let mut builder = PathBuilder::new();
+        builder.move_to(start as f32, center as f32);
+        builder.line_to(end as f32, center as f32);
+        if let Some(path) = builder.finish() {
+            let stroke = Stroke {
+                width: 3.0,
+                ..Stroke::default()
+            };
+            pixmap.stroke_path(&path, &paint(*color), &stroke, Transform::identity(), None);
+        }
+        let position = [legend.text_x(), center + FONT_SIZE / 3.0];
+        text(pixmap, font, name, position, Anchor::Start);
+    }
+}
+
+/// Draws a line of text in [`AXIS_COLOR`], `position` being on its baseline.
+fn text(pixmap: &mut Pixmap, font: &FontArc, text: &str, [x, y]: [f64; 2], anchor: Anchor) {
+    let font = font.as_scaled(PxScale::from(FONT_SIZE as f32));
+    let mut glyphs = vec![];
+    let mut caret = 0.0;
+    let mut previous = None;
+    for c in text.chars() {
+        let id = font.glyph_id(c);
+        if let Some(previous) = previous {
+            caret += font.kern(previous, id);
+        }
+        glyphs.push(id.with_scale_and_position(font.scale(), point(caret, 0.0)));
+        caret += font.h_advance(id);
+        previous = Some(id);
+    }
+    let x = match anchor {
+        Anchor::Start => x as f32,
+        Anchor::Middle => x as f32 - caret / 2.0,
+        Anchor::End => x as f32 - caret,
+    };
+    let [r, g, b, _] = AXIS_COLOR.to_array();
+    let (width, height) = (pixmap.width() as i32, pixmap.height() as i32);
+    let pixels = pixmap.pixels_mut();
+    for mut glyph in glyphs {
+        glyph.position.x += x;
+        glyph.position.y += y as f32;
+        let Some(outline) = font.outline_glyph(glyph) else {
+            continue;
+        };
+        let bounds = outline.px_bounds();
+        outline.draw(|gx, gy, coverage| {
+            let px = bounds.min.x as i32 + gx as i32;
+            let py = bounds.min.y as i32 + gy as i32;
+            if px < 0 || py < 0 || px >= width || py >= height {
+                return;
+            }
+            let pixel = &mut pixels[(py * width + px) as usize];
+            // the text is opaque, blended over the image by its coverage
+            let coverage = coverage.clamp(0.0, 1.0);
+            let blend = |source: u8, destination: u8| {
+                (source as f32 * coverage + destination as f32 * (1.0 - coverage)).round() as u8
+            };
+            let alpha = blend(255, pixel.alpha());
+            *pixel = PremultipliedColorU8::from_rgba(
+                blend(r, pixel.red()).min(alpha),
+                blend(g, pixel.green()).min(alpha),
+                blend(b, pixel.blue()).min(alpha),
+                alpha,
+            )
+            .unwrap_or(*pixel);
+        });
+    }
+}
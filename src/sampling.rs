@@ -0,0 +1,439 @@
+//! Sampling of curves into drawable line segments.
+use std::collections::{HashMap, VecDeque};
+
+/// How densely a curve is sampled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplingOptions {
+    /// Number of evenly spaced samples taken before refining.
+    pub initial_samples: usize,
+    /// Maximum number of times an interval between two initial samples is halved.
+    pub max_depth: u32,
+    /// Upper bound on the number of points of a curve, refinement stops once reached.
+    pub max_points: usize,
+    /// Maximum distance in y between the curve and the straight line drawn between two points.
+    pub tolerance: f64,
+    /// Difference in y between two points at the maximum depth above which the curve is
+    /// considered discontinuous, and the line is split instead of joining both sides.
+    pub jump_threshold: f64,
+}
+impl SamplingOptions {
+    /// Options adapted to a view showing `y_span` units vertically.
+    pub fn for_view(y_span: f64) -> Self {
+        let y_span = if y_span.is_finite() && y_span > 0.0 {
+            y_span
+        } else {
+            1.0
+        };
+        Self {
+            tolerance: y_span * 1e-3,
+            jump_threshold: y_span * 0.5,
+            ..Self::default()
+        }
+    }
+}
+impl Default for SamplingOptions {
+    fn default() -> Self {
+        Self {
+            initial_samples: 200,
+            max_depth: 10,
+            max_points: 20_000,
+            tolerance: 1e-3,
+            jump_threshold: 1.0,
+        }
+    }
+}
+
+/// A point of the curve and the branch of the function it lies on.
+type Sample = ([f64; 2], u64);
+
+struct Sampler<'a, F, B> {
+    f: F,
+    branch: B,
+    options: &'a SamplingOptions,
+    segments: Vec<Vec<[f64; 2]>>,
+    current: Vec<[f64; 2]>,
+    points: usize,
+}
+impl<F: Fn(f64) -> f64, B: Fn(f64) -> u64> Sampler<'_, F, B> {
+    fn at(&self, x: f64) -> Sample {
+        ([x, (self.f)(x)], (self.branch)(x))
+    }
+    fn push(&mut self, [x, y]: [f64; 2]) {
+        if y.is_finite() {
+            self.current.push([x, y]);
+            self.points += 1;
+        } else {
+            self.split();
+        }
+    }
+    /// Ends the current segment, the next point starts a new one.
+    fn split(&mut self) {
+        let segment = std::mem::take(&mut self.current);
+        if segment.len() > 1 {
+            self.segments.push(segment);
+        }
+    }
+    /// Adds the points needed between `a` and `b`, both excluded.
+    fn refine(&mut self, (a, a_branch): Sample, (b, b_branch): Sample, depth: u32) {
+        let (a_finite, b_finite) = (a[1].is_finite(), b[1].is_finite());
+        if depth >= self.options.max_depth || self.points >= self.options.max_points {
+            if a_finite
+                && b_finite
+                && ((b[1] - a[1]).abs() > self.options.jump_threshold || a_branch != b_branch)
+            {
+                self.split();
+            }
+            return;
+        }
+        let middle = self.at((a[0] + b[0]) / 2.0);
+        let (middle_point, _) = middle;
+        let needs_refinement = match (a_finite, b_finite) {
+            (true, true) => {
+                !middle_point[1].is_finite()
+                    || (middle_point[1] - (a[1] + b[1]) / 2.0).abs() > self.options.tolerance
+                    || (b[1] - a[1]).abs() > self.options.jump_threshold
+                    || a_branch != b_branch
+            }
+            // an undefined interval, unless a defined piece is hiding in the middle
+            (false, false) => middle_point[1].is_finite(),
+            // the edge of the domain, bisect to get as close to it as possible
+            _ => true,
+        };
+        if needs_refinement {
+            self.refine((a, a_branch), middle, depth + 1);
+            self.push(middle_point);
+            self.refine(middle, (b, b_branch), depth + 1);
+        }
+    }
+}
+
+/// Samples `f` over `x_range` into line segments.
+///
+/// Samples are added where the curve bends, and the curve is split where it is not finite or
+/// where it jumps, so poles like the ones of `tan(x)` or `1/x` are not joined by a vertical line.
+pub fn sample(
+    f: impl Fn(f64) -> f64,
+    x_range: (f64, f64),
+    options: &SamplingOptions,
+) -> Vec<Vec<[f64; 2]>> {
+    sample_piecewise(f, |_| 0, x_range, options)
+}
+
+/// Like [`sample`], but also splits the curve where `branch` changes value, which tells the
+/// piece of a piecewise definition `f` is on at `x`, like
+/// [`CompiledExpression::branch`](crate::compiled::CompiledExpression::branch) does.
+///
+/// Steps like the one of `if(x > 0, 1, 0)` are then split whatever their height.
+pub fn sample_piecewise(
+    f: impl Fn(f64) -> f64,
+    branch: impl Fn(f64) -> u64,
+    x_range: (f64, f64),
+    options: &SamplingOptions,
+) -> Vec<Vec<[f64; 2]>> {
+    let mut sampler = Sampler {
+        f,
+        branch,
+        options,
+        segments: vec![],
+        current: vec![],
+        points: 0,
+    };
+    let n = options.initial_samples.max(2);
+    let (x0, x1) = x_range;
+    let mut previous = sampler.at(x0);
+    sampler.push(previous.0);
+    for i in 1..n {
+        let point = sampler.at(x0 + (x1 - x0) * i as f64 / (n - 1) as f64);
+        sampler.refine(previous, point, 0);
+        sampler.push(point.0);
+        previous = point;
+    }
+    sampler.split();
+    sampler.segments
+}
+
+/// Samples the curve `t -> f(t)` at `samples` evenly spaced values of `t` over `t_range`.
+///
+/// The curve is split where a coordinate is not finite.
+pub fn sample_parametric(
+    f: impl Fn(f64) -> [f64; 2],
+    t_range: (f64, f64),
+    samples: usize,
+) -> Vec<Vec<[f64; 2]>> {
+    let n = samples.max(2);
+    let (t0, t1) = t_range;
+    let mut segments = vec![];
+    let mut current: Vec<[f64; 2]> = vec![];
+    for i in 0..n {
+        let point = f(t0 + (t1 - t0) * i as f64 / (n - 1) as f64);
+        if point.iter().all(|coordinate| coordinate.is_finite()) {
+            current.push(point);
+        } else if current.len() > 1 {
+            segments.push(std::mem::take(&mut current));
+        } else {
+            current.clear();
+        }
+    }
+    if current.len() > 1 {
+        segments.push(current);
+    }
+    segments
+}
+
+/// An edge of the grid used by [`contour`]: its lower left node and whether it is vertical.
+type Edge = (usize, usize, bool);
+
+/// Traces the curve `f(x, y) = 0` over `xlim` × `ylim` with marching squares.
+///
+/// `f` is evaluated on the nodes of a grid of `resolution` cells along each axis, and the curve
+/// crosses the cell edges along which `f` changes sign. Each crossing is refined by bisection,
+/// which also tells sign changes through a pole, like the ones of `1/x - y`, from roots.
+pub fn contour(
+    mut f: impl FnMut(f64, f64) -> f64,
+    xlim: (f64, f64),
+    ylim: (f64, f64),
+    resolution: (usize, usize),
+) -> Vec<Vec<[f64; 2]>> {
+    let (nx, ny) = (resolution.0.max(1), resolution.1.max(1));
+    let x = |i: usize| xlim.0 + (xlim.1 - xlim.0) * i as f64 / nx as f64;
+    let y = |j: usize| ylim.0 + (ylim.1 - ylim.0) * j as f64 / ny as f64;
+    let values: Vec<Vec<f64>> = (0..=nx)
+        .map(|i| (0..=ny).map(|j| f(x(i), y(j))).collect())
+        .collect();
+
+    let mut pieces: Vec<[Edge; 2]> = vec![];
+    for i in 0..nx {
+        for j in 0..ny {
+            let corners = [
+                values[i][j],
+                values[i + 1][j],
+                values[i + 1][j + 1],
+                values[i][j + 1],
+            ];
+            if !corners.iter().all(|value| value.is_finite()) {
+                continue;
+            }
+            let case = corners.iter().enumerate().fold(0, |case, (k, value)| {
+                case | (usize::from(*value > 0.0) << k)
+            });
+            let (bottom, right, top, left) = (
+                (i, j, false),
+                (i + 1, j, true),
+                (i, j + 1, false),
+                (i, j, true),
+            );
+            let cell_pieces: &[[Edge; 2]] = match case {
+                0 | 15 => &[],
+                1 | 14 => &[[left, bottom]],
+                2 | 13 => &[[bottom, right]],
+                3 | 12 => &[[left, right]],
+                4 | 11 => &[[right, top]],
+                6 | 9 => &[[bottom, top]],
+                7 | 8 => &[[left, top]],
+                // saddles, the value at the center tells which corners are connected
+                5 | 10 => {
+                    let center_positive = corners.iter().sum::<f64>() > 0.0;
+                    if (case == 5) == center_positive {
+                        &[[left, top], [bottom, right]]
+                    } else {
+                        &[[left, bottom], [right, top]]
+                    }
+                }
+                _ => unreachable!("a case has 4 bits"),
+            };
+            pieces.extend_from_slice(cell_pieces);
+        }
+    }
+
+    let mut crossings: HashMap<Edge, Option<[f64; 2]>> = HashMap::new();
+    for edge @ (i, j, vertical) in pieces.iter().flatten().copied() {
+        crossings.entry(edge).or_insert_with(|| {
+            let (i1, j1) = if vertical { (i, j + 1) } else { (i + 1, j) };
+            let a = ([x(i), y(j)], values[i][j]);
+            let b = ([x(i1), y(j1)], values[i1][j1]);
+            bisect(&mut f, a, b)
+        });
+    }
+    // pieces through a pole end on an edge without a crossing
+    pieces.retain(|piece| piece.iter().all(|edge| crossings[edge].is_some()));
+    chain(&pieces)
+        .into_iter()
+        .map(|edges| {
+            let point = |edge| crossings[&edge].expect("pieces without crossing were removed");
+            edges.into_iter().map(point).collect()
+        })
+        .collect()
+}
+
+/// Where `f` is 0 between `a` and `b`, two points with their values of opposite signs.
+///
+/// Returns `None` when the values grow instead of getting closer to 0, the sign changes
+/// through a pole.
+fn bisect(
+    f: &mut impl FnMut(f64, f64) -> f64,
+    mut a: ([f64; 2], f64),
+    mut b: ([f64; 2], f64),
+) -> Option<[f64; 2]> {
+    const STEPS: usize = 8;
+    let initial_bound = a.1.abs().max(b.1.abs());
+    for _ in 0..STEPS {
+        let middle = [(a.0[0] + b.0[0]) / 2.0, (a.0[1] + b.0[1]) / 2.0];
+        let value = f(middle[0], middle[1]);
+        if !value.is_finite() {
+            return None;
+        }
+        if (value > 0.0) == (a.1 > 0.0) {
+            a = (middle, value);
+        } else {
+            b = (middle, value);
+        }
+    }
+    if a.1.abs().min(b.1.abs()) > initial_bound {
+        return None;
+    }
+    let t = if a.1 == b.1 { 0.5 } else { a.1 / (a.1 - b.1) };
+    Some([
+        a.0[0] + (b.0[0] - a.0[0]) * t,
+        a.0[1] + (b.0[1] - a.0[1]) * t,
+    ])
+}
+
+/// Joins the pieces sharing an edge into polylines, as lists of edges.
+fn chain(pieces: &[[Edge; 2]]) -> Vec<Vec<Edge>> {
+    let mut by_edge: HashMap<Edge, Vec<usize>> = HashMap::new();
+    for (k, piece) in pieces.iter().enumerate() {
+        for edge in piece {
+            by_edge.entry(*edge).or_default().push(k);
+        }
+    }
+    let mut used = vec![false; pieces.len()];
+    // the piece after `k` when leaving it through `edge`
+    let next = |k: usize, edge: Edge, used: &[bool]| {
+        by_edge[&edge]
+            .iter()
+            .copied()
+            .find(|other| *other != k && !used[*other])
+    };
+    let mut polylines = vec![];
+    for start in 0..pieces.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let mut polyline = VecDeque::from(pieces[start]);
+        // extend forwards from the end, then backwards from the start
+        for forwards in [true, false] {
+            let mut k = start;
+            loop {
+                let edge = if forwards {
+                    polyline[polyline.len() - 1]
+                } else {
+                    polyline[0]
+                };
+                let Some(other) = next(k, edge, &used) else {
+                    break;
+                };
+                used[other] = true;
+                let [a, b] = pieces[other];
+                let far = if a == edge { b } else { a };
+                if forwards {
+                    polyline.push_back(far);
+                } else {
+                    polyline.push_front(far);
+                }
+                k = other;
+            }
+        }
+        polylines.push(polyline.into());
+    }
+    polylines
+}
+
+/// Covers the region where `f(x, y) <= 0` over `xlim` × `ylim` with convex polygons.
+///
+/// The grid is the one of [`contour`]: runs of cells inside the region are merged into
+/// rectangles, and the cells the boundary goes through are clipped along it.
+pub fn fill(
+    mut f: impl FnMut(f64, f64) -> f64,
+    xlim: (f64, f64),
+    ylim: (f64, f64),
+    resolution: (usize, usize),
+) -> Vec<Vec<[f64; 2]>> {
+    let (nx, ny) = (resolution.0.max(1), resolution.1.max(1));
+    let x = |i: usize| xlim.0 + (xlim.1 - xlim.0) * i as f64 / nx as f64;
+    let y = |j: usize| ylim.0 + (ylim.1 - ylim.0) * j as f64 / ny as f64;
+    let values: Vec<Vec<f64>> = (0..=nx)
+        .map(|i| (0..=ny).map(|j| f(x(i), y(j))).collect())
+        .collect();
+    let inside = |value: f64| value <= 0.0;
+
+    let mut polygons = vec![];
+    for j in 0..ny {
+        // first cell of the current run of cells entirely inside
+        let mut run_start = None;
+        for i in 0..=nx {
+            let corners = (i < nx).then(|| {
+                [
+                    ([x(i), y(j)], values[i][j]),
+                    ([x(i + 1), y(j)], values[i + 1][j]),
+                    ([x(i + 1), y(j + 1)], values[i + 1][j + 1]),
+                    ([x(i), y(j + 1)], values[i][j + 1]),
+                ]
+            });
+            let is_inside =
+                corners.is_some_and(|corners| corners.iter().all(|(_, value)| inside(*value)));
+            match (run_start, is_inside) {
+                (None, true) => run_start = Some(i),
+                (Some(start), false) => {
+                    let (x0, x1, y0, y1) = (x(start), x(i), y(j), y(j + 1));
+                    polygons.push(vec![[x0, y0], [x1, y0], [x1, y1], [x0, y1]]);
+                    run_start = None;
+                }
+                _ => {}
+            }
+            let Some(corners) = corners else { continue };
+            if is_inside || !corners.iter().all(|(_, value)| value.is_finite()) {
+                continue;
+            }
+            polygons.extend(clip_cell(&corners, inside));
+        }
+    }
+    polygons
+}
+
+/// The parts of a cell inside the region, given its corners counterclockwise with their values.
+fn clip_cell(corners: &[([f64; 2], f64); 4], inside: impl Fn(f64) -> bool) -> Vec<Vec<[f64; 2]>> {
+    let crossing = |(a, va): ([f64; 2], f64), (b, vb): ([f64; 2], f64)| {
+        let t = va / (va - vb);
+        [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+    };
+    // walks around the cell, keeping the inside corners and where the boundary crosses
+    let mut polygon = vec![];
+    for k in 0..4 {
+        let (a, b) = (corners[k], corners[(k + 1) % 4]);
+        if inside(a.1) {
+            polygon.push(a.0);
+        }
+        if inside(a.1) != inside(b.1) {
+            polygon.push(crossing(a, b));
+        }
+    }
+    let inside_corners: Vec<bool> = corners.iter().map(|(_, value)| inside(*value)).collect();
+    let is_saddle = inside_corners == [true, false, true, false]
+        || inside_corners == [false, true, false, true];
+    let center_inside = inside(corners.iter().map(|(_, value)| value).sum::<f64>() / 4.0);
+    if polygon.len() == 6 && is_saddle && !center_inside {
+        // two separate corners, each between the two crossings around it
+        let start = if inside_corners[0] { 5 } else { 0 };
+        let rotated: Vec<_> = polygon[start..]
+            .iter()
+            .chain(&polygon[..start])
+            .copied()
+            .collect();
+        vec![rotated[..3].to_vec(), rotated[3..].to_vec()]
+    } else if polygon.len() >= 3 {
+        vec![polygon]
+    } else {
+        vec![]
+    }
+}
@@ -0,0 +1,304 @@
+//! What the plot of a workspace shows, sampled and styled once to be drawn by `egui_plot` in
+//! the app or rendered without a window, see [`crate::svg`].
+use egui::Color32;
+use egui_plot::LineStyle;
+
+use crate::elements::PolarGrid;
+use crate::evaluation::{CurveStyle, Parametrized};
+use crate::plotting::PlottingApp;
+use crate::sampling::{self, SamplingOptions};
+
+/// Color of the `index`-th curve, the same sequence `egui_plot` uses for automatic colors.
+pub(crate) fn auto_color(index: usize) -> Color32 {
+    let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+    let h = index as f32 * golden_ratio;
+    egui::ecolor::Hsva::new(h, 0.85, 0.5, 1.0).into()
+}
+
+/// Color of the `k`-th of `count` curves of a family, a gradient around the color `base` of the
+/// function.
+pub(crate) fn gradient_color(base: Color32, k: usize, count: usize) -> Color32 {
+    let t = if count > 1 {
+        k as f32 / (count - 1) as f32
+    } else {
+        0.5
+    };
+    let mut color = egui::ecolor::Hsva::from(base);
+    color.h = (color.h + 0.3 * (t - 0.5)).rem_euclid(1.0);
+    color.v = 0.3 + 0.5 * t;
+    color.into()
+}
+
+/// How a curve is stroked, lengths in points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dash {
+    Solid,
+    Dashed { length: f32 },
+    Dotted { spacing: f32 },
+}
+impl Dash {
+    pub const LOOSE_DASHES: Self = Self::Dashed { length: 10.0 };
+    pub const DENSE_DASHES: Self = Self::Dashed { length: 5.0 };
+    pub const LOOSE_DOTS: Self = Self::Dotted { spacing: 10.0 };
+}
+impl From<Dash> for LineStyle {
+    fn from(dash: Dash) -> Self {
+        match dash {
+            Dash::Solid => Self::Solid,
+            Dash::Dashed { length } => Self::Dashed { length },
+            Dash::Dotted { spacing } => Self::Dotted { spacing },
+        }
+    }
+}
+
+/// Lines sharing a style, like the pieces of a function between its discontinuities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Curve {
+    /// The legend entry, `None` for a line of the grid.
+    pub name: Option<String>,
+    pub color: Color32,
+    pub width: f32,
+    pub dash: Dash,
+    pub segments: Vec<Vec<[f64; 2]>>,
+}
+
+/// Shaded polygons, like the parts of a region.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub name: String,
+    pub color: Color32,
+    pub polygons: Vec<Vec<[f64; 2]>>,
+}
+
+/// What is drawn, in drawing order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Curve(Curve),
+    Fill(Fill),
+}
+
+/// Everything the plot of a workspace shows within its view.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub xlim: (f64, f64),
+    pub ylim: (f64, f64),
+    /// Whether a Cartesian grid is drawn behind the items, a polar grid being one of them.
+    pub cartesian_grid: bool,
+    pub items: Vec<Item>,
+}
+impl Scene {
+    /// Samples every element of `app` over its view, with the colors and styles of the app.
+    pub fn new(app: &PlottingApp) -> Self {
+        let (xlim, ylim) = (app.xlim(), app.ylim());
+        let mut items = vec![];
+        if app.polar_grid() {
+            // first to stay behind the curves
+            let grid = PolarGrid::for_view(xlim, ylim, 8);
+            let color = Color32::from_gray(128).gamma_multiply(0.4);
+            let circles = grid.radii.iter().map(|r| {
+                (0..=100)
+                    .map(|k| {
+                        let t = std::f64::consts::TAU * k as f64 / 100.0;
+                        [r * t.cos(), r * t.sin()]
+                    })
+                    .collect()
+            });
+            let spokes = grid.angles.iter().map(|angle| {
+                let end = [grid.max_radius * angle.cos(), grid.max_radius * angle.sin()];
+                vec![[0.0, 0.0], end]
+            });
+            items.push(Item::Curve(Curve {
+                name: None,
+                color,
+                width: 1.0,
+                dash: Dash::Solid,
+                segments: circles.chain(spokes).collect(),
+            }));
+        }
+        let curve = |name: &str, color, width, dash, segments| {
+            Item::Curve(Curve {
+                name: Some(name.to_owned()),
+                color,
+                width,
+                dash,
+                segments,
+            })
+        };
+        let options = SamplingOptions::for_view(ylim.1 - ylim.0);
+        let functions = app.functions();
+        for (i, function) in functions.iter().enumerate() {
+            // an element whose saved text does not parse has nothing to draw
+            if !function.is_compiled() {
+                continue;
+            }
+            // every segment of a function shares its color and legend entry
+            let color = function
+                .style
+                .color
+                .map_or(auto_color(i), |[r, g, b]| Color32::from_rgb(r, g, b));
+            let width = function.style.width;
+            let branch = |x| function.branch(x);
+            let family = function.sweep.as_ref().and_then(|sweep| {
+                // an invalid sweep is reported in its card, the function is drawn alone meanwhile
+                Some((&sweep.parameter, sweep.family(function).ok()?))
+            });
+            if let Some((parameter, family)) = family {
+                // one legend entry per value, the colors following the values
+                for (k, (value, member)) in family.iter().enumerate() {
+                    let name = format!("{} ({} = {})", function.name, parameter, value);
+                    let (eval, branch) = (|x| member.eval(x), |x| member.branch(x));
+                    let segments = sampling::sample_piecewise(eval, branch, xlim, &options);
+                    let color = gradient_color(color, k, family.len());
+                    items.push(curve(&name, color, width, Dash::Solid, segments));
+                }
+            } else {
+                let eval = |x| function.eval(x);
+                let segments = sampling::sample_piecewise(eval, branch, xlim, &options);
+                items.push(curve(&function.name, color, width, Dash::Solid, segments));
+            }
+            // derivatives keep the color of the function, told apart by their line style
+            let derivatives = [
+                (function.show_derivative, 1, "'", Dash::LOOSE_DASHES),
+                (function.show_second_derivative, 2, "''", Dash::LOOSE_DOTS),
+            ];
+            for (show, order, primes, dash) in derivatives {
+                if !show {
+                    continue;
+                }
+                let name = format!("{}{}", function.name, primes);
+                let derivative = |x| function.eval_derivative(order, x);
+                let segments = sampling::sample_piecewise(derivative, branch, xlim, &options);
+                items.push(curve(&name, color, width, dash, segments));
+            }
+        }
+        let mut index = functions.len();
+        let mut next_color = || {
+            index += 1;
+            auto_color(index - 1)
+        };
+        for parametric in app.parametric_curves() {
+            let color = next_color();
+            if !parametric.is_compiled() {
+                continue;
+            }
+            let segments = parametric.sample();
+            items.push(curve(
+                &parametric.name,
+                color,
+                CurveStyle::DEFAULT_WIDTH,
+                Dash::Solid,
+                segments,
+            ));
+        }
+        for polar in app.polar_curves() {
+            let color = next_color();
+            if !polar.is_compiled() {
+                continue;
+            }
+            let segments = polar.sample();
+            items.push(curve(
+                &polar.name,
+                color,
+                CurveStyle::DEFAULT_WIDTH,
+                Dash::Solid,
+                segments,
+            ));
+        }
+        for implicit in app.implicit_curves() {
+            let color = next_color();
+            if !implicit.is_compiled() {
+                continue;
+            }
+            let segments = implicit.sample(xlim, ylim);
+            items.push(curve(
+                &implicit.name,
+                color,
+                CurveStyle::DEFAULT_WIDTH,
+                Dash::Solid,
+                segments,
+            ));
+        }
+        for region in app.regions() {
+            let color = next_color();
+            let Some(inequality) = region.inequality() else {
+                continue;
+            };
+            items.push(Item::Fill(Fill {
+                name: region.name.to_owned(),
+                color: color.gamma_multiply(0.25),
+                polygons: region.sample_fill(xlim, ylim),
+            }));
+            // a strict inequality excludes its boundary, drawn dashed
+            let dash = if inequality.is_strict() {
+                Dash::DENSE_DASHES
+            } else {
+                Dash::Solid
+            };
+            let segments = region.sample_boundary(xlim, ylim);
+            items.push(curve(
+                &region.name,
+                color,
+                CurveStyle::DEFAULT_WIDTH,
+                dash,
+                segments,
+            ));
+        }
+        Self {
+            xlim,
+            ylim,
+            cartesian_grid: !app.polar_grid(),
+            items,
+        }
+    }
+    /// The entries of the legend with their color, in order, items sharing a name sharing their
+    /// entry like in `egui_plot`.
+    pub fn legend(&self) -> Vec<(&str, Color32)> {
+        let mut entries: Vec<(&str, Color32)> = vec![];
+        for item in &self.items {
+            let (name, color) = match item {
+                Item::Curve(Curve {
+                    name: Some(name),
+                    color,
+                    ..
+                }) => (name, *color),
+                Item::Fill(fill) => (&fill.name, fill.color),
+                Item::Curve(_) => continue,
+            };
+            if !entries.iter().any(|(entry, _)| *entry == name) {
+                entries.push((name, color));
+            }
+        }
+        entries
+    }
+}
+
+/// About `count` evenly spaced round values within `(min, max)`, steps being 1, 2 or 5 times
+/// a power of ten.
+pub fn ticks((min, max): (f64, f64), count: usize) -> Vec<f64> {
+    let span = max - min;
+    if !span.is_finite() || span <= 0.0 || count == 0 {
+        return vec![];
+    }
+    let rough = span / count as f64;
+    let exponent = rough.log10().floor() as i32;
+    let magnitude = 10_f64.powi(exponent);
+    let factor = [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .find(|factor| factor * magnitude >= rough)
+        .unwrap_or(10.0);
+    let step = factor * magnitude;
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    // multiples of the step avoid accumulating rounding errors, a division by a power of ten
+    // keeps values like 0.3 exact
+    (first..=last)
+        .map(|k| {
+            let multiple = k as f64 * factor;
+            if exponent < 0 {
+                multiple / 10_f64.powi(-exponent)
+            } else {
+                multiple * magnitude
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,209 @@
+//! Renders a [`Scene`] as an SVG document, without a window or a GPU.
+use std::fmt::Write;
+
+use egui::Color32;
+
+use crate::layout::{
+    tick_labels, Frame, Legend, AXIS_COLOR, FONT_SIZE, GRID_COLOR, LABEL_GAP, TICK_LENGTH,
+};
+use crate::scene::{Curve, Dash, Fill, Item, Scene};
+
+/// Path data of a polyline, broken where a point is not finite.
+fn path(frame: &Frame, points: &[[f64; 2]], closed: bool) -> String {
+    let mut data = String::new();
+    let mut pen_down = false;
+    for [x, y] in points {
+        if !x.is_finite() || !y.is_finite() {
+            pen_down = false;
+            continue;
+        }
+        let command = if pen_down { 'L' } else { 'M' };
+        write!(data, "{}{:.2} {:.2}", command, frame.x(*x), frame.y(*y)).unwrap();
+        pen_down = true;
+    }
+    if closed && !data.is_empty() {
+        data.push('Z');
+    }
+    data
+}
+
+/// The scene as an SVG image of `width` by `height` pixels, with its grid, axes, ticks and
+/// legend.
+pub fn render_svg(scene: &Scene, width: u32, height: u32) -> String {
+    let frame = Frame::new(scene, width, height);
+    let (plot_width, plot_height) = (frame.width(), frame.height());
+    let (grid_color, axis_color) = (color(GRID_COLOR).0, color(AXIS_COLOR).0);
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="{FONT_SIZE}">"#
+    )
+    .unwrap();
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="white"/>"#).unwrap();
+    writeln!(
+        svg,
+        r#"<defs><clipPath id="plot-area"><rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}"/></clipPath></defs>"#,
+        frame.left, frame.top, plot_width, plot_height
+    )
+    .unwrap();
+
+    if scene.cartesian_grid {
+        writeln!(svg, r#"<g stroke="{grid_color}" stroke-width="1">"#).unwrap();
+        for x in &frame.x_ticks {
+            let x = frame.x(*x);
+            line(&mut svg, [x, frame.top], [x, frame.bottom]);
+        }
+        for y in &frame.y_ticks {
+            let y = frame.y(*y);
+            line(&mut svg, [frame.left, y], [frame.right, y]);
+        }
+        writeln!(svg, "</g>").unwrap();
+    }
+
+    writeln!(svg, r#"<g clip-path="url(#plot-area)">"#).unwrap();
+    for item in &scene.items {
+        match item {
+            Item::Curve(curve) => write_curve(&mut svg, &frame, curve),
+            Item::Fill(fill) => write_fill(&mut svg, &frame, fill),
+        }
+    }
+    writeln!(svg, "</g>").unwrap();
+
+    // the frame with its ticks, labelled below and on the left
+    writeln!(svg, r#"<g stroke="{axis_color}" stroke-width="1">"#).unwrap();
+    writeln!(
+        svg,
+        r#"<rect x="{:.2}" y="{:.2}" width="{:.2}" height="{:.2}" fill="none"/>"#,
+        frame.left, frame.top, plot_width, plot_height
+    )
+    .unwrap();
+    for x in &frame.x_ticks {
+        let x = frame.x(*x);
+        line(&mut svg, [x, frame.bottom], [x, frame.bottom + TICK_LENGTH]);
+    }
+    for y in &frame.y_ticks {
+        let y = frame.y(*y);
+        line(&mut svg, [frame.left - TICK_LENGTH, y], [frame.left, y]);
+    }
+    writeln!(svg, "</g>").unwrap();
+    writeln!(svg, r#"<g fill="{axis_color}">"#).unwrap();
+    for (x, label) in frame.x_ticks.iter().zip(tick_labels(&frame.x_ticks)) {
+        let (px, py) = (frame.x(*x), frame.bottom + LABEL_GAP + FONT_SIZE);
+        writeln!(
+            svg,
+            r#"<text x="{px:.2}" y="{py:.2}" text-anchor="middle">{label}</text>"#
+        )
+        .unwrap();
+    }
+    for (y, label) in frame.y_ticks.iter().zip(tick_labels(&frame.y_ticks)) {
+        let (px, py) = (frame.left - LABEL_GAP, frame.y(*y) + FONT_SIZE / 3.0);
+        writeln!(
+            svg,
+            r#"<text x="{px:.2}" y="{py:.2}" text-anchor="end">{label}</text>"#
+        )
+        .unwrap();
+    }
+    writeln!(svg, "</g>").unwrap();
+
+    if let Some(legend) = Legend::new(&frame, scene) {
+        write_legend(&mut svg, &legend);
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn line(svg: &mut String, [x1, y1]: [f64; 2], [x2, y2]: [f64; 2]) {
+    writeln!(
+        svg,
+        r#"<line x1="{x1:.2}" y1="{y1:.2}" x2="{x2:.2}" y2="{y2:.2}"/>"#
+    )
+    .unwrap();
+}
+
+fn write_curve(svg: &mut String, frame: &Frame, curve: &Curve) {
+    let (color, opacity) = color(curve.color);
+    let dash = match curve.dash {
+        Dash::Solid => String::new(),
+        Dash::Dashed { length } => format!(r#" stroke-dasharray="{length} {length}""#),
+        // zero length dashes with round caps are dots
+        Dash::Dotted { spacing } => format!(r#" stroke-dasharray="0 {spacing}""#),
+    };
+    for segment in &curve.segments {
+        let data = path(frame, segment, false);
+        if data.is_empty() {
+            continue;
+        }
+        writeln!(
+            svg,
+            r#"<path d="{data}" fill="none" stroke="{color}" stroke-opacity="{opacity}" stroke-width="{}" stroke-linecap="round" stroke-linejoin="round"{dash}/>"#,
+            curve.width
+        )
+        .unwrap();
+    }
+}
+
+fn write_fill(svg: &mut String, frame: &Frame, fill: &Fill) {
+    let (color, opacity) = color(fill.color);
+    for polygon in &fill.polygons {
+        let data = path(frame, polygon, true);
+        if data.is_empty() {
+            continue;
+        }
+        writeln!(
+            svg,
+            r#"<path d="{data}" fill="{color}" fill-opacity="{opacity}" stroke="none"/>"#
+        )
+        .unwrap();
+    }
+}
+
+fn write_legend(svg: &mut String, legend: &Legend<'_>) {
+    let Legend {
+        x,
+        y,
+        width,
+        height,
+        ..
+    } = legend;
+    let grid_color = color(GRID_COLOR).0;
+    writeln!(svg, "<g>").unwrap();
+    writeln!(
+        svg,
+        r#"<rect x="{x:.2}" y="{y:.2}" width="{width:.2}" height="{height:.2}" fill="white" fill-opacity="0.8" stroke="{grid_color}"/>"#
+    )
+    .unwrap();
+    let (start, end) = legend.sample();
+    for (k, (name, entry_color)) in legend.entries.iter().enumerate() {
+        let (color, opacity) = color(*entry_color);
+        let center = legend.center(k);
+        writeln!(
+            svg,
+            r#"<line x1="{start:.2}" y1="{center:.2}" x2="{end:.2}" y2="{center:.2}" stroke="{color}" stroke-opacity="{opacity}" stroke-width="3"/>"#
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            r#"<text x="{:.2}" y="{:.2}">{}</text>"#,
+            legend.text_x(),
+            center + FONT_SIZE / 3.0,
+            escape(name)
+        )
+        .unwrap();
+    }
+    writeln!(svg, "</g>").unwrap();
+}
+
+/// The color as `#rrggbb` with its opacity.
+fn color(color: Color32) -> (String, String) {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let opacity = format!("{:.3}", a as f64 / 255.0);
+    let opacity = opacity.trim_end_matches('0').trim_end_matches('.');
+    (format!("#{r:02x}{g:02x}{b:02x}"), opacity.to_owned())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,75 @@
+//! Families of curves, a function being drawn once per value of one of its parameters.
+use std::ops::RangeInclusive;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::PlotError;
+use crate::evaluation::Function;
+
+/// The values a parameter takes along a sweep.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SweepValues {
+    /// `steps` evenly spaced values from `from` to `to`, both included.
+    Range { from: f64, to: f64, steps: usize },
+    /// Comma separated values, as typed, like `0.5, 1, 2`.
+    List(String),
+}
+impl SweepValues {
+    pub const DEFAULT_STEPS: usize = 5;
+    /// Bounds of the number of curves of a range.
+    pub const STEPS_RANGE: RangeInclusive<usize> = 1..=100;
+
+    /// The values in order, or the first item of a list that is not a number.
+    pub fn values(&self) -> Result<Vec<f64>, PlotError> {
+        match self {
+            Self::Range { from, to, steps } => Ok(match steps {
+                0 => vec![],
+                1 => vec![*from],
+                _ => (0..*steps)
+                    .map(|k| from + (to - from) * k as f64 / (steps - 1) as f64)
+                    .collect(),
+            }),
+            Self::List(text) => text
+                .split(',')
+                .map(str::trim)
+                // a trailing comma is left while typing the next value
+                .filter(|item| !item.is_empty())
+                .map(|item| {
+                    item.parse().map_err(|_| PlotError::Parse {
+                        message: format!("`{}` is not a number", item),
+                        position: None,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Draws a function once per value of `parameter`, the other parameters keeping their value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParameterSweep {
+    pub parameter: String,
+    pub values: SweepValues,
+}
+impl ParameterSweep {
+    pub fn new(parameter: String, values: SweepValues) -> Self {
+        Self { parameter, values }
+    }
+    /// A copy of `function` for each value of the sweep, with that value.
+    ///
+    /// Fails if `function` does not use the parameter or a value is not a number.
+    pub fn family(&self, function: &Function) -> Result<Vec<(f64, Function)>, PlotError> {
+        if !function.vars_names().contains(&self.parameter) {
+            return Err(PlotError::UnboundVariable(self.parameter.to_owned()));
+        }
+        self.values
+            .values()?
+            .into_iter()
+            .map(|value| {
+                let mut member = function.clone();
+                member.assign_value_to_var(self.parameter.to_owned(), value)?;
+                Ok((value, member))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,822 @@
+//! Symbolic manipulation of [`Expression`] trees.
+use std::cmp::Ordering;
+
+use crate::compiled::{CompiledExpression, CONSTANTS};
+use crate::error::PlotError;
+use crate::parser::{Expression, Operator, UnaryOperator};
+
+fn number(value: f64) -> Expression {
+    Expression::Number(value)
+}
+
+fn is_number(expression: &Expression, value: f64) -> bool {
+    matches!(expression, Expression::Number(v) if *v == value)
+}
+
+fn call(name: &str, arg: Expression) -> Expression {
+    Expression::Call(name.to_owned(), vec![arg])
+}
+
+// The constructors below skip the trivial terms differentiation produces, like `0 * u` or `u^1`.
+
+fn add(left: Expression, right: Expression) -> Expression {
+    if is_number(&left, 0.0) {
+        right
+    } else if is_number(&right, 0.0) {
+        left
+    } else {
+        Expression::Binary(Operator::Add, Box::new(left), Box::new(right))
+    }
+}
+
+fn subtract(left: Expression, right: Expression) -> Expression {
+    if is_number(&right, 0.0) {
+        left
+    } else if is_number(&left, 0.0) {
+        negate(right)
+    } else {
+        Expression::Binary(Operator::Subtract, Box::new(left), Box::new(right))
+    }
+}
+
+fn multiply(left: Expression, right: Expression) -> Expression {
+    if is_number(&left, 0.0) || is_number(&right, 0.0) {
+        number(0.0)
+    } else if is_number(&left, 1.0) {
+        right
+    } else if is_number(&right, 1.0) {
+        left
+    } else {
+        Expression::Binary(Operator::Multiply, Box::new(left), Box::new(right))
+    }
+}
+
+fn divide(left: Expression, right: Expression) -> Expression {
+    if is_number(&left, 0.0) {
+        number(0.0)
+    } else if is_number(&right, 1.0) {
+        left
+    } else {
+        Expression::Binary(Operator::Divide, Box::new(left), Box::new(right))
+    }
+}
+
+fn pow(base: Expression, exponent: Expression) -> Expression {
+    if is_number(&exponent, 1.0) {
+        base
+    } else if is_number(&exponent, 0.0) {
+        number(1.0)
+    } else {
+        Expression::Binary(Operator::Pow, Box::new(base), Box::new(exponent))
+    }
+}
+
+fn negate(operand: Expression) -> Expression {
+    match operand {
+        Expression::Number(value) => number(-value),
+        Expression::Unary(UnaryOperator::Minus, inner) => *inner,
+        operand => Expression::Unary(UnaryOperator::Minus, Box::new(operand)),
+    }
+}
+
+impl Expression {
+    /// Whether the value of the expression changes with `variable`.
+    pub fn depends_on(&self, variable: &str) -> bool {
+        let mut found = false;
+        self.visit(&mut |expression| {
+            found |= matches!(expression, Expression::Variable(name) if name == variable);
+        });
+        found
+    }
+
+    /// Replaces every occurrence of the variable `name` with `replacement`.
+    pub fn substitute(&self, name: &str, replacement: &Expression) -> Expression {
+        let substitute =
+            |expression: &Expression| Box::new(expression.substitute(name, replacement));
+        match self {
+            Self::Variable(variable) if variable == name => replacement.clone(),
+            Self::Number(_) | Self::Variable(_) => self.clone(),
+            Self::Paren(inner) => Self::Paren(substitute(inner)),
+            Self::Unary(op, operand) => Self::Unary(*op, substitute(operand)),
+            Self::Binary(op, left, right) => Self::Binary(*op, substitute(left), substitute(right)),
+            Self::Call(function, args) => Self::Call(
+                function.to_owned(),
+                args.iter()
+                    .map(|arg| arg.substitute(name, replacement))
+                    .collect(),
+            ),
+            Self::Piecewise(branches, otherwise) => Self::Piecewise(
+                branches
+                    .iter()
+                    .map(|(condition, value)| {
+                        (
+                            condition.substitute(name, replacement),
+                            value.substitute(name, replacement),
+                        )
+                    })
+                    .collect(),
+                otherwise.as_deref().map(substitute),
+            ),
+        }
+    }
+
+    /// Derivative of the expression with respect to `variable`.
+    ///
+    /// Piecewise constant functions such as `floor` have a derivative of 0, as do conditions.
+    /// Conditionals are differentiated branch by branch, ignoring the jumps between branches.
+    /// `min`, `max` and a remainder by a non constant are not differentiable.
+    pub fn derivative(&self, variable: &str) -> Result<Expression, PlotError> {
+        if !self.depends_on(variable) {
+            return Ok(number(0.0));
+        }
+        let d = |expression: &Expression| expression.derivative(variable);
+        let derivative = match self {
+            Self::Number(_) => number(0.0),
+            Self::Variable(name) => number(if name == variable { 1.0 } else { 0.0 }),
+            Self::Paren(inner) | Self::Unary(UnaryOperator::Plus, inner) => d(inner)?,
+            Self::Unary(UnaryOperator::Minus, operand) => negate(d(operand)?),
+            Self::Unary(UnaryOperator::Not, _) => number(0.0),
+            Self::Binary(op, _, _) if op.precedence() < Operator::Add.precedence() => number(0.0),
+            Self::Binary(op, u, v) => {
+                let (u, v) = (u.as_ref().clone(), v.as_ref().clone());
+                match op {
+                    Operator::Add => add(d(&u)?, d(&v)?),
+                    Operator::Subtract => subtract(d(&u)?, d(&v)?),
+                    // (uv)' = u'v + uv'
+                    Operator::Multiply => {
+                        add(multiply(d(&u)?, v.clone()), multiply(u.clone(), d(&v)?))
+                    }
+                    // (u/v)' = (u'v - uv') / v^2
+                    Operator::Divide => divide(
+                        subtract(multiply(d(&u)?, v.clone()), multiply(u.clone(), d(&v)?)),
+                        pow(v, number(2.0)),
+                    ),
+                    Operator::Remainder if !v.depends_on(variable) => d(&u)?,
+                    Operator::Remainder => {
+                        return Err(PlotError::NotDifferentiable("%".to_owned()))
+                    }
+                    // (u^n)' = n u^(n-1) u'
+                    Operator::Pow if !v.depends_on(variable) => multiply(
+                        multiply(v.clone(), pow(u.clone(), subtract(v, number(1.0)))),
+                        d(&u)?,
+                    ),
+                    // (a^v)' = a^v ln(a) v'
+                    Operator::Pow if !u.depends_on(variable) => {
+                        multiply(multiply(self.clone(), call("ln", u)), d(&v)?)
+                    }
+                    // (u^v)' = u^v (v' ln(u) + v u' / u)
+                    Operator::Pow => multiply(
+                        self.clone(),
+                        add(
+                            multiply(d(&v)?, call("ln", u.clone())),
+                            divide(multiply(v, d(&u)?), u),
+                        ),
+                    ),
+                    _ => unreachable!("conditions are handled above"),
+                }
+            }
+            Self::Call(name, args) => match (name.as_str(), args.as_slice()) {
+                // atan2(y, x)' = (x y' - y x') / (x^2 + y^2)
+                ("atan2", [y, x]) => divide(
+                    subtract(multiply(x.clone(), d(y)?), multiply(y.clone(), d(x)?)),
+                    add(pow(x.clone(), number(2.0)), pow(y.clone(), number(2.0))),
+                ),
+                ("if", [condition, then, otherwise]) => Self::Call(
+                    "if".to_owned(),
+                    vec![condition.clone(), d(then)?, d(otherwise)?],
+                ),
+                (name, [u]) => multiply(Self::outer_derivative(name, u)?, d(u)?),
+                (name, _) => return Err(PlotError::NotDifferentiable(name.to_owned())),
+            },
+            Self::Piecewise(branches, otherwise) => Self::Piecewise(
+                branches
+                    .iter()
+                    .map(|(condition, value)| Ok((condition.clone(), d(value)?)))
+                    .collect::<Result<_, PlotError>>()?,
+                match otherwise {
+                    Some(otherwise) => Some(Box::new(d(otherwise)?)),
+                    None => None,
+                },
+            ),
+        };
+        Ok(derivative)
+    }
+
+    /// Derivative of the one argument function `name` evaluated at `u`, for the chain rule.
+    fn outer_derivative(name: &str, u: &Expression) -> Result<Expression, PlotError> {
+        let u = || u.clone();
+        let one_minus_u2 = || subtract(number(1.0), pow(u(), number(2.0)));
+        let derivative = match name {
+            "sin" => call("cos", u()),
+            "cos" => negate(call("sin", u())),
+            "tan" => divide(number(1.0), pow(call("cos", u()), number(2.0))),
+            "exp" => call("exp", u()),
+            "ln" => divide(number(1.0), u()),
+            "sqrt" => divide(number(1.0), multiply(number(2.0), call("sqrt", u()))),
+            "abs" => call("signum", u()),
+            "asin" => divide(number(1.0), call("sqrt", one_minus_u2())),
+            "acos" => negate(divide(number(1.0), call("sqrt", one_minus_u2()))),
+            "atan" => divide(number(1.0), add(number(1.0), pow(u(), number(2.0)))),
+            "sinh" => call("cosh", u()),
+            "cosh" => call("sinh", u()),
+            "tanh" => divide(number(1.0), pow(call("cosh", u()), number(2.0))),
+            "asinh" => divide(
+                number(1.0),
+                call("sqrt", add(pow(u(), number(2.0)), number(1.0))),
+            ),
+            "acosh" => divide(
+                number(1.0),
+                call("sqrt", subtract(pow(u(), number(2.0)), number(1.0))),
+            ),
+            "atanh" => divide(number(1.0), one_minus_u2()),
+            "floor" | "ceil" | "round" | "signum" => number(0.0),
+            _ => return Err(PlotError::NotDifferentiable(name.to_owned())),
+        };
+        Ok(derivative)
+    }
+}
+
+// Largest magnitude up to which every integer is exactly representable by an `f64`.
+const EXACT_INTEGERS: f64 = 9_007_199_254_740_992.0;
+
+fn is_exact_integer(value: f64) -> bool {
+    value.fract() == 0.0 && value.abs() < EXACT_INTEGERS
+}
+
+fn gcd(mut a: f64, mut b: f64) -> f64 {
+    while b != 0.0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// A numeric coefficient, kept as a fraction while it is made of integers so `x / 3` does not
+/// turn into `0.3333333333333333 * x`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rational {
+    numerator: f64,
+    denominator: f64,
+}
+impl Rational {
+    const ONE: Self = Self::integer(1.0);
+
+    const fn integer(value: f64) -> Self {
+        Self {
+            numerator: value,
+            denominator: 1.0,
+        }
+    }
+    fn new(numerator: f64, denominator: f64) -> Self {
+        if is_exact_integer(numerator) && is_exact_integer(denominator) {
+            let divisor = gcd(numerator.abs(), denominator.abs()).copysign(denominator);
+            Self {
+                numerator: numerator / divisor,
+                denominator: denominator / divisor,
+            }
+        } else {
+            Self::integer(numerator / denominator)
+        }
+    }
+    fn value(self) -> f64 {
+        self.numerator / self.denominator
+    }
+    fn is_zero(self) -> bool {
+        self.numerator == 0.0
+    }
+    fn add(self, other: Self) -> Self {
+        Self::new(
+            self.numerator * other.denominator + other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+    fn mul(self, other: Self) -> Self {
+        Self::new(
+            self.numerator * other.numerator,
+            self.denominator * other.denominator,
+        )
+    }
+    fn abs(self) -> Self {
+        Self::new(self.numerator.abs(), self.denominator)
+    }
+    /// `self^exponent`, `None` when it is not finite.
+    fn powi(self, exponent: i32) -> Option<Self> {
+        let (numerator, denominator) = if exponent < 0 {
+            (self.denominator, self.numerator)
+        } else {
+            (self.numerator, self.denominator)
+        };
+        let power = Self::new(
+            numerator.powi(exponent.abs()),
+            denominator.powi(exponent.abs()),
+        );
+        power.value().is_finite().then_some(power)
+    }
+}
+
+/// `base^exponent` inside a product.
+#[derive(Debug, Clone, PartialEq)]
+struct Factor {
+    base: Expression,
+    exponent: Expression,
+}
+impl Factor {
+    /// A factor that is not simplified any further.
+    fn opaque(base: Expression) -> Self {
+        Self {
+            base,
+            exponent: number(1.0),
+        }
+    }
+    fn numeric_exponent(&self) -> Option<f64> {
+        match self.exponent {
+            Expression::Number(exponent) => Some(exponent),
+            _ => None,
+        }
+    }
+    fn to_expression(&self) -> Expression {
+        pow(self.base.clone(), self.exponent.clone())
+    }
+    fn sort_key(&self) -> (bool, String, String) {
+        let is_symbolic = !matches!(self.base, Expression::Number(_));
+        (
+            is_symbolic,
+            self.base.to_string(),
+            self.exponent.to_string(),
+        )
+    }
+}
+
+/// `coefficient * factors[0] * factors[1] * ...` inside a sum.
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    coefficient: Rational,
+    factors: Vec<Factor>,
+}
+impl Term {
+    fn constant(coefficient: Rational) -> Self {
+        Self {
+            coefficient,
+            factors: vec![],
+        }
+    }
+    fn opaque(expression: Expression) -> Self {
+        Self {
+            coefficient: Rational::ONE,
+            factors: vec![Factor::opaque(expression)],
+        }
+    }
+    /// Combines the factors with the same base by adding their exponents.
+    fn with_merged_factors(self) -> Self {
+        let mut coefficient = self.coefficient;
+        let mut factors: Vec<Factor> = vec![];
+        for factor in self.factors {
+            match factors.iter_mut().find(|f| f.base == factor.base) {
+                Some(existing) => {
+                    existing.exponent = add(existing.exponent.clone(), factor.exponent).simplify()
+                }
+                None => factors.push(factor),
+            }
+        }
+        factors.retain(|factor| match (&factor.base, factor.numeric_exponent()) {
+            (_, Some(0.0)) => false,
+            // a number to an integer power is folded into the coefficient
+            (Expression::Number(base), Some(exponent)) if is_exact_integer(exponent) => {
+                match Rational::integer(*base).powi(exponent as i32) {
+                    Some(power) => {
+                        coefficient = coefficient.mul(power);
+                        false
+                    }
+                    None => true,
+                }
+            }
+            _ => true,
+        });
+        factors.sort_by_key(Factor::sort_key);
+        Self {
+            coefficient,
+            factors,
+        }
+    }
+    fn mul(mut self, other: Self) -> Self {
+        self.coefficient = self.coefficient.mul(other.coefficient);
+        self.factors.extend(other.factors);
+        self.with_merged_factors()
+    }
+    /// `self^exponent`, only valid for an integer `exponent`.
+    fn powi(self, exponent: i32) -> Option<Self> {
+        let factors = self
+            .factors
+            .into_iter()
+            .map(|factor| Factor {
+                exponent: multiply(factor.exponent, number(exponent.into())).simplify(),
+                base: factor.base,
+            })
+            .collect();
+        let term = Self {
+            coefficient: self.coefficient.powi(exponent)?,
+            factors,
+        };
+        Some(term.with_merged_factors())
+    }
+    /// Sum of the numeric exponents of the symbolic factors, the degree of a monomial.
+    fn degree(&self) -> f64 {
+        self.factors
+            .iter()
+            .filter(|factor| !matches!(factor.base, Expression::Number(_)))
+            .filter_map(Factor::numeric_exponent)
+            .sum()
+    }
+    /// Terms are printed by decreasing degree, as in `x^2 + 2 * x + 1`.
+    fn order(&self, other: &Self) -> Ordering {
+        let key = |term: &Self| {
+            term.factors
+                .iter()
+                .map(Factor::sort_key)
+                .collect::<Vec<_>>()
+        };
+        other
+            .degree()
+            .total_cmp(&self.degree())
+            .then_with(|| key(self).cmp(&key(other)))
+    }
+    fn to_expression(&self) -> Expression {
+        let mut numerator = vec![];
+        let mut denominator = vec![];
+        let coefficient = self.coefficient;
+        if coefficient.numerator.abs() != 1.0 || self.factors.is_empty() {
+            numerator.push(number(coefficient.numerator.abs()));
+        }
+        if coefficient.denominator != 1.0 {
+            denominator.push(number(coefficient.denominator));
+        }
+        for factor in &self.factors {
+            match factor.numeric_exponent() {
+                Some(exponent) if exponent < 0.0 => {
+                    denominator.push(pow(factor.base.clone(), number(-exponent)))
+                }
+                _ => numerator.push(factor.to_expression()),
+            }
+        }
+        if numerator.is_empty() {
+            numerator.push(number(1.0));
+        }
+        if coefficient.numerator < 0.0 {
+            let first = numerator.remove(0);
+            numerator.insert(0, negate(first));
+        }
+        let product = |factors: Vec<Expression>| {
+            factors
+                .into_iter()
+                .reduce(|product, factor| {
+                    Expression::Binary(Operator::Multiply, Box::new(product), Box::new(factor))
+                })
+                .unwrap_or(number(1.0))
+        };
+        let numerator = product(numerator);
+        if denominator.is_empty() {
+            numerator
+        } else {
+            Expression::Binary(
+                Operator::Divide,
+                Box::new(numerator),
+                Box::new(product(denominator)),
+            )
+        }
+    }
+}
+
+/// A sum of terms, the normal form the simplifier works on. Empty for 0.
+type Sum = Vec<Term>;
+
+/// Adds up like terms, drops the null ones and sorts them.
+fn collect(terms: Sum) -> Sum {
+    let mut collected: Sum = vec![];
+    // a factored sum left alone after a cancellation, as in `(x + 1)^2 / (x + 1)`, is expanded
+    let terms = terms
+        .into_iter()
+        .flat_map(|term| match term.factors.as_slice() {
+            [Factor {
+                base: base @ Expression::Binary(Operator::Add | Operator::Subtract, _, _),
+                exponent: Expression::Number(exponent),
+            }] if *exponent == 1.0 => to_sum(base)
+                .into_iter()
+                .map(|mut inner| {
+                    inner.coefficient = inner.coefficient.mul(term.coefficient);
+                    inner
+                })
+                .collect(),
+            _ => vec![term],
+        });
+    for term in terms {
+        match collected.iter_mut().find(|t| t.factors == term.factors) {
+            Some(existing) => existing.coefficient = existing.coefficient.add(term.coefficient),
+            None => collected.push(term),
+        }
+    }
+    collected.retain(|term| !term.coefficient.is_zero());
+    collected.sort_by(Term::order);
+    collected
+}
+
+/// Takes out a sum that is a factor of every term, the terms left may be alike, as in the
+/// derivative of `x * (x + 1)^2`, `(2 * x + 1) * (x + 1) + x * (x + 1)` becoming
+/// `(3 * x + 1) * (x + 1)`.
+fn factor_out(terms: Sum) -> Sum {
+    let Some(common) = common_sum_factor(&terms) else {
+        return terms;
+    };
+    let reciprocal = Term {
+        coefficient: Rational::ONE,
+        factors: vec![Factor {
+            base: common.base.clone(),
+            exponent: number(-common.numeric_exponent().expect("a numeric exponent")),
+        }],
+    };
+    let rest = terms.into_iter().map(|term| term.mul(reciprocal.clone()));
+    let common = Term {
+        coefficient: Rational::ONE,
+        factors: vec![common],
+    };
+    product(collect(rest.collect()), vec![common])
+}
+
+/// A sum, like `x + 1`, that is a factor of every term, at the lowest power it has in them.
+fn common_sum_factor(terms: &[Term]) -> Option<Factor> {
+    let (first, others) = terms
+        .split_first()
+        .filter(|(_, others)| !others.is_empty())?;
+    first.factors.iter().find_map(|factor| {
+        if !matches!(
+            factor.base,
+            Expression::Binary(Operator::Add | Operator::Subtract, _, _)
+        ) {
+            return None;
+        }
+        let exponent_in = |term: &Term| {
+            let factor = term.factors.iter().find(|f| f.base == factor.base)?;
+            factor.numeric_exponent().filter(|exponent| *exponent > 0.0)
+        };
+        let mut exponent = exponent_in(first)?;
+        for term in others {
+            exponent = exponent.min(exponent_in(term)?);
+        }
+        Some(Factor {
+            base: factor.base.clone(),
+            exponent: number(exponent),
+        })
+    })
+}
+
+fn sum_to_expression(terms: &[Term]) -> Expression {
+    let mut terms = terms.iter();
+    let Some(first) = terms.next() else {
+        return number(0.0);
+    };
+    terms.fold(first.to_expression(), |sum, term| {
+        let operator = if term.coefficient.numerator < 0.0 {
+            Operator::Subtract
+        } else {
+            Operator::Add
+        };
+        let term = Term {
+            coefficient: term.coefficient.abs(),
+            factors: term.factors.clone(),
+        };
+        Expression::Binary(operator, Box::new(sum), Box::new(term.to_expression()))
+    })
+}
+
+/// The sum as a single term, sums of several terms being kept whole as a factor.
+fn as_term(terms: Sum) -> Term {
+    match <[Term; 1]>::try_from(terms) {
+        Ok([term]) => term,
+        Err(terms) if terms.is_empty() => Term::constant(Rational::integer(0.0)),
+        Err(terms) => Term::opaque(sum_to_expression(&terms)),
+    }
+}
+
+fn as_constant(terms: &[Term]) -> Option<Rational> {
+    match terms {
+        [] => Some(Rational::integer(0.0)),
+        [term] if term.factors.is_empty() => Some(term.coefficient),
+        _ => None,
+    }
+}
+
+fn scale(terms: Sum, coefficient: Rational) -> Sum {
+    let terms = terms.into_iter().map(|mut term| {
+        term.coefficient = term.coefficient.mul(coefficient);
+        term
+    });
+    collect(terms.collect())
+}
+
+fn product(left: Sum, right: Sum) -> Sum {
+    // constants are distributed, other products of sums are kept factored
+    match (as_constant(&left), as_constant(&right)) {
+        (Some(coefficient), _) => scale(right, coefficient),
+        (_, Some(coefficient)) => scale(left, coefficient),
+        _ => collect(vec![as_term(left).mul(as_term(right))]),
+    }
+}
+
+fn quotient(numerator: Sum, denominator: Sum, original: &Expression) -> Sum {
+    let reciprocal = match as_constant(&denominator) {
+        Some(coefficient) if coefficient.is_zero() => return vec![Term::opaque(original.clone())],
+        Some(coefficient) => Term::constant(Rational::ONE).mul(Term::constant(Rational::new(
+            coefficient.denominator,
+            coefficient.numerator,
+        ))),
+        None => match as_term(denominator).powi(-1) {
+            Some(reciprocal) => reciprocal,
+            None => return vec![Term::opaque(original.clone())],
+        },
+    };
+    product(numerator, vec![reciprocal])
+}
+
+fn power(base: Sum, exponent: Expression) -> Sum {
+    let numeric_exponent = match exponent {
+        Expression::Number(exponent) => Some(exponent),
+        _ => None,
+    };
+    match (as_constant(&base), numeric_exponent) {
+        (_, Some(0.0)) => return vec![Term::constant(Rational::ONE)],
+        (_, Some(1.0)) => return base,
+        (Some(base), _) if base == Rational::ONE => return vec![Term::constant(base)],
+        // a constant to a non integer power is only folded when the result is exact, like `4^0.5`
+        (Some(base), Some(exponent)) if !is_exact_integer(exponent) => {
+            let value = base.value().powf(exponent);
+            if is_exact_integer(value) {
+                return vec![Term::constant(Rational::integer(value))];
+            }
+        }
+        _ => {}
+    }
+    let term = as_term(base);
+    match numeric_exponent {
+        Some(exponent) if is_exact_integer(exponent) && exponent.abs() <= i32::MAX as f64 => {
+            if let Some(power) = term.clone().powi(exponent as i32) {
+                return collect(vec![power]);
+            }
+        }
+        _ => {}
+    }
+    // `(x^2)^0.5` is `abs(x)`, not `x`, so exponents only multiply for integer powers
+    let factor = match term {
+        Term {
+            coefficient: Rational::ONE,
+            mut factors,
+        } if factors.len() == 1 && factors[0].exponent == number(1.0) => Factor {
+            base: factors.remove(0).base,
+            exponent,
+        },
+        term => Factor {
+            base: sum_to_expression(&[term]),
+            exponent,
+        },
+    };
+    collect(vec![Term {
+        coefficient: Rational::ONE,
+        factors: vec![factor],
+    }
+    .with_merged_factors()])
+}
+
+/// Folds a call whose arguments are all numbers or constants, when the result is an integer
+/// like `sin(0)` or `ln(e)`.
+fn call_value(name: &str, args: &[Expression]) -> Option<f64> {
+    let is_constant = |arg: &Expression| match arg {
+        Expression::Number(_) => true,
+        Expression::Variable(name) => CONSTANTS.iter().any(|(constant, _)| constant == name),
+        _ => false,
+    };
+    if !args.iter().all(is_constant) {
+        return None;
+    }
+    let call = Expression::Call(name.to_owned(), args.to_vec());
+    let value = CompiledExpression::new(&call, &[]).ok()?.eval(0.0, &[]);
+    is_exact_integer(value).then_some(value)
+}
+
+/// Keeps a condition or a conditional as a whole, folded when it is made of numbers only.
+fn condition(expression: Expression) -> Sum {
+    let mut constant = true;
+    expression.visit(&mut |e| constant &= !matches!(e, Expression::Variable(_)));
+    let value = constant
+        .then(|| CompiledExpression::new(&expression, &[]).ok())
+        .flatten()
+        .map(|compiled| compiled.eval(0.0, &[]))
+        .filter(|value| is_exact_integer(*value));
+    match value {
+        Some(value) => collect(vec![Term::constant(Rational::integer(value))]),
+        None => vec![Term::opaque(expression)],
+    }
+}
+
+/// `left op right` for one of the four operations.
+fn arithmetic(op: Operator, left: &Expression, right: &Expression) -> Sum {
+    let sum = match op {
+        Operator::Add => collect([to_sum(left), to_sum(right)].concat()),
+        Operator::Subtract => {
+            let right = scale(to_sum(right), Rational::integer(-1.0));
+            collect([to_sum(left), right].concat())
+        }
+        Operator::Multiply => product(to_sum(left), to_sum(right)),
+        _ => {
+            let original = Expression::Binary(op, Box::new(left.clone()), Box::new(right.clone()));
+            quotient(to_sum(left), to_sum(right), &original)
+        }
+    };
+    // a coefficient that overflows is not folded, `1e308 * 10` is not `inf`
+    if sum.iter().all(|term| term.coefficient.value().is_finite()) {
+        sum
+    } else {
+        vec![Term::opaque(Expression::Binary(
+            op,
+            Box::new(left.simplify()),
+            Box::new(right.simplify()),
+        ))]
+    }
+}
+
+fn to_sum(expression: &Expression) -> Sum {
+    match expression {
+        Expression::Number(value) => collect(vec![Term::constant(Rational::integer(*value))]),
+        Expression::Variable(_) => vec![Term::opaque(expression.clone())],
+        Expression::Paren(inner) | Expression::Unary(UnaryOperator::Plus, inner) => to_sum(inner),
+        Expression::Unary(UnaryOperator::Minus, operand) => {
+            scale(to_sum(operand), Rational::integer(-1.0))
+        }
+        Expression::Unary(UnaryOperator::Not, operand) => condition(Expression::Unary(
+            UnaryOperator::Not,
+            Box::new(operand.simplify()),
+        )),
+        Expression::Binary(op, left, right) => match op {
+            Operator::Pow => power(to_sum(left), right.simplify()),
+            Operator::Remainder => {
+                let (left, right) = (left.simplify(), right.simplify());
+                match (&left, &right) {
+                    (Expression::Number(l), Expression::Number(r)) if (l % r).is_finite() => {
+                        collect(vec![Term::constant(Rational::integer(l % r))])
+                    }
+                    _ => vec![Term::opaque(Expression::Binary(
+                        Operator::Remainder,
+                        Box::new(left),
+                        Box::new(right),
+                    ))],
+                }
+            }
+            Operator::Add | Operator::Subtract | Operator::Multiply | Operator::Divide => {
+                arithmetic(*op, left, right)
+            }
+            Operator::Less
+            | Operator::LessOrEqual
+            | Operator::Greater
+            | Operator::GreaterOrEqual
+            | Operator::Equal
+            | Operator::NotEqual
+            | Operator::And
+            | Operator::Or => condition(Expression::Binary(
+                *op,
+                Box::new(left.simplify()),
+                Box::new(right.simplify()),
+            )),
+        },
+        Expression::Call(name, args) => {
+            let args: Vec<_> = args.iter().map(Expression::simplify).collect();
+            if let ("if", [Expression::Number(condition), then, otherwise]) =
+                (name.as_str(), args.as_slice())
+            {
+                if !condition.is_nan() {
+                    return to_sum(if *condition != 0.0 { then } else { otherwise });
+                }
+            }
+            match call_value(name, &args) {
+                Some(value) => collect(vec![Term::constant(Rational::integer(value))]),
+                None => vec![Term::opaque(Expression::Call(name.to_owned(), args))],
+            }
+        }
+        Expression::Piecewise(branches, otherwise) => condition(Expression::Piecewise(
+            branches
+                .iter()
+                .map(|(condition, value)| (condition.simplify(), value.simplify()))
+                .collect(),
+            otherwise
+                .as_ref()
+                .map(|otherwise| Box::new(otherwise.simplify())),
+        )),
+    }
+}
+
+impl Expression {
+    /// Rewrites the expression in a simpler canonical form.
+    ///
+    /// Constants are folded, like terms and like factors are collected, as in `x * x + 2 * x^2`
+    /// becoming `3 * x^2`, and terms are sorted by decreasing degree. Products of sums are not
+    /// expanded, a sum that is a factor of every term is taken out. The result can be defined
+    /// in more places than the original, `x / x` becomes `1`.
+    pub fn simplify(&self) -> Expression {
+        sum_to_expression(&factor_out(to_sum(self)))
+    }
+}
@@ -0,0 +1,79 @@
+use plotting_rs::animation::{Animation, AnimationMode};
+use plotting_rs::evaluation::Function;
+use plotting_rs::plotting::PlottingApp;
+
+fn playing(mode: AnimationMode, speed: f64) -> Animation {
+    let mut animation = Animation::new(mode, speed);
+    animation.play();
+    animation
+}
+
+#[test]
+fn loop_starts_again_from_the_lower_limit() {
+    let mut animation = playing(AnimationMode::Loop, 2.0);
+    assert_eq!(animation.advance(0.0, (0.0, 4.0), 1.0), 2.0);
+    assert_eq!(animation.advance(3.0, (0.0, 4.0), 1.0), 1.0);
+    // values outside of the limits are brought back first
+    assert_eq!(animation.advance(-5.0, (0.0, 4.0), 0.5), 1.0);
+    assert!(animation.playing);
+}
+
+#[test]
+fn bounce_goes_back_and_forth() {
+    let mut animation = playing(AnimationMode::Bounce, 2.0);
+    let mut value = 0.0;
+    let mut values = vec![];
+    for _ in 0..6 {
+        value = animation.advance(value, (0.0, 4.0), 0.75);
+        values.push(value);
+    }
+    assert_eq!(values, [1.5, 3.0, 3.5, 2.0, 0.5, 1.0]);
+}
+
+#[test]
+fn once_stops_at_the_upper_limit() {
+    let mut animation = playing(AnimationMode::Once, 1.0);
+    assert_eq!(animation.advance(3.5, (0.0, 4.0), 1.0), 4.0);
+    assert!(!animation.playing);
+    assert_eq!(animation.advance(4.0, (0.0, 4.0), 1.0), 4.0);
+    // played again from the end, it starts over
+    animation.play();
+    assert_eq!(animation.advance(4.0, (0.0, 4.0), 1.0), 1.0);
+}
+
+#[test]
+fn stopped_animations_keep_the_value() {
+    let mut animation = Animation::default();
+    assert_eq!(animation.advance(1.0, (0.0, 4.0), 1.0), 1.0);
+    animation.play();
+    // nothing to sweep within empty limits
+    assert_eq!(animation.advance(1.0, (1.0, 1.0), 1.0), 1.0);
+}
+
+#[test]
+fn app_animates_parameters_until_paused() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("a*x".to_owned(), "f1".to_owned()).unwrap());
+    app.set_parameter_limits("a", (0.0, 10.0));
+    app.set_parameter("a", 0.0);
+    // nothing to draw again while nothing plays
+    assert!(!app.animate(1.0));
+    assert!(app.set_parameter_animation("a", playing(AnimationMode::Loop, 2.0)));
+    assert!(!app.set_parameter_animation("b", Animation::default()));
+
+    assert!(app.animate(1.0));
+    assert_eq!(app.parameter("a"), Some(2.0));
+    assert_eq!(app.functions()[0].eval(1.0), 2.0);
+
+    app.set_animations_paused(true);
+    assert!(!app.animate(1.0));
+    assert_eq!(app.parameter("a"), Some(2.0));
+    app.set_animations_paused(false);
+    assert!(app.animate(1.0));
+    assert_eq!(app.parameter("a"), Some(4.0));
+
+    // derived parameters follow the animated ones
+    app.set_parameter_expression("b", Some("a / 2")).unwrap();
+    app.animate(1.0);
+    assert_eq!(app.parameter("b"), Some(3.0));
+}
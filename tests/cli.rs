@@ -0,0 +1,208 @@
+use std::path::PathBuf;
+
+use plotting_rs::cli::{Command, ImageOutput, TableFormat, DEFAULT_SIZE};
+use plotting_rs::error::PlotError;
+
+fn args(line: &[&str]) -> Vec<String> {
+    line.iter().map(|arg| arg.to_string()).collect()
+}
+
+/// What the command writes to the standard output.
+fn run(line: &[&str]) -> Result<String, PlotError> {
+    let mut out = vec![];
+    Command::parse(&args(line))?.run(&mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn commands_are_parsed() {
+    let plot = Command::parse(&args(&[
+        "plot", "sin(x*a)", "--param", "a=2", "--xlim", "-5,5", "-o", "out.svg",
+    ]));
+    assert_eq!(
+        plot.unwrap(),
+        Command::Plot {
+            expressions: vec!["sin(x*a)".to_owned()],
+            parameters: vec![("a".to_owned(), 2.0)],
+            limits: vec![],
+            xlim: (-5.0, 5.0),
+            ylim: None,
+            image: ImageOutput {
+                path: Some(PathBuf::from("out.svg")),
+                size: DEFAULT_SIZE,
+            },
+        }
+    );
+    // an expression starting like an option is still an expression
+    let eval = Command::parse(&args(&["eval", "-x", "--at", "1.5", "--format", "json"]));
+    assert_eq!(
+        eval.unwrap(),
+        Command::Eval {
+            expression: "-x".to_owned(),
+            parameters: vec![],
+            limits: vec![],
+            at: vec![1.5],
+            format: TableFormat::Json,
+        }
+    );
+    let render = Command::parse(&args(&["--png", "out.png", "--size", "40x30", "w.json"]));
+    assert_eq!(
+        render.unwrap(),
+        Command::Render {
+            workspace: PathBuf::from("w.json"),
+            image: ImageOutput {
+                path: Some(PathBuf::from("out.png")),
+                size: (40, 30),
+            },
+        }
+    );
+    assert_eq!(Command::parse(&[]).unwrap(), Command::Help);
+}
+
+#[test]
+fn invalid_command_lines_are_reported() {
+    let invalid: [&[&str]; 18] = [
+        &["frob"],
+        &["plot"],
+        &["plot", "x", "--at", "1"],
+        &["plot", "x", "--xlim", "5,-5"],
+        &["plot", "x", "--xlim", "1,1"],
+        &["plot", "x", "--xlim", "-inf,5"],
+        &["plot", "x", "--ylim", "NaN,1"],
+        &["plot", "x", "-o", "out.gif"],
+        &["eval", "x"],
+        &["eval", "x", "y", "--at", "1"],
+        &["eval", "x", "--at"],
+        &["table", "x", "--from", "0", "--to", "1", "--step", "-1"],
+        &["table", "x", "--from", "0", "--to", "1", "--step", "1e-300"],
+        &["table", "x", "--from", "0", "--to", "inf", "--step", "1"],
+        &["eval", "a*x", "--at", "1", "--param", "a=inf"],
+        &["eval", "a*x", "--at", "1", "--param", "a=1", "--limit", "b=0,2"],
+        &["eval", "a*x", "--at", "1", "--param", "a=5", "--limit", "a=0,2"],
+        &["eval", "a*x", "--at", "1", "--param", "a=1", "--limit", "a=2"],
+    ];
+    for line in invalid {
+        assert!(
+            matches!(Command::parse(&args(line)), Err(PlotError::Usage(_))),
+            "{:?}",
+            line
+        );
+    }
+}
+
+#[test]
+fn values_are_printed_as_csv_or_json() {
+    let value = run(&["eval", "sin(x*a)", "--at", "1.5", "--param", "a=2"]).unwrap();
+    assert_eq!(value.trim().parse::<f64>().unwrap(), 3f64.sin());
+    assert_eq!(
+        run(&["eval", "x^2", "--at", "1", "--at", "2"]).unwrap(),
+        "1\n4\n"
+    );
+    // nothing but the value is printed, even for a constant
+    assert_eq!(run(&["eval", "2+3", "--at", "1"]).unwrap(), "5\n");
+    let json = run(&["eval", "x^2", "--at", "1", "--at", "2", "--format", "json"]);
+    assert_eq!(json.unwrap(), "[1.0,4.0]\n");
+    let json = run(&["eval", "x^2", "--at", "3", "--format", "json"]);
+    assert_eq!(json.unwrap(), "[9.0]\n");
+
+    let table = run(&[
+        "table", "2*x", "--from", "0", "--to", "0.3", "--step", "0.1",
+    ]);
+    assert_eq!(table.unwrap(), "x,y\n0,0\n0.1,0.2\n0.2,0.4\n0.3,0.6\n");
+    let table = run(&[
+        "table", "sqrt(x)", "--from", "-1", "--to", "0", "--step", "1", "--format", "json",
+    ]);
+    // undefined values are null
+    assert_eq!(
+        table.unwrap(),
+        r#"[{"x":-1.0,"y":null},{"x":0.0,"y":0.0}]"#.to_owned() + "\n"
+    );
+}
+
+#[test]
+fn small_steps_give_distinct_points() {
+    let table = run(&[
+        "table", "x", "--from", "0", "--to", "1e-12", "--step", "1e-13",
+    ]);
+    let xs: Vec<f64> = table
+        .unwrap()
+        .lines()
+        .skip(1)
+        .map(|row| row.split(',').next().unwrap().parse().unwrap())
+        .collect();
+    assert_eq!(xs.len(), 11);
+    assert!(xs.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(xs[10], 1e-12);
+}
+
+#[test]
+fn parameters_need_a_value() {
+    assert_eq!(
+        run(&["eval", "a*x", "--at", "1"]),
+        Err(PlotError::UnboundVariable("a".to_owned()))
+    );
+    // beyond the range of the sliders of the app
+    assert_eq!(
+        run(&["eval", "a*x", "--at", "2", "--param", "a=50"]).unwrap(),
+        "100\n"
+    );
+    // within a range of its own
+    let line = [
+        "eval", "a*x", "--at", "2", "--param", "a=10", "--limit", "a=0,20",
+    ];
+    assert_eq!(run(&line).unwrap(), "20\n");
+    assert!(matches!(
+        Command::parse(&args(&line)).unwrap(),
+        Command::Eval { limits, .. } if limits == [("a".to_owned(), (0.0, 20.0))]
+    ));
+}
+
+#[test]
+fn plots_and_workspaces_are_rendered() {
+    let svg = run(&[
+        "plot", "sin(x*a)", "f1(x)/2", "--param", "a=2", "--size", "300x200",
+    ]);
+    let svg = svg.unwrap();
+    assert!(svg.starts_with("<svg") && svg.contains(r#"width="300""#));
+    assert!(svg.contains(">f2</text>"));
+
+    let directory = std::env::temp_dir();
+    let (workspace, image) = (
+        directory.join("plotting-rs-cli.json"),
+        directory.join("plotting-rs-cli.png"),
+    );
+    std::fs::write(
+        &workspace,
+        r#"{ "functions": [{ "name": "g", "expression": "x^2" }] }"#,
+    )
+    .unwrap();
+    let line = [
+        "render",
+        workspace.to_str().unwrap(),
+        "-o",
+        image.to_str().unwrap(),
+        "--size",
+        "120x90",
+    ];
+    assert_eq!(run(&line).unwrap(), "");
+    let png = std::fs::read(&image).unwrap();
+    assert_eq!(&png[1..4], b"PNG");
+    std::fs::remove_file(&workspace).unwrap();
+    std::fs::remove_file(&image).unwrap();
+}
+
+#[test]
+fn workspaces_with_invalid_expressions_are_not_rendered() {
+    let workspace = std::env::temp_dir().join("plotting-rs-cli-invalid.json");
+    std::fs::write(
+        &workspace,
+        r#"{"functions":[{"name":"f1","expression":"sin(x"}]}"#,
+    )
+    .unwrap();
+    let result = run(&["render", workspace.to_str().unwrap()]);
+    std::fs::remove_file(&workspace).unwrap();
+    match result {
+        Err(PlotError::Document(message)) => assert!(message.starts_with("f1: ")),
+        result => panic!("rendered anyway: {:?}", result.map(|svg| svg.len())),
+    }
+}
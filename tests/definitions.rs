@@ -0,0 +1,107 @@
+use plotting_rs::definitions::{parse_definition, Definitions};
+use plotting_rs::error::PlotError;
+use plotting_rs::evaluation::Function;
+use plotting_rs::parser::Expression;
+use plotting_rs::plotting::PlottingApp;
+
+fn definitions(functions: &[(&str, &str)]) -> Definitions {
+    let mut definitions = Definitions::default();
+    for (name, body) in functions {
+        definitions.define(name.to_string(), Expression::parse(body).unwrap());
+    }
+    definitions
+}
+
+fn expand(definitions: &Definitions, text: &str) -> Result<String, PlotError> {
+    let expanded = definitions.expand(&Expression::parse(text).unwrap())?;
+    Ok(expanded.to_string())
+}
+
+#[test]
+fn calls_are_replaced_by_the_definitions() {
+    let definitions = definitions(&[("f1", "sin(a*x)"), ("g", "f1(x) + x")]);
+    assert_eq!(
+        expand(&definitions, "f1(x)^2 + f1(x - 1)").unwrap(),
+        "sin(a * x)^2 + sin(a * (x - 1))"
+    );
+    assert_eq!(
+        expand(&definitions, "2 * g(x^2)").unwrap(),
+        "2 * (sin(a * x^2) + x^2)"
+    );
+    // built-in functions cannot be redefined
+    let mut builtin = Definitions::default();
+    builtin.define("sin".to_owned(), Expression::parse("x").unwrap());
+    assert!(!builtin.contains("sin"));
+    assert!(matches!(
+        expand(&definitions, "f1(x, 2)"),
+        Err(PlotError::Parse { .. })
+    ));
+
+    let function =
+        Function::with_definitions("f1(2 * x)".to_owned(), "h".to_owned(), &definitions).unwrap();
+    assert_eq!(function.vars_names(), ["a"]);
+    assert_eq!(function.eval(0.25), 0.5f64.sin());
+    assert_eq!(
+        function.derivative(1).unwrap().to_string(),
+        "2 * a * cos(2 * a * x)"
+    );
+}
+
+#[test]
+fn cycles_are_reported() {
+    let definitions = definitions(&[
+        ("f1", "f2(x) + 1"),
+        ("f2", "x * f1(x)"),
+        ("f3", "f3(x - 1)"),
+    ]);
+    let cycle = |names: &[&str]| {
+        Err(PlotError::CyclicDefinition(
+            names.iter().map(|name| name.to_string()).collect(),
+        ))
+    };
+    assert_eq!(expand(&definitions, "f1(x)"), cycle(&["f1", "f2", "f1"]));
+    assert_eq!(
+        expand(&definitions, "f2(x) / 2"),
+        cycle(&["f2", "f1", "f2"])
+    );
+    assert_eq!(expand(&definitions, "f3(x)"), cycle(&["f3", "f3"]));
+    assert_eq!(
+        cycle(&["f1", "f2", "f1"]).unwrap_err().to_string(),
+        "`f1` is defined in terms of itself: f1 → f2 → f1"
+    );
+}
+
+#[test]
+fn definitions_are_recognized() {
+    assert_eq!(
+        parse_definition("f1(x) = sin(a*x)"),
+        Some(("f1".to_owned(), "sin(a*x)"))
+    );
+    assert_eq!(parse_definition("sin(x*a)"), None);
+    assert_eq!(parse_definition("f(x) == 1"), None);
+    assert_eq!(parse_definition("f(x) <= 1"), None);
+    assert_eq!(parse_definition("f(t) = t"), None);
+    assert_eq!(parse_definition("cos(x) = 1"), None);
+}
+
+#[test]
+fn elements_follow_the_functions_they_call() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("sin(a*x)".to_owned(), "f1".to_owned()).unwrap());
+    let caller =
+        Function::with_definitions("f1(x)^2".to_owned(), "g".to_owned(), &app.definitions())
+            .unwrap();
+    app.add_function(caller);
+    app.set_parameter("a", 2.0);
+    assert_eq!(app.functions()[1].eval(1.0), 2f64.sin().powi(2));
+
+    // the callers of a removed function report it
+    app.remove_function(0);
+    assert_eq!(
+        app.functions()[0].error(),
+        Some(&PlotError::UnknownFunction("f1".to_owned()))
+    );
+    app.add_function(Function::new("x + b".to_owned(), "f1".to_owned()).unwrap());
+    assert_eq!(app.functions()[0].error(), None);
+    assert_eq!(app.functions()[0].vars_names(), ["b"]);
+}
@@ -0,0 +1,243 @@
+use plotting_rs::animation::{Animation, AnimationMode};
+use plotting_rs::document::{Document, Format, CURRENT_VERSION};
+use plotting_rs::elements::{ImplicitCurve, ParametricCurve, PolarCurve, Region};
+use plotting_rs::error::PlotError;
+use plotting_rs::evaluation::Function;
+use plotting_rs::parameters::Parameter;
+use plotting_rs::plotting::PlottingApp;
+use plotting_rs::scene::Scene;
+use plotting_rs::sweep::{ParameterSweep, SweepValues};
+
+fn workspace() -> PlottingApp {
+    let mut app = PlottingApp::empty();
+    let mut f1 = Function::new("sin(a*x)".to_owned(), "f1".to_owned()).unwrap();
+    f1.style.color = Some([10, 20, 30]);
+    f1.style.width = 3.0;
+    f1.show_derivative = true;
+    f1.sweep = Some(ParameterSweep::new(
+        "a".to_owned(),
+        SweepValues::List("1, 2".to_owned()),
+    ));
+    app.add_function(f1);
+    let definitions = app.definitions();
+    let g = Function::with_definitions("f1(x) + omega".to_owned(), "g".to_owned(), &definitions);
+    app.add_function(g.unwrap());
+    let mut curve =
+        ParametricCurve::new("cos(t)".to_owned(), "sin(b*t)".to_owned(), "c".to_owned()).unwrap();
+    curve.t_range = (0.0, 2.0);
+    app.add_parametric_curve(curve);
+    app.add_polar_curve(PolarCurve::new("1 + cos(theta)".to_owned(), "r".to_owned()).unwrap());
+    app.add_implicit_curve(ImplicitCurve::new("x^2 + y^2 = 4".to_owned(), "i".to_owned()).unwrap());
+    app.add_region(Region::new("y <= x".to_owned(), "s".to_owned()).unwrap());
+    app.set_parameter_limits("a", (0.0, 5.0));
+    app.set_parameter("a", 2.5);
+    app.set_parameter_expression("omega", Some("2*pi*freq"))
+        .unwrap();
+    app.set_parameter_animation("b", Animation::new(AnimationMode::Bounce, 0.5));
+    app.set_polar_grid(true);
+    app.set_view_bounds((-1.0, 3.0), (-2.0, 2.0));
+    app
+}
+
+#[test]
+fn documents_round_trip_through_json_and_ron() {
+    let document = workspace().to_document();
+    assert_eq!(document.version, CURRENT_VERSION);
+    for format in [Format::Json, Format::Ron] {
+        let text = document.to_string(format).unwrap();
+        assert_eq!(Document::from_str(&text, format).unwrap(), document);
+    }
+}
+
+#[test]
+fn workspaces_are_restored_from_documents() {
+    let app = workspace();
+    let restored = PlottingApp::from_document(&app.to_document());
+    assert_eq!(restored.to_document(), app.to_document());
+    assert_eq!(restored.parameter("a"), Some(2.5));
+    assert_eq!(restored.parameter_limits("a"), Some((0.0, 5.0)));
+    assert_eq!(restored.parameter("omega"), app.parameter("omega"));
+    // calls between functions are resolved
+    assert_eq!(
+        restored.functions()[1].eval(1.0),
+        app.functions()[1].eval(1.0)
+    );
+    assert_eq!(restored.xlim(), (-1.0, 3.0));
+    assert!(restored.polar_grid());
+    // animations are restored stopped
+    let b = restored.parameter_registry().get("b").unwrap();
+    assert_eq!(b.animation.mode, AnimationMode::Bounce);
+    assert!(!b.animation.playing);
+}
+
+#[test]
+fn invalid_expressions_are_kept_with_their_error() {
+    let mut document = workspace().to_document();
+    document.functions[0].expression = "sin(a*".to_owned();
+    let app = PlottingApp::from_document(&document);
+    assert_eq!(app.functions()[0].expression, "sin(a*");
+    assert!(app.functions()[0].error().is_some());
+    assert_eq!(app.to_document(), document);
+    // nothing is drawn for it, not even a placeholder
+    assert!(app.functions()[0].eval(1.0).is_nan());
+    let scene = Scene::new(&app);
+    assert!(!scene.legend().iter().any(|(name, _)| *name == "f1"));
+    assert!(!scene.items.is_empty());
+
+    let text = r#"{ "regions": [{ "name": "r", "expression": "y <", "resolution": 10 }] }"#;
+    let app = PlottingApp::from_document(&Document::from_str(text, Format::Json).unwrap());
+    assert!(app.regions()[0].inequality().is_none());
+    assert!(!app.regions()[0].contains(0.0, -1.0));
+    assert!(Scene::new(&app).items.is_empty());
+    assert_eq!(app.first_error().map(|(name, _)| name), Some("r"));
+}
+
+#[test]
+fn older_documents_are_migrated() {
+    // written by hand, without a version and most fields
+    let text = r#"{
+        "functions": [{ "name": "f1", "expression": "k*x" }],
+        "parameters": [{ "name": "k", "value": 3 }],
+        "view": { "xlim": [0, 1], "ylim": [0, 2] }
+    }"#;
+    let document = Document::from_str(text, Format::Json).unwrap();
+    assert_eq!(document.version, CURRENT_VERSION);
+    let app = PlottingApp::from_document(&document);
+    assert_eq!(app.functions()[0].eval(2.0), 6.0);
+    assert_eq!(app.parameter_limits("k"), Some((-10.0, 10.0)));
+    assert_eq!(app.ylim(), (0.0, 2.0));
+
+    let newer = format!(r#"{{ "version": {} }}"#, CURRENT_VERSION + 1);
+    assert!(matches!(
+        Document::from_str(&newer, Format::Json),
+        Err(PlotError::Document(_))
+    ));
+    assert!(matches!(
+        Document::from_str("{ functions: ", Format::Ron),
+        Err(PlotError::Document(_))
+    ));
+}
+
+#[test]
+fn workspaces_are_saved_to_files() {
+    let app = workspace();
+    let directory = std::env::temp_dir();
+    for file_name in ["plotting-rs-workspace.json", "plotting-rs-workspace.ron"] {
+        let path = directory.join(file_name);
+        app.save_file(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        // the extension picks the format
+        assert_eq!(text.starts_with('{'), file_name.ends_with(".json"));
+        let opened = PlottingApp::open_file(&path).unwrap();
+        assert_eq!(opened.to_document(), app.to_document());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn documents_round_trip_through_url_fragments() {
+    let document = workspace().to_document();
+    let fragment = document.to_fragment().unwrap();
+    assert!(fragment.starts_with("w="));
+    assert!(fragment[2..]
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    // compressed, it is shorter than the JSON itself
+    assert!(fragment.len() < serde_json::to_string(&document).unwrap().len());
+    assert_eq!(Document::from_fragment(&fragment).unwrap(), document);
+    // as read from the address of a page
+    assert_eq!(
+        Document::from_fragment(&format!("#{}", fragment)).unwrap(),
+        document
+    );
+}
+
+#[test]
+fn damaged_fragments_are_reported() {
+    let fragment = workspace().to_document().to_fragment().unwrap();
+    for damaged in ["", "#", "#other", "w=***", &fragment[..fragment.len() / 2]] {
+        assert!(matches!(
+            Document::from_fragment(damaged),
+            Err(PlotError::Document(_))
+        ));
+    }
+}
+
+#[test]
+fn out_of_range_values_are_clamped_on_load() {
+    let mut document = workspace().to_document();
+    document.functions[0].style.width = 1e9;
+    document.functions[0].sweep = Some(ParameterSweep::new(
+        "a".to_owned(),
+        SweepValues::Range {
+            from: 0.0,
+            to: 1.0,
+            steps: 1_000_000_000,
+        },
+    ));
+    document.parametric_curves[0].samples = 1_000_000_000;
+    document.polar_curves[0].samples = 0;
+    document.implicit_curves[0].resolution = 1_000_000_000;
+    document.regions[0].resolution = 1;
+    document.parameters[0].range = (5.0, -5.0);
+    document.view.xlim = (5.0, -5.0);
+    let fragment = document.to_fragment().unwrap();
+    let app = PlottingApp::from_document(&Document::from_fragment(&fragment).unwrap());
+    let f1 = &app.functions()[0];
+    assert_eq!(f1.style.width, 8.0);
+    assert!(matches!(
+        f1.sweep.as_ref().unwrap().values,
+        SweepValues::Range { steps: 100, .. }
+    ));
+    assert_eq!(app.parametric_curves()[0].samples, 100_000);
+    assert_eq!(app.polar_curves()[0].samples, 2);
+    assert_eq!(app.implicit_curves()[0].resolution, 1000);
+    assert_eq!(app.regions()[0].resolution, 10);
+    assert_eq!(app.parameter_limits("a"), Some((-5.0, 5.0)));
+    // a reversed view is left for the default one
+    assert_eq!(app.xlim(), (-10.0, 10.0));
+
+    // values that are not finite can come from a file, not from JSON
+    document.functions[0].sweep = Some(ParameterSweep::new(
+        "a".to_owned(),
+        SweepValues::Range {
+            from: f64::NEG_INFINITY,
+            to: 1.0,
+            steps: 3,
+        },
+    ));
+    document.parametric_curves[0].t_range = (0.0, f64::NAN);
+    document.parameters[0].range = (f64::INFINITY, 0.0);
+    document.parameters[0].step = f64::NAN;
+    let text = document.to_string(Format::Ron).unwrap();
+    let app = PlottingApp::from_document(&Document::from_str(&text, Format::Ron).unwrap());
+    assert_eq!(
+        app.functions()[0].sweep.as_ref().unwrap().values,
+        SweepValues::Range {
+            from: -10.0,
+            to: 10.0,
+            steps: 3
+        }
+    );
+    assert_eq!(
+        app.parametric_curves()[0].t_range,
+        ParametricCurve::DEFAULT_T_RANGE
+    );
+    let a = app.parameter_registry().get("a").unwrap();
+    assert_eq!(a.range, Parameter::DEFAULT_RANGE);
+    assert_eq!(a.step, Parameter::DEFAULT_STEP);
+}
+
+#[test]
+fn undefined_values_are_kept() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("k*x".to_owned(), "f1".to_owned()).unwrap());
+    app.set_parameter_expression("k", Some("sqrt(-1)")).unwrap();
+    let document = app.to_document();
+    let fragment = document.to_fragment().unwrap();
+    let value = Document::from_fragment(&fragment).unwrap().parameters[0].value;
+    assert!(value.is_nan());
+    let text = document.to_string(Format::Json).unwrap();
+    let value = Document::from_str(&text, Format::Json).unwrap().parameters[0].value;
+    assert!(value.is_nan());
+}
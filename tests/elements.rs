@@ -0,0 +1,201 @@
+use std::f64::consts::PI;
+
+use plotting_rs::elements::{
+    parse_equation, parse_inequality, ImplicitCurve, Inequality, PolarCurve, PolarGrid, Region,
+};
+use plotting_rs::error::PlotError;
+use plotting_rs::sampling::{contour, fill};
+
+#[test]
+fn polar_curve_accepts_both_spellings_of_theta() {
+    for expression in ["a * cos(3*θ)", "a * cos(3*theta)"] {
+        let mut curve = PolarCurve::new(expression.to_owned(), "r".to_owned()).unwrap();
+        assert_eq!(curve.vars_names(), ["a"]);
+        curve.assign_value_to_var("a".to_owned(), 2.0).unwrap();
+        assert!((curve.eval(PI / 3.0) + 2.0).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn polar_curve_is_drawn_in_cartesian_coordinates() {
+    let mut curve = PolarCurve::new("2".to_owned(), "circle".to_owned()).unwrap();
+    curve.theta_range = (0.0, PI);
+    curve.samples = 3;
+    let segments = curve.sample();
+    assert_eq!(segments.len(), 1);
+    let expected = [[2.0, 0.0], [0.0, 2.0], [-2.0, 0.0]];
+    for (point, expected) in segments[0].iter().zip(expected) {
+        assert!((point[0] - expected[0]).abs() < 1e-12 && (point[1] - expected[1]).abs() < 1e-12);
+    }
+}
+
+#[test]
+fn polar_grid_covers_the_view_with_round_radii() {
+    let grid = PolarGrid::for_view((-3.0, 4.0), (-1.0, 4.0), 5);
+    // the farthest corner is (4, 4)
+    assert!((grid.max_radius - 32f64.sqrt()).abs() < 1e-12);
+    assert_eq!(grid.radii, [2.0, 4.0, 6.0]);
+    assert_eq!(grid.angles.len(), 12);
+
+    let grid = PolarGrid::for_view((-0.3, 0.3), (-0.4, 0.4), 5);
+    assert_eq!(grid.radii.len(), 5);
+    assert!((grid.radii[0] - 0.1).abs() < 1e-12 && (grid.radii[4] - 0.5).abs() < 1e-12);
+}
+
+#[test]
+fn contour_of_a_circle_is_a_single_closed_line() {
+    let segments = contour(
+        |x, y| x * x + y * y - 4.0,
+        (-3.0, 3.0),
+        (-3.0, 3.0),
+        (50, 50),
+    );
+    assert_eq!(segments.len(), 1);
+    let circle = &segments[0];
+    assert_eq!(circle.first(), circle.last());
+    for [x, y] in circle {
+        assert!(
+            (x.hypot(*y) - 2.0).abs() < 0.01,
+            "({}, {}) is off the circle",
+            x,
+            y
+        );
+    }
+}
+
+#[test]
+fn contour_skips_sign_changes_through_poles() {
+    // `1/x - y` changes sign across x = 0 without being 0 there
+    let segments = contour(|x, y| 1.0 / x - y, (-2.05, 2.0), (-2.0, 2.0), (40, 40));
+    assert_eq!(segments.len(), 2);
+    for [x, y] in segments.iter().flatten() {
+        assert!(
+            x.abs() > 0.4 && (x * y - 1.0).abs() < 0.05,
+            "({}, {})",
+            x,
+            y
+        );
+    }
+}
+
+#[test]
+fn implicit_curve_follows_its_parameters() {
+    let mut curve = ImplicitCurve::new("x^2 + y^2 = r^2".to_owned(), "c".to_owned()).unwrap();
+    assert_eq!(curve.vars_names(), ["r"]);
+    curve.assign_value_to_var("r".to_owned(), 3.0).unwrap();
+    assert_eq!(curve.eval(3.0, 0.0), 0.0);
+    assert_eq!(curve.eval(1.0, 2.0), -4.0);
+    curve.resolution = 60;
+    let segments = curve.sample((-4.0, 4.0), (-4.0, 4.0));
+    assert_eq!(segments.len(), 1);
+    assert!(segments[0]
+        .iter()
+        .all(|[x, y]| (x.hypot(*y) - 3.0).abs() < 0.02));
+    // the grid covers the view, so a zoomed in view gives a finer curve
+    let zoomed = curve.sample((2.5, 3.5), (-0.5, 0.5));
+    assert!(zoomed[0]
+        .iter()
+        .all(|[x, y]| (x.hypot(*y) - 3.0).abs() < 1e-4));
+}
+
+#[test]
+fn equations_report_errors_at_their_position() {
+    assert_eq!(
+        parse_equation("x*y").unwrap(),
+        parse_equation("x*y = 0").unwrap().simplify()
+    );
+    let position = |text: &str| match parse_equation(text) {
+        Err(PlotError::Parse { position, .. }) => position,
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(position("x^2 = y +"), Some(9));
+    assert_eq!(position("x = y = 1"), Some(6));
+}
+
+fn area(polygons: &[Vec<[f64; 2]>]) -> f64 {
+    let shoelace = |polygon: &Vec<[f64; 2]>| {
+        let edges = polygon.iter().zip(polygon.iter().cycle().skip(1));
+        edges.map(|(a, b)| a[0] * b[1] - b[0] * a[1]).sum::<f64>() / 2.0
+    };
+    polygons.iter().map(shoelace).sum()
+}
+
+#[test]
+fn fill_covers_the_region_with_counterclockwise_polygons() {
+    let disc = fill(
+        |x, y| x * x + y * y - 4.0,
+        (-3.0, 3.0),
+        (-3.0, 3.0),
+        (100, 100),
+    );
+    assert!(
+        (area(&disc) - 4.0 * PI).abs() < 0.01,
+        "area {}",
+        area(&disc)
+    );
+    assert!(disc.iter().all(|polygon| area(std::slice::from_ref(polygon)) > 0.0));
+
+    // a saddle cell, inside at two opposite corners only
+    let saddle = fill(|x, y| 0.1 - x * y, (-1.0, 1.0), (-1.0, 1.0), (1, 1));
+    assert_eq!(saddle.len(), 2);
+    // the boundary crosses the edges 0.9 away from the inside corners
+    assert!(
+        (area(&saddle) - 0.81).abs() < 1e-9,
+        "area {}",
+        area(&saddle)
+    );
+}
+
+#[test]
+fn inequalities_are_parsed_with_their_strictness() {
+    let (difference, inequality) = parse_inequality("y > x^2 - a").unwrap();
+    assert_eq!(inequality, Inequality::Greater);
+    assert!(inequality.is_strict());
+    assert_eq!(difference.simplify().to_string(), "-x^2 + a + y");
+    assert_eq!(
+        parse_inequality("x^2 + y^2 <= 4").unwrap().1,
+        Inequality::LessOrEqual
+    );
+    assert!(!Inequality::GreaterOrEqual.is_strict());
+    let position = |text: &str| match parse_inequality(text) {
+        Err(PlotError::Parse { position, .. }) => position,
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(position("0 < x < 1"), Some(6));
+    assert_eq!(position("y >= x +"), Some(8));
+}
+
+#[test]
+fn region_follows_its_parameters() {
+    let mut region = Region::new("y > x^2 - a".to_owned(), "R".to_owned()).unwrap();
+    assert_eq!(region.vars_names(), ["a"]);
+    assert!(region.contains(0.0, -0.5) && !region.contains(0.0, -1.5));
+    region.assign_value_to_var("a".to_owned(), 0.0).unwrap();
+    assert!(!region.contains(0.0, -0.5));
+    region.assign_value_to_var("a".to_owned(), 1.0).unwrap();
+    // the boundary is excluded from a strict inequality only
+    assert!(!region.contains(0.0, -1.0));
+    let closed = Region::new("y >= x^2 - 1".to_owned(), "R".to_owned()).unwrap();
+    assert!(closed.contains(0.0, -1.0));
+
+    let polygons = region.sample_fill((-1.0, 1.0), (-1.0, 0.0));
+    // the area above y = x^2 - 1 and below 0
+    assert!(
+        (area(&polygons) - 4.0 / 3.0).abs() < 0.01,
+        "area {}",
+        area(&polygons)
+    );
+    assert!(!region.sample_boundary((-2.0, 2.0), (-2.0, 2.0)).is_empty());
+}
+
+#[test]
+fn conditions_are_not_taken_for_inequalities() {
+    use plotting_rs::elements::is_inequality;
+    assert!(is_inequality("y < x") && is_inequality("x^2 + y^2 >= 1"));
+    assert!(!is_inequality("{x < 0: -x, x}"));
+    assert!(!is_inequality("if(x > 0, 1, 0)"));
+    assert!(!is_inequality("x == y"));
+    // `<=` is not the `=` of an equation
+    let parsed = parse_equation("y = {x <= 0: 0, x}").unwrap();
+    assert_eq!(parsed.to_string(), "y - {x <= 0: 0, x}");
+}
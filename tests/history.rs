@@ -0,0 +1,248 @@
+use eframe::egui;
+use plotting_rs::animation::Animation;
+use plotting_rs::document::{PolarCurveEntry, View};
+use plotting_rs::elements::{ParametricCurve, Region};
+use plotting_rs::evaluation::Function;
+use plotting_rs::history::{Edit, ElementEntry, HISTORY_LIMIT};
+use plotting_rs::plotting::PlottingApp;
+
+fn expressions(app: &PlottingApp) -> Vec<&str> {
+    app.functions()
+        .iter()
+        .map(|function| function.expression.as_str())
+        .collect()
+}
+
+fn demo() -> PlottingApp {
+    let mut app = PlottingApp::default();
+    // the first checkpoint only remembers the workspace
+    app.checkpoint();
+    app
+}
+
+#[test]
+fn removed_functions_come_back() {
+    let mut app = demo();
+    app.remove_function(0);
+    app.checkpoint();
+    assert_eq!(expressions(&app), ["cos(x*a)/b"]);
+    assert!(app.undo());
+    assert_eq!(expressions(&app), ["sin(x*a)*b", "cos(x*a)/b"]);
+    assert!(!app.can_undo());
+    assert!(app.redo());
+    assert_eq!(expressions(&app), ["cos(x*a)/b"]);
+    assert!(!app.redo());
+}
+
+#[test]
+fn slider_drags_are_one_step() {
+    let mut app = demo();
+    for value in [1.1, 1.2, 1.5, 2.0] {
+        app.set_parameter("a", value);
+        app.checkpoint();
+    }
+    app.set_parameter_limits("a", (0.0, 3.0));
+    app.checkpoint();
+    app.set_view_bounds((0.0, 1.0), (0.0, 1.0));
+    app.checkpoint();
+
+    assert!(app.undo());
+    assert_eq!(app.xlim(), (-10.0, 10.0));
+    assert!(app.undo());
+    assert_eq!(app.parameter_limits("a"), Some((-10.0, 10.0)));
+    assert_eq!(app.parameter("a"), Some(2.0));
+    assert!(app.undo());
+    assert_eq!(app.parameter("a"), Some(1.0));
+    assert_eq!(app.functions()[0].eval(1.0), 1.0_f64.sin());
+    assert!(!app.undo());
+}
+
+#[test]
+fn separate_drags_are_separate_steps() {
+    let mut app = demo();
+    for value in [1.1, 1.5] {
+        app.set_parameter("a", value);
+        app.checkpoint();
+    }
+    // the pointer is released
+    app.end_step();
+    for value in [1.8, 2.5] {
+        app.set_parameter("a", value);
+        app.checkpoint();
+    }
+    assert!(app.undo());
+    assert_eq!(app.parameter("a"), Some(1.5));
+    assert!(app.undo());
+    assert_eq!(app.parameter("a"), Some(1.0));
+    assert!(!app.undo());
+}
+
+#[test]
+fn undoing_keeps_the_state_of_the_interface() {
+    let mut app = PlottingApp::default();
+    let mut animation = Animation::default();
+    animation.play();
+    app.set_parameter_animation("b", animation);
+    app.set_animations_paused(true);
+    app.checkpoint();
+    app.set_parameter("a", 2.0);
+    app.checkpoint();
+    assert!(app.undo());
+    assert_eq!(app.parameter("a"), Some(1.0));
+    assert!(app.animations_paused());
+    assert!(app.parameter_registry().get("b").unwrap().animation.playing);
+}
+
+#[test]
+fn new_edits_forget_the_undone_ones() {
+    let mut app = demo();
+    app.set_parameter("a", 2.0);
+    app.checkpoint();
+    app.undo();
+    assert!(app.can_redo());
+    // an edit after undoing is a step of its own
+    app.set_parameter("a", 3.0);
+    app.checkpoint();
+    assert!(!app.can_redo());
+    app.undo();
+    assert_eq!(app.parameter("a"), Some(1.0));
+}
+
+#[test]
+fn history_is_bounded() {
+    let mut app = demo();
+    for i in 0..HISTORY_LIMIT + 20 {
+        // alternating parameters are not merged
+        let name = if i % 2 == 0 { "a" } else { "b" };
+        app.set_parameter(name, i as f64 / 100.0);
+        app.checkpoint();
+    }
+    let mut steps = 0;
+    while app.undo() {
+        steps += 1;
+    }
+    assert_eq!(steps, HISTORY_LIMIT);
+}
+
+#[test]
+fn edits_are_found_from_the_documents() {
+    let mut app = demo();
+    let before = app.to_document();
+    let mut after = before.clone();
+    after.functions[1].expression = "cos(x)".to_owned();
+    assert_eq!(
+        Edit::between(&before, &after),
+        Edit::EditExpression {
+            index: 1,
+            old: "cos(x*a)/b".to_owned(),
+            new: "cos(x)".to_owned()
+        }
+    );
+    let mut after = before.clone();
+    after.functions[0].name = "g".to_owned();
+    assert!(matches!(
+        Edit::between(&before, &after),
+        Edit::RenameFunction { index: 0, .. }
+    ));
+    let mut after = before.clone();
+    after.view = View {
+        xlim: (0.0, 1.0),
+        ylim: (0.0, 1.0),
+    };
+    assert!(matches!(
+        Edit::between(&before, &after),
+        Edit::SetView { .. }
+    ));
+
+    app.add_function(Function::new("x".to_owned(), "line".to_owned()).unwrap());
+    let after = app.to_document();
+    let edit = Edit::between(&before, &after);
+    assert!(matches!(edit, Edit::AddFunction { index: 2, .. }));
+    let mut document = after.clone();
+    edit.revert(&mut document);
+    assert_eq!(document, before);
+
+    // several changes at once
+    let mut after = before.clone();
+    after.functions.clear();
+    after.parameters.clear();
+    assert!(matches!(
+        Edit::between(&before, &after),
+        Edit::Replace { .. }
+    ));
+}
+
+#[test]
+fn edits_of_other_elements_are_found_from_the_documents() {
+    let mut app = demo();
+    app.add_parametric_curve(
+        ParametricCurve::new("cos(t)".to_owned(), "sin(t)".to_owned(), "c".to_owned()).unwrap(),
+    );
+    app.add_region(Region::new("y < x".to_owned(), "r".to_owned()).unwrap());
+    let before = app.to_document();
+
+    let mut after = before.clone();
+    after.parametric_curves[0].y_expression = "sin(2*t)".to_owned();
+    let edit = Edit::between(&before, &after);
+    assert!(matches!(edit, Edit::EditElement { index: 0, .. }));
+    let mut document = after.clone();
+    edit.revert(&mut document);
+    assert_eq!(document, before);
+
+    let mut after = before.clone();
+    after.regions[0].resolution = 20;
+    assert!(matches!(
+        Edit::between(&before, &after),
+        Edit::EditElement {
+            new: ElementEntry::Region(_),
+            ..
+        }
+    ));
+    let mut after = before.clone();
+    after.polar_curves.push(PolarCurveEntry {
+        name: "p".to_owned(),
+        expression: "1".to_owned(),
+        theta_range: (0.0, 1.0),
+        samples: 10,
+    });
+    assert!(matches!(
+        Edit::between(&before, &after),
+        Edit::AddElement { index: 0, .. }
+    ));
+    let mut after = before.clone();
+    after.functions[0].style.width = 4.0;
+    assert!(matches!(
+        Edit::between(&before, &after),
+        Edit::EditFunction { index: 0, .. }
+    ));
+    let mut after = before.clone();
+    after.parameters[0].animation = Animation::default();
+    after.parameters[0].animation.speed = 3.0;
+    assert!(matches!(
+        Edit::between(&before, &after),
+        Edit::EditParameter { index: 0, .. }
+    ));
+    let mut after = before.clone();
+    after.settings.polar_grid = true;
+    assert!(matches!(
+        Edit::between(&before, &after),
+        Edit::SetSettings { .. }
+    ));
+}
+
+#[test]
+fn frames_without_input_record_nothing() {
+    let mut app = demo();
+    let ctx = egui::Context::default();
+    app.set_parameter("a", 2.0);
+    let _ = ctx.run(egui::RawInput::default(), |ctx| app.show(ctx));
+    assert!(!app.can_undo());
+    // the next frame with input records the edits made since the last one
+    let input = egui::RawInput {
+        events: vec![egui::Event::PointerGone],
+        ..Default::default()
+    };
+    let _ = ctx.run(input, |ctx| app.show(ctx));
+    assert!(app.undo());
+    assert_eq!(app.parameter("a"), Some(1.0));
+}
@@ -0,0 +1,252 @@
+use std::f64::consts::PI;
+
+use plotting_rs::elements::{ImplicitCurve, ParametricCurve, PolarCurve, Region};
+use plotting_rs::error::PlotError;
+use plotting_rs::evaluation::{Function, DEFAULT_VALUE_PARAMETER};
+use plotting_rs::parameters::{DerivedParameter, Parameter, ParameterRegistry};
+use plotting_rs::parser::rename_variable;
+use plotting_rs::plotting::PlottingApp;
+
+fn registry(parameters: &[(&str, f64, Option<&str>)]) -> ParameterRegistry {
+    let mut registry = ParameterRegistry::new();
+    for (name, value, expression) in parameters {
+        let mut parameter = Parameter::new(*value);
+        parameter.derived =
+            expression.map(|expression| DerivedParameter::new(expression.to_owned()).unwrap());
+        registry.insert(name.to_string(), parameter);
+    }
+    registry
+}
+
+#[test]
+fn derived_parameters_are_computed_in_dependency_order() {
+    let mut parameters = registry(&[
+        ("k", 0.0, Some("omega^2 + b")),
+        ("omega", 0.0, Some("2*pi*freq")),
+        ("freq", 0.5, None),
+        ("b", 3.0, None),
+    ]);
+    parameters.update_derived();
+    assert_eq!(parameters.value("omega"), Some(PI));
+    assert_eq!(parameters.value("k"), Some(PI * PI + 3.0));
+    assert!(parameters
+        .iter()
+        .filter_map(|(_, parameter)| parameter.derived.as_ref())
+        .all(|derived| derived.error().is_none()));
+}
+
+#[test]
+fn cycles_and_missing_inputs_are_reported() {
+    let mut parameters = registry(&[
+        ("a", 1.0, Some("b + 1")),
+        ("b", 2.0, Some("2 * a")),
+        ("c", 3.0, Some("a + c")),
+        ("d", 4.0, Some("g")),
+    ]);
+    parameters.update_derived();
+    let error = |name: &str| {
+        let derived = parameters.get(name).unwrap().derived.as_ref();
+        derived.unwrap().error().cloned()
+    };
+    let cycle = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+    assert_eq!(
+        error("a"),
+        Some(PlotError::CyclicDefinition(cycle(&["a", "b", "a"])))
+    );
+    assert_eq!(
+        error("b"),
+        Some(PlotError::CyclicDefinition(cycle(&["a", "b", "a"])))
+    );
+    assert_eq!(
+        error("c"),
+        Some(PlotError::CyclicDefinition(cycle(&["c", "c"])))
+    );
+    assert_eq!(error("d"), Some(PlotError::UnboundVariable("g".to_owned())));
+    // the values that cannot be computed are kept
+    let values: Vec<f64> = parameters.iter().map(|(_, p)| p.value).collect();
+    assert_eq!(values, [1.0, 2.0, 3.0, 4.0]);
+}
+
+#[test]
+fn registry_keeps_names_unique_and_in_order() {
+    let mut parameters = ParameterRegistry::new();
+    assert!(parameters
+        .insert("b".to_owned(), Parameter::new(2.0))
+        .is_none());
+    parameters.add_missing(["a", "b", "c"]);
+    assert_eq!(parameters.names().collect::<Vec<_>>(), ["b", "a", "c"]);
+    assert_eq!(parameters.value("b"), Some(2.0));
+    assert_eq!(parameters.value("a"), Some(DEFAULT_VALUE_PARAMETER));
+    // replacing returns the previous parameter
+    let previous = parameters.insert("a".to_owned(), Parameter::new(5.0));
+    assert_eq!(previous.map(|p| p.value), Some(DEFAULT_VALUE_PARAMETER));
+    assert_eq!(parameters.len(), 3);
+
+    assert_eq!(parameters.remove("b").map(|p| p.value), Some(2.0));
+    assert!(parameters.remove("b").is_none());
+    assert_eq!(parameters.names().collect::<Vec<_>>(), ["a", "c"]);
+}
+
+#[test]
+fn values_stay_within_the_range() {
+    let mut parameters = registry(&[("a", 1.0, None), ("d", 0.0, Some("2 * a"))]);
+    assert!(parameters.set_value("a", 50.0));
+    assert_eq!(parameters.value("a"), Some(Parameter::DEFAULT_RANGE.1));
+    assert!(parameters.set_range("a", (-1.0, 2.0)));
+    assert_eq!(parameters.value("a"), Some(2.0));
+    // reversed limits are refused
+    assert!(!parameters.set_range("a", (3.0, 1.0)));
+    assert_eq!(parameters.get("a").unwrap().range, (-1.0, 2.0));
+    // derived and missing parameters cannot be set
+    assert!(!parameters.set_value("d", 1.0));
+    assert!(!parameters.set_value("z", 1.0));
+    assert!(!parameters.set_range("z", (0.0, 1.0)));
+    // and the bounds and values must be numbers
+    assert!(!parameters.set_range("a", (f64::NAN, 1.0)));
+    assert!(!parameters.set_range("a", (0.0, f64::INFINITY)));
+    assert!(!parameters.set_value("a", f64::NAN));
+    assert_eq!(parameters.value("a"), Some(2.0));
+}
+
+#[test]
+fn reversed_ranges_are_put_back_in_order_on_load() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("a*x".to_owned(), "f1".to_owned()).unwrap());
+    let mut document = app.to_document();
+    document.parameters[0].range = (5.0, -5.0);
+    document.parameters[0].value = 8.0;
+    let mut app = PlottingApp::from_document(&document);
+    assert_eq!(app.parameter_limits("a"), Some((-5.0, 5.0)));
+    assert_eq!(app.parameter_registry().value("a"), Some(5.0));
+    app.set_parameter("a", 2.0);
+    assert_eq!(app.parameter_registry().value("a"), Some(2.0));
+
+    document.parameters[0].range = (f64::NAN, 1.0);
+    let app = PlottingApp::from_document(&document);
+    assert_eq!(app.parameter_limits("a"), Some(Parameter::DEFAULT_RANGE));
+}
+
+#[test]
+fn renaming_follows_in_derived_parameters() {
+    let mut parameters = registry(&[
+        ("freq", 0.5, None),
+        ("omega", 0.0, Some("2*pi*freq")),
+        ("b", 1.0, None),
+    ]);
+    parameters.rename("freq", "f").unwrap();
+    assert_eq!(parameters.names().collect::<Vec<_>>(), ["f", "omega", "b"]);
+    let omega = parameters.get("omega").unwrap();
+    assert_eq!(omega.derived.as_ref().unwrap().expression, "2*pi*f");
+    assert_eq!(omega.value, PI);
+
+    assert_eq!(
+        parameters.rename("f", "b"),
+        Err(PlotError::DuplicateName("b".to_owned()))
+    );
+    assert_eq!(
+        parameters.rename("z", "y"),
+        Err(PlotError::UnboundVariable("z".to_owned()))
+    );
+    assert!(matches!(
+        parameters.rename("f", "2 f"),
+        Err(PlotError::Parse { .. })
+    ));
+    assert_eq!(parameters.names().collect::<Vec<_>>(), ["f", "omega", "b"]);
+}
+
+#[test]
+fn variables_are_renamed_token_wise() {
+    assert_eq!(rename_variable("a*x + ab - a", "a", "k"), "k*x + ab - k");
+    // calls and other identifiers are kept
+    assert_eq!(
+        rename_variable("sin(x) + s*sin(s)", "sin", "k"),
+        "sin(x) + s*sin(s)"
+    );
+    assert_eq!(rename_variable("s*sin(s)", "s", "t"), "t*sin(t)");
+    // unreadable text is left unchanged
+    assert_eq!(rename_variable("a $ b", "a", "k"), "a $ b");
+}
+
+#[test]
+fn app_recomputes_derived_parameters_when_inputs_change() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("sin(omega*x)".to_owned(), "f1".to_owned()).unwrap());
+    app.set_parameter_expression("omega", Some("2*pi*freq"))
+        .unwrap();
+    // the input is created with the default value
+    assert_eq!(app.parameter("freq"), Some(1.0));
+    assert_eq!(app.parameter("omega"), Some(2.0 * PI));
+    app.set_parameter("freq", 0.25);
+    assert_eq!(app.parameter("omega"), Some(PI / 2.0));
+    assert_eq!(app.functions()[0].eval(1.0), (PI / 2.0).sin());
+    // a derived parameter cannot be set directly
+    app.set_parameter("omega", 5.0);
+    assert_eq!(app.parameter("omega"), Some(PI / 2.0));
+    assert!(app.derived_parameter("omega").is_some());
+
+    assert!(app.set_parameter_expression("k", Some("2 *")).is_err());
+    app.set_parameter_expression("omega", None).unwrap();
+    app.set_parameter("omega", 5.0);
+    assert_eq!(app.parameter("omega"), Some(5.0));
+}
+
+#[test]
+fn app_keeps_elements_and_parameters_consistent() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("a*x + b".to_owned(), "f1".to_owned()).unwrap());
+    app.add_function(Function::new("a*x^2".to_owned(), "f2".to_owned()).unwrap());
+    app.set_parameter("a", 2.0);
+    app.set_parameter_expression("c", Some("a + 1")).unwrap();
+
+    app.rename_parameter("a", "k").unwrap();
+    assert_eq!(app.functions()[0].expression, "k*x + b");
+    assert_eq!(app.functions()[1].expression, "k*x^2");
+    assert_eq!(app.parameter("k"), Some(2.0));
+    assert_eq!(app.parameter("a"), None);
+    assert_eq!(app.derived_parameter("c").unwrap().expression, "k + 1");
+    assert_eq!(app.functions()[1].eval(3.0), 18.0);
+    assert_eq!(
+        app.rename_parameter("k", "b"),
+        Err(PlotError::DuplicateName("b".to_owned()))
+    );
+
+    // a parameter still used comes back, an unused one goes away
+    app.set_parameter("k", 4.0);
+    assert!(app.remove_parameter("k").is_some());
+    assert_eq!(app.parameter("k"), Some(DEFAULT_VALUE_PARAMETER));
+    app.set_parameter("unused", 3.0);
+    app.remove_parameter("unused");
+    assert_eq!(app.parameter("unused"), None);
+
+    // removing the last function using `b` removes it, `k` is still used
+    app.remove_function(0);
+    assert_eq!(app.parameter("b"), None);
+    assert_eq!(app.parameter("k"), Some(DEFAULT_VALUE_PARAMETER));
+    assert_eq!(app.functions()[0].eval(1.0), DEFAULT_VALUE_PARAMETER);
+}
+
+#[test]
+fn removing_any_element_removes_its_own_parameters() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("a*x".to_owned(), "f1".to_owned()).unwrap());
+    let curve = "a*cos(t)".to_owned();
+    app.add_parametric_curve(
+        ParametricCurve::new(curve, "b*sin(t)".to_owned(), "c".to_owned()).unwrap(),
+    );
+    app.add_polar_curve(PolarCurve::new("1 + r0*cos(theta)".to_owned(), "p".to_owned()).unwrap());
+    app.add_implicit_curve(ImplicitCurve::new("x^2 + y^2 = s".to_owned(), "i".to_owned()).unwrap());
+    app.add_region(Region::new("y <= x - d".to_owned(), "R".to_owned()).unwrap());
+    app.set_parameter_expression("e", Some("s + 1")).unwrap();
+
+    app.remove_parametric_curve(0);
+    assert_eq!(app.parameter("b"), None);
+    assert_eq!(app.parameter("a"), Some(DEFAULT_VALUE_PARAMETER));
+    app.remove_polar_curve(0);
+    assert_eq!(app.parameter("r0"), None);
+    app.remove_region(0);
+    assert_eq!(app.parameter("d"), None);
+    // an input of a derived parameter stays
+    app.remove_implicit_curve(0);
+    assert_eq!(app.parameter("s"), Some(DEFAULT_VALUE_PARAMETER));
+    assert!(app.derived_parameter("e").is_some());
+}
@@ -0,0 +1,206 @@
+use plotting_rs::parser::{Expression, Operator, UnaryOperator};
+
+/// Expressions accepted by `meval`, the previous parser of `Function`.
+const ACCEPTED_BY_MEVAL: &[&str] = &[
+    "x",
+    "sin(x*a)*b",
+    "cos(x*a)/b",
+    "x^2 + 3*x + 1+a",
+    "-x^2",
+    "2^3^x",
+    "2^-x",
+    "--x",
+    "+x",
+    "3*-x",
+    "x % 3",
+    "(x)",
+    "((x + 1)) * (2)",
+    "-(x - 1)^2",
+    "1.5e3 * x - 2E-3",
+    "12. + x",
+    "max(x, 1, -2) + min(x)",
+    "atan2(x, 2)",
+    "sin (x)",
+    "  x\t*\n2 ",
+    "exp(-x^2/2)/sqrt(2*pi)",
+    "abs(x)*ln(2) + e",
+    "a_1 + _b * x",
+    "x / y / z",
+    "x - y - z",
+    "sin(cos(tan(x)))",
+];
+
+fn meval_eval(expression: &str, x: f64) -> f64 {
+    let parsed: meval::Expr = expression.parse().unwrap();
+    let mut context = meval::Context::new();
+    context.var("x", x);
+    for name in ["a", "b", "y", "z", "a_1", "_b"] {
+        context.var(name, 1.25);
+    }
+    parsed.eval_with_context(context).unwrap()
+}
+
+fn eval(expression: &Expression, x: f64) -> f64 {
+    use plotting_rs::compiled::CompiledExpression;
+    let names: Vec<String> = ["a", "b", "y", "z", "a_1", "_b"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    CompiledExpression::new(expression, &names)
+        .unwrap()
+        .eval(x, &[1.25; 6])
+}
+
+#[test]
+fn round_trips_every_meval_expression() {
+    for text in ACCEPTED_BY_MEVAL {
+        let parsed = Expression::parse(text).unwrap_or_else(|e| panic!("{}: {}", text, e));
+        let printed = parsed.to_string();
+        assert_eq!(Expression::parse(&printed).unwrap(), parsed, "{} -> {}", text, printed);
+    }
+}
+
+#[test]
+fn evaluates_like_meval() {
+    for text in ACCEPTED_BY_MEVAL {
+        let parsed = Expression::parse(text).unwrap();
+        for x in [-2.5, -0.5, 0.0, 0.7, 3.0] {
+            let expected = meval_eval(text, x);
+            let actual = eval(&parsed, x);
+            assert!(
+                actual == expected || (actual.is_nan() && expected.is_nan()),
+                "{} at {}: {} != {}",
+                text,
+                x,
+                actual,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn follows_precedence_and_associativity() {
+    use Expression::*;
+    let x = || Box::new(Variable("x".to_owned()));
+    let n = |value| Box::new(Number(value));
+    assert_eq!(
+        Expression::parse("-x^2").unwrap(),
+        Unary(UnaryOperator::Minus, Box::new(Binary(Operator::Pow, x(), n(2.0))))
+    );
+    assert_eq!(
+        Expression::parse("2^3^x").unwrap(),
+        Binary(Operator::Pow, n(2.0), Box::new(Binary(Operator::Pow, n(3.0), x())))
+    );
+    assert_eq!(
+        Expression::parse("1 - 2 - x").unwrap(),
+        Binary(
+            Operator::Subtract,
+            Box::new(Binary(Operator::Subtract, n(1.0), n(2.0))),
+            x()
+        )
+    );
+    assert_eq!(
+        Expression::parse("1 + 2 * x").unwrap(),
+        Binary(
+            Operator::Add,
+            n(1.0),
+            Box::new(Binary(Operator::Multiply, n(2.0), x()))
+        )
+    );
+    assert_eq!(
+        Expression::parse("θ_1 * 2").unwrap(),
+        Binary(Operator::Multiply, Box::new(Variable("θ_1".to_owned())), n(2.0))
+    );
+    assert_eq!(
+        Expression::parse("f(x, 2)").unwrap(),
+        Call("f".to_owned(), vec![*x(), *n(2.0)])
+    );
+}
+
+#[test]
+fn reports_errors_with_spans() {
+    let span = |text: &str| Expression::parse(text).unwrap_err().span;
+    assert_eq!(span("x +"), 3..3);
+    assert_eq!(span("sin(x"), 3..4);
+    assert_eq!(span("x)*2"), 1..2);
+    assert_eq!(span("2 ** x"), 3..4);
+    assert_eq!(span("2 x"), 2..3);
+    assert_eq!(span("x # 2"), 2..3);
+    assert_eq!(span("max(x,)"), 6..7);
+    assert_eq!(span("x < ∞"), 4..7);
+    assert_eq!(span("0 < x < 1"), 6..7);
+    assert_eq!(span("{x < 0: -x"), 0..1);
+    assert_eq!(span("{x < 0, x}"), 6..7);
+}
+
+#[test]
+fn parses_conditions_and_piecewise_definitions() {
+    use Expression::*;
+    let x = || Box::new(Variable("x".to_owned()));
+    let n = |value| Box::new(Number(value));
+    // comparisons bind looser than arithmetic and tighter than `&&`
+    assert_eq!(
+        Expression::parse("x + 1 < 2 && x >= 0").unwrap(),
+        Binary(
+            Operator::And,
+            Box::new(Binary(
+                Operator::Less,
+                Box::new(Binary(Operator::Add, x(), n(1.0))),
+                n(2.0)
+            )),
+            Box::new(Binary(Operator::GreaterOrEqual, x(), n(0.0)))
+        )
+    );
+    assert_eq!(
+        Expression::parse("{x < 0: -x, x^2}").unwrap(),
+        Piecewise(
+            vec![(
+                Binary(Operator::Less, x(), n(0.0)),
+                Unary(UnaryOperator::Minus, x())
+            )],
+            Some(Box::new(Binary(Operator::Pow, x(), n(2.0))))
+        )
+    );
+    for text in [
+        "{x < 0: -x, x >= 0: x^2}",
+        "if(x > a, sin(x), 0)",
+        "!(x == 1) || x != 2 && x <= 3",
+        "2 * (x > 0) - {x < -1: 1, 0}",
+    ] {
+        let parsed = Expression::parse(text).unwrap();
+        assert_eq!(parsed.to_string(), text);
+    }
+}
+
+#[test]
+fn prints_with_parentheses_only_where_needed() {
+    use Expression::*;
+    let x = || Box::new(Variable("x".to_owned()));
+    let sum = Box::new(Binary(Operator::Add, x(), Box::new(Number(1.0))));
+    assert_eq!(
+        Binary(Operator::Multiply, sum.clone(), x()).to_string(),
+        "(x + 1) * x"
+    );
+    assert_eq!(Binary(Operator::Add, sum.clone(), x()).to_string(), "x + 1 + x");
+    assert_eq!(Binary(Operator::Subtract, x(), sum.clone()).to_string(), "x - (x + 1)");
+    assert_eq!(
+        Binary(Operator::Pow, Box::new(Unary(UnaryOperator::Minus, x())), x()).to_string(),
+        "(-x)^x"
+    );
+}
+
+#[test]
+fn evaluates_conditions_lazily() {
+    let at = |text: &str, x: f64| eval(&Expression::parse(text).unwrap(), x);
+    let piecewise = "{x < 0: -x, x >= 0: x^2}";
+    assert_eq!([at(piecewise, -2.0), at(piecewise, 3.0)], [2.0, 9.0]);
+    assert_eq!(at("if(x > a, 1, 2)", 1.0), 2.0);
+    assert_eq!(at("(x > 0) + (x <= 0) * 2 + (x == 1) * 4", 1.0), 5.0);
+    assert_eq!(at("!(x > 0) || x == 3 && x != 3", 1.0), 0.0);
+    // no branch holds, or the condition is undefined
+    assert!(at("{x < 0: 1}", 1.0).is_nan());
+    assert!(at("if(sqrt(x) > 0, 1, 2)", -1.0).is_nan());
+    // the branch not taken is not evaluated, even where it is undefined
+    assert_eq!(at("{x > 0: sqrt(x), x < 0: ln(-x), 0}", -1.0), 0.0);
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use plotting_rs::evaluation::Function;
+use plotting_rs::plotting::PlottingApp;
+
+/// Storage kept in memory, like the one of eframe between two sessions.
+#[derive(Default)]
+struct MemoryStorage(HashMap<String, String>);
+
+impl eframe::Storage for MemoryStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+    fn set_string(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_owned(), value);
+    }
+    fn flush(&mut self) {}
+}
+
+#[test]
+fn workspace_comes_back_in_the_next_session() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("k*x^2".to_owned(), "p".to_owned()).unwrap());
+    app.set_parameter("k", 3.0);
+    app.set_view_bounds((0.0, 2.0), (-1.0, 1.0));
+    let mut storage = MemoryStorage::default();
+    eframe::App::save(&mut app, &mut storage);
+
+    let restored = PlottingApp::restore(Some(&storage));
+    assert_eq!(restored.to_document(), app.to_document());
+    assert_eq!(restored.functions()[0].eval(2.0), 12.0);
+    assert_eq!(restored.xlim(), (0.0, 2.0));
+}
+
+#[test]
+fn default_workspace_without_a_saved_one() {
+    let default = PlottingApp::default().to_document();
+    assert_eq!(PlottingApp::restore(None).to_document(), default);
+    // an unreadable workspace is ignored
+    let mut storage = MemoryStorage::default();
+    eframe::Storage::set_string(&mut storage, PlottingApp::STORAGE_KEY, "(".to_owned());
+    assert_eq!(PlottingApp::restore(Some(&storage)).to_document(), default);
+}
+
+#[test]
+fn reset_goes_back_to_the_demo_functions() {
+    let mut app = PlottingApp::empty();
+    app.add_function(Function::new("x".to_owned(), "line".to_owned()).unwrap());
+    app.set_parameter("z", 2.0);
+    app.reset();
+    let expressions: Vec<&str> = app
+        .functions()
+        .iter()
+        .map(|function| function.expression.as_str())
+        .collect();
+    assert_eq!(expressions, ["sin(x*a)*b", "cos(x*a)/b"]);
+    assert_eq!(app.parameter("z"), None);
+    assert_eq!(app.parameter("a"), Some(1.0));
+}
@@ -0,0 +1,57 @@
+use plotting_rs::elements::Region;
+use plotting_rs::error::PlotError;
+use plotting_rs::evaluation::Function;
+use plotting_rs::plotting::PlottingApp;
+use tiny_skia::Pixmap;
+
+fn workspace() -> PlottingApp {
+    let mut app = PlottingApp::empty();
+    let mut f1 = Function::new("x/2".to_owned(), "f1".to_owned()).unwrap();
+    f1.style.color = Some([200, 30, 30]);
+    f1.style.width = 3.0;
+    app.add_function(f1);
+    app.add_region(Region::new("y < -2".to_owned(), "r".to_owned()).unwrap());
+    app.set_view_bounds((-4.0, 4.0), (-3.0, 3.0));
+    app
+}
+
+fn pixel(image: &Pixmap, x: u32, y: u32) -> [u8; 4] {
+    let pixel = image.pixel(x, y).unwrap().demultiply();
+    [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+}
+
+#[test]
+fn workspaces_are_rendered_without_a_window() {
+    let png = workspace().export_png(480, 360).unwrap();
+    let image = Pixmap::decode_png(&png).unwrap();
+    assert_eq!((image.width(), image.height()), (480, 360));
+    // outside of the plot area
+    assert_eq!(pixel(&image, 2, 2), [255, 255, 255, 255]);
+    // the plot area spans 56..468 by 12..324, the origin is in its middle
+    assert_eq!(pixel(&image, 262, 168), [200, 30, 30, 255]);
+    // the region is tinted with its color, the rest of the plot is not
+    let [r, g, b, _] = pixel(&image, 150, 310);
+    assert!(r < 255 && g < 255 && b < 255);
+    assert_eq!(pixel(&image, 150, 30), [255, 255, 255, 255]);
+}
+
+#[test]
+fn labels_and_legend_are_drawn() {
+    let image = Pixmap::decode_png(&workspace().export_png(480, 360).unwrap()).unwrap();
+    let inked = |xs: std::ops::Range<u32>, ys: std::ops::Range<u32>| {
+        ys.flat_map(|y| xs.clone().map(move |x| (x, y)))
+            .any(|(x, y)| pixel(&image, x, y)[0] < 160)
+    };
+    // the label of the tick at x = 0, below the plot area
+    assert!(inked(255..270, 334..350));
+    // the name of the first entry of the legend
+    assert!(inked(400..460, 24..40));
+}
+
+#[test]
+fn empty_images_are_reported() {
+    assert!(matches!(
+        workspace().export_png(0, 100),
+        Err(PlotError::Export(_))
+    ));
+}
@@ -0,0 +1,118 @@
+use std::f64::consts::PI;
+
+use plotting_rs::elements::ParametricCurve;
+use plotting_rs::sampling::{sample, sample_parametric, SamplingOptions};
+
+fn options() -> SamplingOptions {
+    SamplingOptions::for_view(20.0)
+}
+
+#[test]
+fn straight_line_is_not_refined() {
+    let options = options();
+    let segments = sample(|x| 2.0 * x + 1.0, (-10.0, 10.0), &options);
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].len(), options.initial_samples);
+}
+
+#[test]
+fn curved_regions_get_more_points() {
+    let options = options();
+    let segments = sample(|x| (10.0 * x).sin(), (-10.0, 10.0), &options);
+    assert_eq!(segments.len(), 1);
+    assert!(segments[0].len() > options.initial_samples);
+    for pair in segments[0].windows(2) {
+        assert!(pair[0][0] < pair[1][0]);
+    }
+}
+
+#[test]
+fn tan_is_split_at_its_poles() {
+    let segments = sample(f64::tan, (-5.0, 5.0), &options());
+    // poles at ±π/2 and ±3π/2
+    assert_eq!(segments.len(), 5);
+    for segment in &segments {
+        let branch = |x: f64| ((x + PI / 2.0) / PI).floor();
+        let first = branch(segment[0][0]);
+        assert!(segment.iter().all(|[x, _]| branch(*x) == first));
+    }
+}
+
+#[test]
+fn reciprocal_is_split_at_zero() {
+    let segments = sample(|x| 1.0 / x, (-1.0, 1.0), &options());
+    assert_eq!(segments.len(), 2);
+    assert!(segments[0].iter().all(|[x, _]| *x < 0.0));
+    assert!(segments[1].iter().all(|[x, _]| *x > 0.0));
+}
+
+#[test]
+fn undefined_points_split_and_domain_edges_are_located() {
+    let segments = sample(f64::sqrt, (-1.0, 1.0), &options());
+    assert_eq!(segments.len(), 1);
+    let [x, y] = segments[0][0];
+    assert!((0.0..1e-3).contains(&x), "first point at {}", x);
+    assert!(y.is_finite());
+
+    assert!(sample(|_| f64::NAN, (-1.0, 1.0), &options()).is_empty());
+}
+
+#[test]
+fn number_of_points_is_bounded() {
+    let options = SamplingOptions {
+        max_points: 1_000,
+        ..options()
+    };
+    let segments = sample(|x| (1.0 / x).sin(), (-1.0, 1.0), &options);
+    let points: usize = segments.iter().map(|segment| segment.len()).sum();
+    // the curve in progress can still be completed up to the initial samples
+    assert!(points <= options.max_points + options.initial_samples);
+}
+
+#[test]
+fn steep_continuous_curve_is_not_split() {
+    let segments = sample(|x| 5.0 * (1000.0 * x).atan(), (-10.0, 10.0), &options());
+    assert_eq!(segments.len(), 1);
+}
+
+#[test]
+fn parametric_curve_is_sampled_evenly_in_t() {
+    let segments = sample_parametric(|t| [t.cos(), t.sin()], (0.0, 2.0 * PI), 100);
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0].len(), 100);
+    let [first, last] = [segments[0][0], segments[0][99]];
+    assert!((first[0] - last[0]).abs() < 1e-12 && (first[1] - last[1]).abs() < 1e-12);
+}
+
+#[test]
+fn parametric_curve_is_split_where_undefined() {
+    let segments = sample_parametric(|t| [t, t.sqrt()], (-1.0, 1.0), 101);
+    assert_eq!(segments.len(), 1);
+    assert_eq!(segments[0][0], [0.0, 0.0]);
+
+    let mut curve =
+        ParametricCurve::new("t".to_owned(), "a / t".to_owned(), "p".to_owned()).unwrap();
+    curve.t_range = (-1.0, 1.0);
+    curve.samples = 101;
+    curve.assign_value_to_var("a".to_owned(), 2.0).unwrap();
+    assert_eq!(curve.vars_names(), ["a"]);
+    assert_eq!(curve.sample().len(), 2);
+    assert_eq!(curve.eval(0.5), [0.5, 4.0]);
+}
+
+#[test]
+fn steps_of_a_piecewise_function_are_split() {
+    use plotting_rs::compiled::CompiledExpression;
+    use plotting_rs::parser::Expression;
+    use plotting_rs::sampling::sample_piecewise;
+
+    let parsed = Expression::parse("{x < 0: 0, 0.001}").unwrap();
+    let compiled = CompiledExpression::new(&parsed, &[]).unwrap();
+    let eval = |x| compiled.eval(x, &[]);
+    // the step is far below the jump threshold, only the change of branch tells it
+    assert_eq!(sample(eval, (-1.0, 1.0), &options()).len(), 1);
+    let segments = sample_piecewise(eval, |x| compiled.branch(x, &[]), (-1.0, 1.0), &options());
+    assert_eq!(segments.len(), 2);
+    assert!(segments[0].iter().all(|[x, y]| *x < 0.0 && *y == 0.0));
+    assert!(segments[1].iter().all(|[x, y]| *x >= 0.0 && *y == 0.001));
+}
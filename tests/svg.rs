@@ -0,0 +1,97 @@
+use std::path::Path;
+
+use plotting_rs::elements::{ParametricCurve, Region};
+use plotting_rs::evaluation::Function;
+use plotting_rs::plotting::PlottingApp;
+use plotting_rs::scene::{ticks, Item, Scene};
+use plotting_rs::svg::render_svg;
+
+/// Compares `svg` with the golden file `name`, rewritten instead when `UPDATE_GOLDEN` is set.
+fn assert_golden(name: &str, svg: &str) {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, svg).unwrap();
+    }
+    let golden = std::fs::read_to_string(&path).unwrap();
+    assert!(golden == svg, "{} differs from the rendered SVG", name);
+}
+
+fn cartesian() -> PlottingApp {
+    let mut app = PlottingApp::empty();
+    let mut f1 = Function::new("sin(a*x)".to_owned(), "f1".to_owned()).unwrap();
+    f1.style.color = Some([200, 30, 30]);
+    f1.style.width = 2.5;
+    f1.show_derivative = true;
+    app.add_function(f1);
+    app.add_function(Function::new("1/x".to_owned(), "<inverse>".to_owned()).unwrap());
+    let mut region = Region::new("y < x^2 - 2".to_owned(), "r".to_owned()).unwrap();
+    region.resolution = 12;
+    app.add_region(region);
+    app.set_parameter("a", 2.0);
+    app.set_view_bounds((-4.0, 4.0), (-3.0, 3.0));
+    app
+}
+
+#[test]
+fn cartesian_plot_matches_golden_file() {
+    assert_golden("cartesian.svg", &cartesian().export_svg(480, 360));
+}
+
+#[test]
+fn polar_plot_matches_golden_file() {
+    let mut app = PlottingApp::empty();
+    let mut curve =
+        ParametricCurve::new("cos(3*t)".to_owned(), "sin(2*t)".to_owned(), "c".to_owned()).unwrap();
+    curve.samples = 60;
+    app.add_parametric_curve(curve);
+    app.set_polar_grid(true);
+    app.set_view_bounds((-1.5, 1.5), (-1.5, 1.5));
+    assert_golden("polar.svg", &app.export_svg(300, 300));
+}
+
+#[test]
+fn scenes_follow_the_styles_of_the_app() {
+    let scene = Scene::new(&cartesian());
+    let legend: Vec<&str> = scene.legend().into_iter().map(|(name, _)| name).collect();
+    assert_eq!(legend, ["f1", "f1'", "<inverse>", "r"]);
+    let Item::Curve(f1) = &scene.items[0] else {
+        panic!("functions are curves");
+    };
+    assert_eq!(f1.color.to_array(), [200, 30, 30, 255]);
+    assert_eq!(f1.width, 2.5);
+    // the points stay within the view
+    assert!(f1
+        .segments
+        .iter()
+        .flatten()
+        .all(|[x, _]| (-4.0..=4.0).contains(x)));
+    let svg = render_svg(&scene, 480, 360);
+    assert!(svg.contains("&lt;inverse&gt;"));
+    assert!(svg.contains(r##"stroke="#c81e1e""##));
+}
+
+#[test]
+fn ticks_are_round_values() {
+    assert_eq!(
+        ticks((-4.0, 4.0), 8),
+        [-4.0, -3.0, -2.0, -1.0, 0.0, 1.0, 2.0, 3.0, 4.0]
+    );
+    assert_eq!(ticks((0.05, 0.31), 5), [0.1, 0.2, 0.3]);
+    assert_eq!(
+        ticks((-1000.0, 1000.0), 4),
+        [-1000.0, -500.0, 0.0, 500.0, 1000.0]
+    );
+    assert!(ticks((1.0, 1.0), 8).is_empty());
+}
+
+#[test]
+fn tick_labels_follow_the_step() {
+    let mut app = PlottingApp::empty();
+    app.set_view_bounds((0.0, 4e-7), (-1000.0, 1000.0));
+    let svg = app.export_svg(480, 360);
+    for label in ["0", "0.00000005", "0.0000002", "-1000", "500"] {
+        assert!(svg.contains(&format!(">{}</text>", label)), "{}", label);
+    }
+}
@@ -0,0 +1,61 @@
+use plotting_rs::error::PlotError;
+use plotting_rs::evaluation::Function;
+use plotting_rs::plotting::PlottingApp;
+use plotting_rs::sweep::{ParameterSweep, SweepValues};
+
+#[test]
+fn ranges_include_both_ends() {
+    let range = |from, to, steps| SweepValues::Range { from, to, steps }.values().unwrap();
+    assert_eq!(range(0.0, 1.0, 5), [0.0, 0.25, 0.5, 0.75, 1.0]);
+    assert_eq!(range(2.0, -2.0, 3), [2.0, 0.0, -2.0]);
+    assert_eq!(range(2.0, 5.0, 1), [2.0]);
+    assert!(range(2.0, 5.0, 0).is_empty());
+}
+
+#[test]
+fn lists_are_parsed_while_typing() {
+    let list = |text: &str| SweepValues::List(text.to_owned()).values();
+    assert_eq!(list("0.5, 1,2"), Ok(vec![0.5, 1.0, 2.0]));
+    assert_eq!(list("1, -3e-1, "), Ok(vec![1.0, -0.3]));
+    assert_eq!(list(""), Ok(vec![]));
+    assert!(matches!(list("1, a"), Err(PlotError::Parse { .. })));
+}
+
+#[test]
+fn family_has_one_function_per_value() {
+    let function = Function::new("a*x + b".to_owned(), "f1".to_owned()).unwrap();
+    let sweep = ParameterSweep::new("a".to_owned(), SweepValues::List("1, 2, 3".to_owned()));
+    let family = sweep.family(&function).unwrap();
+    let values: Vec<(f64, f64)> = family
+        .iter()
+        .map(|(value, member)| (*value, member.eval(2.0)))
+        .collect();
+    // `b` keeps its value
+    assert_eq!(values, [(1.0, 3.0), (2.0, 5.0), (3.0, 7.0)]);
+    // the function itself is left as is
+    assert_eq!(function.eval(2.0), 3.0);
+
+    let sweep = ParameterSweep::new("c".to_owned(), SweepValues::List("1".to_owned()));
+    assert_eq!(
+        sweep.family(&function).err(),
+        Some(PlotError::UnboundVariable("c".to_owned()))
+    );
+}
+
+#[test]
+fn sweeps_follow_parameter_renaming() {
+    let mut app = PlottingApp::empty();
+    let mut function = Function::new("a*x".to_owned(), "f1".to_owned()).unwrap();
+    let values = SweepValues::Range {
+        from: 0.0,
+        to: 1.0,
+        steps: 2,
+    };
+    function.sweep = Some(ParameterSweep::new("a".to_owned(), values));
+    app.add_function(function);
+    app.rename_parameter("a", "k").unwrap();
+    let function = &app.functions()[0];
+    let sweep = function.sweep.as_ref().unwrap();
+    assert_eq!(sweep.parameter, "k");
+    assert_eq!(sweep.family(function).unwrap().len(), 2);
+}
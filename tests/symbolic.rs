@@ -0,0 +1,129 @@
+use plotting_rs::compiled::CompiledExpression;
+use plotting_rs::evaluation::Function;
+use plotting_rs::parser::Expression;
+
+fn simplify(text: &str) -> String {
+    Expression::parse(text).unwrap().simplify().to_string()
+}
+
+fn derivative(text: &str) -> String {
+    let parsed = Expression::parse(text).unwrap();
+    parsed.derivative("x").unwrap().simplify().to_string()
+}
+
+fn eval(expression: &Expression, x: f64) -> f64 {
+    let names = ["a".to_owned(), "b".to_owned()];
+    CompiledExpression::new(expression, &names)
+        .unwrap()
+        .eval(x, &[1.5, -0.75])
+}
+
+#[test]
+fn folds_constants_and_removes_identities() {
+    assert_eq!(simplify("2 + 3 * 4"), "14");
+    assert_eq!(simplify("x * 1 + 0"), "x");
+    assert_eq!(simplify("0 * sin(x) + a"), "a");
+    assert_eq!(simplify("x^1 * 1^x"), "x");
+    assert_eq!(simplify("x^0"), "1");
+    assert_eq!(simplify("4^0.5 + sin(0)"), "2");
+    assert_eq!(simplify("2^-2"), "1 / 4");
+    // only exact results are folded
+    assert_eq!(simplify("2^0.5 + ln(2)"), "ln(2) + 2^0.5");
+    assert_eq!(simplify("6 / 4 * x"), "3 * x / 2");
+    assert_eq!(simplify("if(2 > 1, x, 0) + (3 == 3)"), "x + 1");
+    assert_eq!(simplify("ln(e) + cos(pi)"), "0");
+    // a result that overflows is left unfolded
+    let overflow = Expression::parse("1e308 * 10").unwrap().simplify();
+    assert_eq!(overflow, Expression::parse("1e308 * 10").unwrap());
+    assert!(Expression::parse("(1e308 + 1e308) * x")
+        .unwrap()
+        .simplify()
+        .depends_on("x"));
+}
+
+#[test]
+fn collects_like_terms_and_factors() {
+    assert_eq!(simplify("x * x + 2 * x^2"), "3 * x^2");
+    assert_eq!(simplify("a - a"), "0");
+    assert_eq!(simplify("x - 2 * x"), "-x");
+    assert_eq!(simplify("2 * (x + 1) - 2 * x"), "2");
+    assert_eq!(simplify("x / x"), "1");
+    assert_eq!(simplify("a * x / (x * b)"), "a / b");
+    assert_eq!(simplify("1 + x + x^2 + x + 1"), "x^2 + 2 * x + 2");
+}
+
+#[test]
+fn applies_power_rules() {
+    assert_eq!(simplify("x^2 * x^3"), "x^5");
+    assert_eq!(simplify("x^a * x^b"), "x^(a + b)");
+    assert_eq!(simplify("(x^2)^3"), "x^6");
+    assert_eq!(simplify("(2 * x)^2"), "4 * x^2");
+    assert_eq!(simplify("1 / x^2 * x"), "1 / x");
+    assert_eq!(simplify("(x + 1) * (x + 1)"), "(x + 1)^2");
+    // `(x^2)^0.5` is `abs(x)`, exponents are only multiplied by integers
+    assert_eq!(simplify("(x^2)^0.5"), "(x^2)^0.5");
+}
+
+#[test]
+fn prints_derivatives_in_canonical_form() {
+    assert_eq!(derivative("x^3"), "3 * x^2");
+    assert_eq!(derivative("sin(x*a)*b"), "a * b * cos(a * x)");
+    assert_eq!(derivative("cos(x*a)/b"), "-a * sin(a * x) / b");
+    assert_eq!(derivative("x^x"), "(ln(x) + 1) * x^x");
+    assert_eq!(derivative("x^2 + 3*x + 1"), "2 * x + 3");
+    assert_eq!(derivative("exp(-x^2/2)"), "-exp(-x^2 / 2) * x");
+    assert_eq!(derivative("a"), "0");
+    assert_eq!(derivative("{x < 0: -x, x^2}"), "{x < 0: -1, 2 * x}");
+    assert_eq!(derivative("if(x > a, x^3, 2 * x)"), "if(x > a, 3 * x^2, 2)");
+    assert_eq!(derivative("(x > 0) * x"), "x > 0");
+    assert_eq!(derivative("e^x"), "e^x");
+    // like terms are collected once their common factor is taken out
+    assert_eq!(derivative("x*(x+1)*(x+1)"), "(3 * x + 1) * (x + 1)");
+    assert_eq!(derivative("x*(x+1)^3"), "(4 * x + 1) * (x + 1)^2");
+}
+
+#[test]
+fn keeps_the_value_of_the_expression() {
+    let expressions = [
+        "sin(x*a)*b + x*x - 3*(x - a)",
+        "(x + 1)^2 / (x + 1) - a^2 * x / a",
+        "(x + a)^2 * b + (x + a) * x - (x + a)^3",
+        "2^x * 2^-x + x % 2",
+        "exp(-x^2/2)/sqrt(2*pi) * b",
+        "atan2(x, 2) - (a*x)^3 / x",
+        "{x < a: -x + x, x >= a && !(x > 2): x^2, 1} + if(x * 0 < 1, b, 0)",
+    ];
+    for text in expressions {
+        let parsed = Expression::parse(text).unwrap();
+        let simplified = parsed.simplify();
+        for x in [-2.5, -0.5, 0.7, 3.0] {
+            let (expected, actual) = (eval(&parsed, x), eval(&simplified, x));
+            assert!(
+                (expected - actual).abs() <= 1e-9 * expected.abs().max(1.0),
+                "{} -> {} at {}: {} != {}",
+                text,
+                simplified,
+                x,
+                expected,
+                actual
+            );
+        }
+        // the printed form is already canonical
+        assert_eq!(simplify(&simplified.to_string()), simplified.to_string());
+    }
+}
+
+#[test]
+fn functions_keep_their_simplified_expression() {
+    let mut function = Function::new("x + x".to_owned(), "f".to_owned()).unwrap();
+    assert_eq!(function.simplified(), Some(simplify("x + x").as_str()));
+    function.expression = "x*1".to_owned();
+    function.reparse().unwrap();
+    assert_eq!(function.simplified(), Some("x"));
+    // the last valid one stays while the text does not parse
+    function.expression = "x*".to_owned();
+    assert!(function.reparse().is_err());
+    assert_eq!(function.simplified(), Some("x"));
+    let unparsed = Function::unparsed("x*".to_owned(), "g".to_owned());
+    assert_eq!(unparsed.simplified(), None);
+}